@@ -0,0 +1,39 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Archive: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Archive without manifest")]
+    NoManifest,
+
+    #[error("Manifest: {0}")]
+    Manifest(#[from] lipl_core::reexport::serde_yaml::Error),
+
+    #[error("Archive version {0} is not supported")]
+    Version(u32),
+
+    #[error("Checksum of {0} does not match the manifest")]
+    Checksum(String),
+
+    #[error("File {0} is in the manifest but not in the archive")]
+    Missing(String),
+
+    #[error("File {0} is in the archive but not in the manifest")]
+    Unlisted(String),
+
+    #[error("File {0} is not a lyric, playlist or attachment")]
+    Unexpected(String),
+
+    #[error("Playlist {0} has member {1} that is not in the archive")]
+    Member(String, String),
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Repo: {0}")]
+    Repo(#[from] lipl_core::Error),
+}
+
+lipl_core::import_error!(Error);
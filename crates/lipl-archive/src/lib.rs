@@ -0,0 +1,254 @@
+/*!
+ Portable `.lipl` archives of a whole library.
+
+ A `.lipl` file is a zip archive with
+ - `manifest.yaml`, the archive version and the SHA-256 checksum of every other file
+ - `lyrics/<id>.md`, every lyric in the Markdown disk format of the file repository
+ - `playlists/<id>.yaml`, every playlist in the YAML disk format of the file repository
+ - `attachments/<name>`, optional files like sheet music or recordings
+
+ Reading checks the version, the checksums, that the manifest and the files agree
+ and that every member of a playlist is in the archive, before anything is imported.
+ Writing is deterministic, the same library gives the same archive byte for byte.
+ */
+
+use std::collections::BTreeMap;
+use std::io::{Read, Seek, Write};
+use lipl_core::{LiplRepo, Lyric, LyricPost, Playlist, PlaylistPost, RepoDb, Uuid};
+use lipl_core::reexport::serde_yaml;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+pub use error::Error;
+
+mod error;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub const EXTENSION: &str = "lipl";
+/// Version of the archive layout written, archives of a newer version are refused
+pub const VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.yaml";
+const LYRICS: &str = "lyrics/";
+const PLAYLISTS: &str = "playlists/";
+const ATTACHMENTS: &str = "attachments/";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Manifest {
+    pub version: u32,
+    /// SHA-256 checksum in hex by path in the archive
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Archive {
+    pub lyrics: Vec<Lyric>,
+    pub playlists: Vec<Playlist>,
+    /// Contents by name, without the `attachments/` directory
+    pub attachments: BTreeMap<String, Vec<u8>>,
+}
+
+fn checksum(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn id(path: &str, directory: &str, extension: &str) -> Result<Uuid> {
+    path
+        .strip_prefix(directory)
+        .and_then(|name| name.strip_suffix(extension))
+        .and_then(|stem| stem.parse::<Uuid>().ok())
+        .ok_or_else(|| Error::Unexpected(path.to_owned()))
+}
+
+impl From<RepoDb> for Archive {
+    fn from(db: RepoDb) -> Self {
+        Self { lyrics: db.lyrics, playlists: db.playlists, attachments: BTreeMap::new() }
+    }
+}
+
+impl Archive {
+    pub async fn from_repo<R: LiplRepo + ?Sized>(repo: &R) -> Result<Self> {
+        Ok(RepoDb::from_repo(repo).await?.into())
+    }
+
+    pub fn attach(mut self, name: &str, contents: Vec<u8>) -> Self {
+        self.attachments.insert(name.to_owned(), contents);
+        self
+    }
+
+    /// Files of the archive by path, without the manifest
+    fn files(&self) -> BTreeMap<String, Vec<u8>> {
+        let lyrics = self.lyrics.iter().map(|lyric| (format!("{LYRICS}{}.md", lyric.id), lyric.to_string().into_bytes()));
+        let playlists = self.playlists.iter().map(|playlist| (format!("{PLAYLISTS}{}.yaml", playlist.id), playlist.to_string().into_bytes()));
+        let attachments = self.attachments.iter().map(|(name, contents)| (format!("{ATTACHMENTS}{name}"), contents.clone()));
+        lyrics.chain(playlists).chain(attachments).collect()
+    }
+
+    pub fn write<W: Write + Seek>(&self, w: W) -> Result<()> {
+        let files = self.files();
+        let manifest = Manifest {
+            version: VERSION,
+            files: files.iter().map(|(path, contents)| (path.clone(), checksum(contents))).collect(),
+        };
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(w);
+        zip.start_file(MANIFEST, options)?;
+        zip.write_all(serde_yaml::to_string(&manifest)?.as_bytes())?;
+        for (path, contents) in files {
+            zip.start_file(path, options)?;
+            zip.write_all(&contents)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Reads an archive and checks its integrity
+    pub fn read<R: Read + Seek>(r: R) -> Result<Self> {
+        let mut zip = ZipArchive::new(r)?;
+        let mut files = BTreeMap::new();
+        for index in 0..zip.len() {
+            let mut file = zip.by_index(index)?;
+            if file.is_dir() {
+                continue;
+            }
+            let mut contents = vec![];
+            file.read_to_end(&mut contents)?;
+            files.insert(file.name().to_owned(), contents);
+        }
+
+        let manifest: Manifest = serde_yaml::from_slice(&files.remove(MANIFEST).ok_or(Error::NoManifest)?)?;
+        if manifest.version > VERSION {
+            return Err(Error::Version(manifest.version));
+        }
+        if let Some(path) = files.keys().find(|path| !manifest.files.contains_key(*path)) {
+            return Err(Error::Unlisted(path.clone()));
+        }
+        for (path, expected) in manifest.files.iter() {
+            let contents = files.get(path).ok_or_else(|| Error::Missing(path.clone()))?;
+            if checksum(contents) != *expected {
+                return Err(Error::Checksum(path.clone()));
+            }
+        }
+
+        let mut archive = Archive::default();
+        for (path, contents) in files {
+            let text = || String::from_utf8_lossy(&contents).to_string();
+            if path.starts_with(LYRICS) {
+                let lyric_post = text().parse::<LyricPost>()?;
+                archive.lyrics.push((Some(id(&path, LYRICS, ".md")?), lyric_post).into());
+            }
+            else if path.starts_with(PLAYLISTS) {
+                let playlist_post = text().parse::<PlaylistPost>()?;
+                archive.playlists.push((Some(id(&path, PLAYLISTS, ".yaml")?), playlist_post).into());
+            }
+            else if let Some(name) = path.strip_prefix(ATTACHMENTS) {
+                archive.attachments.insert(name.to_owned(), contents);
+            }
+            else {
+                return Err(Error::Unexpected(path));
+            }
+        }
+
+        for playlist in archive.playlists.iter() {
+            if let Some(member) = playlist.members.iter().find(|member| !archive.lyrics.iter().any(|lyric| lyric.id == **member)) {
+                return Err(Error::Member(playlist.title.clone(), member.to_string()));
+            }
+        }
+        Ok(archive)
+    }
+
+    /// Upserts the lyrics and then the playlists, attachments are not stored in a repository
+    pub async fn import<R: LiplRepo + ?Sized>(&self, repo: &R) -> Result<()> {
+        for lyric in self.lyrics.iter() {
+            repo.upsert_lyric(lyric.clone()).await?;
+        }
+        for playlist in self.playlists.iter() {
+            repo.upsert_playlist(playlist.clone()).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+    use lipl_core::{LiplRepo, Lyric, Playlist, RepoDb, Uuid};
+    use lipl_repo_memory::MemoryRepo;
+    use zip::write::FileOptions;
+    use zip::{ZipArchive, ZipWriter};
+    use super::{Archive, Error};
+
+    fn archive() -> Archive {
+        let lyric = Lyric {
+            id: Uuid::default(),
+            title: "Roodkapje".to_owned(),
+            parts: vec![vec!["Zeg roodkapje".to_owned(), "waar ga je hene".to_owned()], vec!["Refrein".to_owned()]],
+            extensions: Default::default(),
+            times: None,
+        };
+        let playlist = Playlist { id: Uuid::default(), title: "Kinderliedjes".to_owned(), members: vec![lyric.id] };
+        Archive::from(RepoDb { lyrics: vec![lyric], playlists: vec![playlist] }).attach("roodkapje.pdf", b"%PDF".to_vec())
+    }
+
+    fn bytes(archive: &Archive) -> Vec<u8> {
+        let mut buffer = Cursor::new(vec![]);
+        archive.write(&mut buffer).unwrap();
+        buffer.into_inner()
+    }
+
+    /// Copy of an archive with one file replaced
+    fn replace(archive: &[u8], path: &str, contents: &[u8]) -> Vec<u8> {
+        let mut source = ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        for index in 0..source.len() {
+            let file = source.by_index(index).unwrap();
+            if file.name() == path {
+                zip.start_file(path, FileOptions::default()).unwrap();
+                zip.write_all(contents).unwrap();
+            }
+            else {
+                zip.raw_copy_file(file).unwrap();
+            }
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[tokio::test]
+    async fn export_and_import() {
+        let archive = archive();
+        let written = bytes(&archive);
+        assert_eq!(written, bytes(&archive));
+
+        let read = Archive::read(Cursor::new(written)).unwrap();
+        assert_eq!(read.attachments["roodkapje.pdf"], b"%PDF");
+
+        let repo = MemoryRepo::default();
+        read.import(&repo).await.unwrap();
+        let lyric = repo.get_lyric(archive.lyrics[0].id).await.unwrap();
+        assert_eq!(lyric.parts, archive.lyrics[0].parts);
+        assert_eq!(repo.get_playlist(archive.playlists[0].id).await.unwrap().members, vec![lyric.id]);
+
+        let exported = Archive::from_repo(&repo).await.unwrap();
+        assert_eq!(exported.lyrics[0].title, "Roodkapje");
+    }
+
+    #[test]
+    fn integrity_checks() {
+        let archive = archive();
+        let written = bytes(&archive);
+        let lyric = format!("lyrics/{}.md", archive.lyrics[0].id);
+
+        let changed = replace(&written, &lyric, b"---\ntitle: Sneeuwwitje\n---\n");
+        assert!(matches!(Archive::read(Cursor::new(changed)), Err(Error::Checksum(path)) if path == lyric));
+
+        let newer = replace(&written, "manifest.yaml", b"version: 2\nfiles: {}\n");
+        assert!(matches!(Archive::read(Cursor::new(newer)), Err(Error::Version(2))));
+
+        let mut orphan = archive.clone();
+        orphan.lyrics.clear();
+        assert!(matches!(Archive::read(Cursor::new(bytes(&orphan))), Err(Error::Member(..))));
+    }
+}
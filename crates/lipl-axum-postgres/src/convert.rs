@@ -15,6 +15,8 @@ pub fn to_lyric(row: Row) -> Result<Lyric> {
         id: row.try_get::<&str, reexport::uuid::Uuid>(column::ID)?.into(),
         title: row.try_get::<&str, String>(column::TITLE)?,
         parts: parts::to_parts(row.try_get::<&str, String>(column::PARTS)?),
+        extensions: Default::default(),
+        times: None,
     })
 }
 
@@ -48,6 +48,7 @@ impl LiplRepo for PostgresConnectionPool {
     }
 
     async fn upsert_lyric(&self, lyric: Lyric) -> Result<Lyric> {
+        lyric.untimed()?;
         self.query_one(
             lyric::UPSERT,
             lyric::UPSERT_TYPES,
@@ -1,71 +1,54 @@
 use core::str::{FromStr};
 use core::fmt::{Display, Formatter};
-use core::str::Lines;
-use core::iter::once;
 
-use lipl_util::VecExt;
-use crate::{Etag, Lyric, LyricMeta, LyricPost, PlaylistPost, Playlist};
+use parts::{Frontmatter, Markdown};
+use crate::{Lyric, LyricMeta, LyricPost, PlaylistPost, Playlist};
 use crate::error::{Error};
 
-const YAML_PREFIX: &str = "---";
-
-fn lines_to_lyric_post(acc: LyricPost, mut lines: Lines) -> Result<LyricPost, serde_yaml::Error>
-{
-    let next = 
-        lines
-        .by_ref()
-        .skip_while(
-            |l| l.trim().is_empty()
-        )
-        .take_while(
-            |l| !l.trim().is_empty()
-        )
-        .map(|s| s.trim())
-        .map(String::from)
-        .collect::<Vec<_>>();
-
-    if next.is_empty() {
-        Ok(acc)
-    }
-    else if next.first().map(|s| s.trim()) == Some(YAML_PREFIX) {
-        let new = next.without(&YAML_PREFIX.to_owned());
-        let meta: LyricMeta = serde_yaml::from_str(&new.join("\n"))?;
-        lines_to_lyric_post(
-            LyricPost {
-                title: meta.title,
-                parts: acc.parts,
-            },
-            lines
-        )
-    }
-    else {
-        lines_to_lyric_post(
-            LyricPost {
-                title: acc.title,
-                parts: acc.parts.into_iter().chain(once(next)).collect::<Vec<_>>(),
-            },
-            lines
-        )
+fn parse_meta(style: Frontmatter, s: &str) -> Result<LyricMeta, Error> {
+    match style {
+        Frontmatter::Yaml => serde_yaml::from_str(s).map_err(Into::into),
+        Frontmatter::Toml => toml::from_str(s).map_err(|e| Error::Toml(Box::new(e))),
+        Frontmatter::Json => serde_json::from_str(s).map_err(|e| Error::Json(Box::new(e))),
     }
 }
 
+fn format_meta(style: Frontmatter, meta: &LyricMeta) -> Result<String, Error> {
+    match style {
+        Frontmatter::Yaml => serde_yaml::to_string(meta).map_err(Into::into),
+        Frontmatter::Toml => {
+            crate::check_toml(&meta.title, &meta.extensions)?;
+            toml::to_string(meta).map_err(|e| Error::Toml(Box::new(e)))
+        },
+        Frontmatter::Json => serde_json::to_string_pretty(meta).map_err(|e| Error::Json(Box::new(e))),
+    }
+}
+
+/// Lyric post from Markdown with YAML, TOML or JSON frontmatter
 impl FromStr for LyricPost {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lyric_post = lines_to_lyric_post(Default::default(), s.lines())?;
-        Ok(lyric_post)
+        let markdown = Markdown::from(s.to_owned());
+        let (title, extensions, times) = match markdown.frontmatter {
+            Some(frontmatter) => parse_meta(markdown.style, &frontmatter).map(|meta| (meta.title, meta.extensions, meta.times))?,
+            None => Default::default(),
+        };
+        Ok(LyricPost { title, parts: markdown.parts, extensions, times })
+    }
+}
+
+impl Lyric {
+    /// Markdown with frontmatter in the given style, [`Display`] gives YAML frontmatter
+    pub fn to_markdown(&self, style: Frontmatter) -> Result<String, Error> {
+        let lyric_meta = LyricMeta::from(self);
+        let parts_string: String = self.parts.iter().map(|p| p.join("  \n")).collect::<Vec<_>>().join("\n\n");
+        Ok(format!("{}\n{parts_string}", style.wrap(&format_meta(style, &lyric_meta)?)))
     }
 }
 
 impl Display for Lyric {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        let lyric_meta = LyricMeta {
-            title: self.title.clone(),
-            hash: self.etag(),
-        };
-        let yaml = serde_yaml::to_string(&lyric_meta).unwrap();
-        let parts_string: String = self.parts.iter().map(|p| p.join("  \n")).collect::<Vec<_>>().join("\n\n");
-        write!(f, "{YAML_PREFIX}\n{yaml}{YAML_PREFIX}\n\n{parts_string}")
+        write!(f, "{}", self.to_markdown(Frontmatter::Yaml).map_err(|_| core::fmt::Error)?)
     }
 }
 
@@ -85,28 +68,13 @@ impl Display for Playlist {
     }
 }
 
-fn empty_line(s: &&str) -> bool {
-    s.trim().is_empty()
-}
-
-fn non_empty_line(s: &&str) -> bool {
-    !empty_line(s)
-}
-
 impl FromStr for LyricMeta {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let yaml = 
-            s
-            .lines()
-            .skip_while(empty_line)
-            .take_while(non_empty_line)
-            .map(String::from)
-            .filter(|s| s != YAML_PREFIX)
-            .collect::<Vec<_>>()
-            .join("\n");
-
-        serde_yaml::from_str(&yaml).map_err(Into::into)
+        match parts::frontmatter::split(s).0 {
+            Some((style, frontmatter)) => parse_meta(style, frontmatter),
+            None => parse_meta(Frontmatter::Yaml, ""),
+        }
     }
 }
 
@@ -204,7 +172,9 @@ mod tests {
                     "En op Sint Jan geklommen".to_owned(),
                     "Daar staat hij dag en nacht".to_owned(),
                 ]
-            ]
+            ],
+            extensions: Default::default(),
+            times: None,
         }
     }
 
@@ -243,10 +213,96 @@ mod tests {
         assert_eq!(lyric.to_string().as_str(), hertog_jan_lyric().to_string().as_str());
     }
 
+    #[test]
+    fn lyric_post_parse_keeps_formatting() {
+        let lyric = Lyric {
+            id: Uuid::default(),
+            title: "Kyrie".to_owned(),
+            parts: vec![vec![
+                "Heer, ontferm U".to_owned(),
+                "    *All:* Heer, **ontferm** U".to_owned(),
+                "\t*over ons*".to_owned(),
+            ]],
+            extensions: Default::default(),
+            times: None,
+        };
+        let lyric_post: LyricPost = lyric.to_string().parse().unwrap();
+        assert_eq!(lyric_post.parts, lyric.parts);
+    }
+
+    #[test]
+    fn lyric_post_parse_keeps_extensions() {
+        let text = "---\ntitle: Kyrie\nnotes: Langzaam\ntags:\n- mis\n- latijn\nsource: https://example.org/kyrie\n---\n\nKyrie eleison\n";
+        let lyric = Lyric::from((Some(Uuid::default()), text.parse::<LyricPost>().unwrap()));
+        assert_eq!(lyric.extensions.keys().collect::<Vec<_>>(), vec!["notes", "source", "tags"]);
+        assert!(!lyric.extensions.contains_key("hash"));
+
+        let written = lyric.to_string();
+        assert!(written.contains("notes: Langzaam\nsource: https://example.org/kyrie\ntags:\n- mis\n- latijn\n"));
+        let lyric_post: LyricPost = written.parse().unwrap();
+        assert_eq!(lyric_post.extensions, lyric.extensions);
+    }
+
+    #[test]
+    fn lyric_post_drops_reserved_extensions() {
+        let lyric_post = serde_json::from_str::<LyricPost>(r#"{"title":"A","parts":[],"extensions":{"title":"B","hash":"v1:00","times":[],"notes":"C"}}"#).unwrap();
+        assert_eq!(lyric_post.extensions.keys().collect::<Vec<_>>(), vec!["notes"]);
+
+        let mut lyric = Lyric::from((Some(Uuid::default()), lyric_post));
+        lyric.extensions.insert("title".to_owned(), "B".into());
+        let written = lyric.to_string();
+        assert_eq!(written.matches("title:").count(), 1);
+        let lyric_meta: LyricMeta = written.parse().unwrap();
+        assert_eq!(lyric_meta.title, "A".to_owned());
+        assert_eq!(lyric_meta.hash, Some(crate::hash::hash(&Lyric::from((Some(lyric.id), written.parse::<LyricPost>().unwrap())))));
+    }
+
+    #[test]
+    fn lyric_post_parse_keeps_times() {
+        let lrc = parts::lrc::parse("[ti:Kyrie]\n[00:01.50]Kyrie eleison\n[00:04.00]Christe eleison\n\n[00:08.25]Kyrie eleison\n");
+        let lyric = Lyric::from((Some(Uuid::default()), LyricPost::from(lrc)));
+        assert_eq!(lyric.parts, vec![vec!["Kyrie eleison", "Christe eleison"], vec!["Kyrie eleison"]]);
+
+        let written = lyric.to_string();
+        assert!(written.contains("times:\n- - 00:01.50\n  - 00:04.00\n- - 00:08.25\n"));
+        let lyric_post: LyricPost = written.parse().unwrap();
+        assert_eq!(lyric_post.parts, lyric.parts);
+        assert_eq!(lyric_post.times, lyric.times);
+        assert_eq!(lyric.timed()[1][0].time, Some(std::time::Duration::from_millis(8_250)));
+        assert!(matches!(lyric.untimed(), Err(crate::Error::TimesNotKept(id)) if id == lyric.id));
+        assert!(hertog_jan_lyric().untimed().is_ok());
+    }
+
+    #[test]
+    fn lyric_post_parse_toml_and_json() {
+        use parts::Frontmatter;
+        let mut lyric = hertog_jan_lyric();
+        lyric.extensions.insert("notes".to_owned(), "Brabants".into());
+        lyric.times = Some(vec![vec!["00:10.50".parse().unwrap(), crate::LineTime(None)]]);
+        for style in [Frontmatter::Toml, Frontmatter::Json] {
+            let markdown = lyric.to_markdown(style).unwrap();
+            assert_eq!(Frontmatter::of(&markdown), Some(style));
+            let lyric_post: LyricPost = markdown.parse().unwrap();
+            assert_eq!(lyric_post.title, HERTOG_JAN_TITLE.to_owned());
+            assert_eq!(lyric_post.parts, lyric.parts);
+            assert_eq!(lyric_post.extensions, lyric.extensions);
+            assert_eq!(lyric_post.times, lyric.times);
+            let lyric_meta: LyricMeta = markdown.parse().unwrap();
+            assert_eq!(lyric_meta.hash, Some(crate::hash::hash(&lyric)));
+        }
+        assert!(matches!("+++\ntitle = \n+++\n\nRegel\n".parse::<LyricPost>(), Err(crate::Error::Toml(_))));
+
+        lyric.extensions.insert("capo".to_owned(), serde_yaml::Value::Null);
+        let error = lyric.to_markdown(Frontmatter::Toml).unwrap_err();
+        assert!(matches!(&error, crate::Error::Toml(_)));
+        assert!(error.to_string().contains("extension capo of Hertog Jan"));
+    }
+
     #[test]
     fn lyric_meta_parse() {
         let lyric_meta: LyricMeta = hertog_jan_lyric().to_string().parse().unwrap();
         assert_eq!(lyric_meta.title, HERTOG_JAN_TITLE.to_owned());
-        assert_eq!(lyric_meta.hash, Some("\"2530-189459479300553739784561073837696755448\"".to_owned()));
+        assert_eq!(lyric_meta.hash, Some(crate::hash::hash(&hertog_jan_lyric())));
+        assert!(lyric_meta.hash.unwrap().starts_with("v1:"));
     }
 }
\ No newline at end of file
@@ -0,0 +1,258 @@
+/*!
+ Dumps of a whole library in several formats.
+
+ YAML, JSON, NDJSON and TOML are always available, RON with feature `ron`.
+ [`Dump`] loads and saves in any of them, [`Yaml`](crate::Yaml) is the YAML dump.
+
+ NDJSON has one entity per line, like `{"lyric": {...}}` or `{"playlist": {...}}`, lyrics first.
+ It is the format for huge libraries: [`stream_out`] writes a repository one lyric at a time
+ and [`stream_in`] upserts one line at a time, neither holds the library in memory.
+ */
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::{Error, Lyric, Playlist, RepoDb, Result};
+use crate::LiplRepo;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DumpFormat {
+    Yaml,
+    Json,
+    Ndjson,
+    Toml,
+    #[cfg(feature = "ron")]
+    Ron,
+}
+
+/// Line of an NDJSON dump
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Entity {
+    Lyric(Lyric),
+    Playlist(Playlist),
+}
+
+pub trait Dump {
+    fn load<R>(format: DumpFormat, r: R) -> Result<Self> where R: Read, Self: Sized;
+    fn save<W>(&self, format: DumpFormat, w: W) -> Result<()> where W: Write;
+}
+
+fn json_error(error: serde_json::Error) -> Error {
+    Error::Json(Box::new(error))
+}
+
+fn dump_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> Error {
+    Error::Dump(Box::new(error))
+}
+
+impl DumpFormat {
+    pub const ALL: &'static [DumpFormat] = &[
+        DumpFormat::Yaml,
+        DumpFormat::Json,
+        DumpFormat::Ndjson,
+        DumpFormat::Toml,
+        #[cfg(feature = "ron")]
+        DumpFormat::Ron,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DumpFormat::Yaml => "yaml",
+            DumpFormat::Json => "json",
+            DumpFormat::Ndjson => "ndjson",
+            DumpFormat::Toml => "toml",
+            #[cfg(feature = "ron")]
+            DumpFormat::Ron => "ron",
+        }
+    }
+
+    /// Format of a file by its extension, `yml` and `jsonl` are accepted as well
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref().extension().and_then(|extension| extension.to_str()).and_then(|extension| extension.parse().ok())
+    }
+}
+
+impl Display for DumpFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.extension())
+    }
+}
+
+impl FromStr for DumpFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "yml" => Ok(DumpFormat::Yaml),
+            "jsonl" => Ok(DumpFormat::Ndjson),
+            name =>
+                DumpFormat::ALL
+                .iter()
+                .find(|format| format.extension() == name)
+                .copied()
+                .ok_or(Error::Argument("unknown dump format")),
+        }
+    }
+}
+
+impl Dump for RepoDb {
+    fn load<R>(format: DumpFormat, r: R) -> Result<Self> where R: Read {
+        match format {
+            DumpFormat::Yaml => serde_yaml::from_reader(r).map_err(Into::into),
+            DumpFormat::Json => serde_json::from_reader(r).map_err(json_error),
+            DumpFormat::Ndjson => {
+                let mut db = RepoDb { lyrics: vec![], playlists: vec![] };
+                for entity in entities(std::io::BufReader::new(r)) {
+                    match entity? {
+                        Entity::Lyric(lyric) => db.lyrics.push(lyric),
+                        Entity::Playlist(playlist) => db.playlists.push(playlist),
+                    }
+                }
+                Ok(db)
+            },
+            DumpFormat::Toml => {
+                let mut s = String::new();
+                let mut r = r;
+                r.read_to_string(&mut s)?;
+                toml::from_str(&s).map_err(dump_error)
+            },
+            #[cfg(feature = "ron")]
+            DumpFormat::Ron => ron::de::from_reader(r).map_err(dump_error),
+        }
+    }
+
+    fn save<W>(&self, format: DumpFormat, w: W) -> Result<()> where W: Write {
+        match format {
+            DumpFormat::Yaml => serde_yaml::to_writer(w, self).map_err(Into::into),
+            DumpFormat::Json => serde_json::to_writer_pretty(w, self).map_err(json_error),
+            DumpFormat::Ndjson => {
+                let mut w = w;
+                for lyric in self.lyrics.iter() {
+                    write_entity(&mut w, &Entity::Lyric(lyric.clone()))?;
+                }
+                for playlist in self.playlists.iter() {
+                    write_entity(&mut w, &Entity::Playlist(playlist.clone()))?;
+                }
+                Ok(())
+            },
+            DumpFormat::Toml => {
+                for lyric in self.lyrics.iter() {
+                    crate::check_toml(&lyric.title, &lyric.extensions)?;
+                }
+                let s = toml::to_string(self).map_err(dump_error)?;
+                let mut w = w;
+                w.write_all(s.as_bytes()).map_err(Into::into)
+            },
+            #[cfg(feature = "ron")]
+            DumpFormat::Ron => {
+                let s = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(dump_error)?;
+                let mut w = w;
+                w.write_all(s.as_bytes()).map_err(Into::into)
+            },
+        }
+    }
+}
+
+/// Entities of an NDJSON dump, empty lines are skipped
+pub fn entities<R: std::io::BufRead>(r: R) -> impl Iterator<Item = Result<Entity>> {
+    r
+    .lines()
+    .filter(|line| line.as_ref().map(|line| !line.trim().is_empty()).unwrap_or(true))
+    .map(|line| line.map_err(Error::from).and_then(|line| serde_json::from_str(&line).map_err(json_error)))
+}
+
+pub fn write_entity<W: Write>(w: &mut W, entity: &Entity) -> Result<()> {
+    serde_json::to_writer(&mut *w, entity).map_err(json_error)?;
+    w.write_all(b"\n").map_err(Into::into)
+}
+
+/// Writes an NDJSON dump of a repository, one lyric at a time
+pub async fn stream_out<W: Write>(repo: &dyn LiplRepo, mut w: W) -> Result<()> {
+    for summary in repo.get_lyric_summaries().await? {
+        write_entity(&mut w, &Entity::Lyric(repo.get_lyric(summary.id).await?))?;
+    }
+    for summary in repo.get_playlist_summaries().await? {
+        write_entity(&mut w, &Entity::Playlist(repo.get_playlist(summary.id).await?))?;
+    }
+    w.flush().map_err(Into::into)
+}
+
+/// Upserts the entities of an NDJSON dump, returns the number of lyrics and playlists
+pub async fn stream_in<R: std::io::BufRead>(r: R, repo: &dyn LiplRepo) -> Result<(usize, usize)> {
+    let mut count = (0, 0);
+    for entity in entities(r) {
+        match entity? {
+            Entity::Lyric(lyric) => {
+                repo.upsert_lyric(lyric).await?;
+                count.0 += 1;
+            },
+            Entity::Playlist(playlist) => {
+                repo.upsert_playlist(playlist).await?;
+                count.1 += 1;
+            },
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Lyric, Playlist, RepoDb, Uuid};
+    use super::{Dump, DumpFormat};
+
+    fn db() -> RepoDb {
+        let lyric = Lyric {
+            id: Uuid::default(),
+            title: "Roodkapje".to_owned(),
+            parts: vec![vec!["Zeg roodkapje".to_owned(), "waar ga je hene".to_owned()], vec!["Refrein".to_owned()]],
+            extensions: Default::default(),
+            times: None,
+        };
+        let playlist = Playlist { id: Uuid::default(), title: "Kinderliedjes".to_owned(), members: vec![lyric.id] };
+        RepoDb { lyrics: vec![lyric], playlists: vec![playlist] }
+    }
+
+    #[test]
+    fn save_and_load_in_every_format() {
+        let db = db();
+        for format in DumpFormat::ALL {
+            let mut buffer = vec![];
+            db.save(*format, &mut buffer).unwrap();
+            let loaded = RepoDb::load(*format, buffer.as_slice()).unwrap();
+            assert_eq!(loaded.lyrics[0].id, db.lyrics[0].id, "{format}");
+            assert_eq!(loaded.lyrics[0].parts, db.lyrics[0].parts, "{format}");
+            assert_eq!(loaded.playlists[0].members, db.playlists[0].members, "{format}");
+        }
+    }
+
+    #[test]
+    fn toml_names_null_extension() {
+        let mut db = db();
+        db.lyrics[0].extensions.insert("notes".to_owned(), serde_yaml::from_str("{ page: ~ }").unwrap());
+        let error = db.save(DumpFormat::Toml, vec![]).unwrap_err();
+        assert!(error.to_string().contains("extension notes of Roodkapje"));
+        db.save(DumpFormat::Yaml, vec![]).unwrap();
+    }
+
+    #[test]
+    fn format_by_name_and_path() {
+        assert_eq!("YML".parse::<DumpFormat>().unwrap(), DumpFormat::Yaml);
+        assert_eq!(DumpFormat::from_path("library.yaml"), Some(DumpFormat::Yaml));
+        assert!("xml".parse::<DumpFormat>().is_err());
+    }
+
+    #[test]
+    fn ndjson_one_entity_per_line() {
+        let mut buffer = vec![];
+        db().save(DumpFormat::Ndjson, &mut buffer).unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"lyric\":"));
+        assert!(lines[1].starts_with("{\"playlist\":"));
+        assert_eq!(DumpFormat::from_path("library.jsonl"), Some(DumpFormat::Ndjson));
+    }
+}
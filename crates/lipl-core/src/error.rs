@@ -36,6 +36,20 @@ pub enum Error {
     #[error("Argument error: {0}")]
     Argument(&'static str),
 
+    #[cfg(feature = "openlyrics")]
+    #[error("OpenLyrics: {0}")]
+    OpenLyrics(String),
+
+    #[cfg(feature = "opensong")]
+    #[error("OpenSong: {0}")]
+    OpenSong(String),
+
+    #[error("Unknown repository scheme {0}, expected one of {1}")]
+    UnknownScheme(String, String),
+
+    #[error("Unknown option {0} in repository url")]
+    UnknownOption(String),
+
     #[error("Directory does not exist: {0}")]
     NonExistingDirectory(std::path::PathBuf),
 
@@ -74,6 +88,15 @@ pub enum Error {
     #[error("Occupied")]
     Occupied,
 
+    #[error("Repository is read-only")]
+    ReadOnly,
+
+    #[error("{0} is held by a repository that is not writable")]
+    NotOwned(Uuid),
+
+    #[error("Repository cannot keep the line times of lyric {0}")]
+    TimesNotKept(Uuid),
+
     #[error(transparent)]
     Warp(Box<dyn std::error::Error + Send + Sync>),
 
@@ -82,6 +105,33 @@ pub enum Error {
 
     #[error(transparent)]
     Json(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("Toml Error: {0}")]
+    Toml(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error(transparent)]
+    Import(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error(transparent)]
+    Pdf(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error(transparent)]
+    Dump(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Whether the error says the lyric or playlist does not exist, other errors say nothing about existence
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Error::NotFound(_) | Error::NoKey(_) => true,
+            Error::IOError(error) => error.kind() == std::io::ErrorKind::NotFound,
+            #[cfg(feature = "file")]
+            Error::File(FileRepoError::IOError(error)) => error.kind() == std::io::ErrorKind::NotFound,
+            #[cfg(feature = "postgres")]
+            Error::Postgres(PostgresRepoError::NoResults) => true,
+            _ => false,
+        }
+    }
 }
 
 #[cfg(feature = "file")]
@@ -114,6 +164,8 @@ pub enum FileRepoError {
     #[error("No Path: {0}")]
     NoPath(String),
 
+    #[error("Lyric {0} was modified outside lipl, accept the change or write without protection")]
+    Modified(String),
 }
 
 // #[cfg(feature = "file")]
@@ -0,0 +1,327 @@
+/*!
+ Union of several repositories presented as one [`LiplRepo`].
+
+ The first member is the write repository. All upserts go to the write repository,
+ deleting an item is only possible when the write repository holds it, otherwise it fails with [`Error::NotOwned`].
+ Reads merge all members, an item held by several members is taken from the first one that holds it,
+ so a local copy in the write repository shadows the shared original.
+ Lists are ordered with [`by_title`] across members.
+
+ Playlist members may refer to lyrics from any member of the federation.
+ The write repository must accept such foreign members, like `memory:` or `file:` with the `foreign` option.
+
+ [`FederatedScheme`] opens a federation from a url listing the members, the write repository first,
+ each as `name=url` or just the url, named after its scheme:
+
+ `federated:[team=file:./team?foreign,shared=postgres://localhost/lipl]`
+ */
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use crate::{by_title, Error, HasSummary, LiplRepo, Lyric, Playlist, Result, Summary, Uuid};
+use crate::registry::{RepoRegistry, RepoScheme, RepoUrl};
+
+struct Member {
+    name: String,
+    repo: Arc<dyn LiplRepo>,
+}
+
+/// Summary of an item together with the name of the member that holds it
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Origin {
+    pub origin: String,
+    pub id: Uuid,
+    pub title: String,
+}
+
+pub struct FederatedRepo {
+    members: Vec<Member>,
+}
+
+fn tag(name: &str) -> impl Fn(Summary) -> Origin + '_ {
+    move |summary| Origin { origin: name.to_owned(), id: summary.id, title: summary.title }
+}
+
+fn first_by_id<T: HasSummary>(items: Vec<T>) -> Vec<T> {
+    let mut seen = HashSet::new();
+    let mut items = items.into_iter().filter(|item| seen.insert(item.summary().id)).collect::<Vec<_>>();
+    items.sort_by(by_title);
+    items
+}
+
+impl FederatedRepo {
+    pub fn new(name: &str, write: Arc<dyn LiplRepo>) -> Self {
+        Self {
+            members: vec![Member { name: name.to_owned(), repo: write }],
+        }
+    }
+
+    pub fn member(mut self, name: &str, repo: Arc<dyn LiplRepo>) -> Self {
+        self.members.push(Member { name: name.to_owned(), repo });
+        self
+    }
+
+    fn write(&self) -> &dyn LiplRepo {
+        self.members[0].repo.as_ref()
+    }
+
+    pub async fn lyric_origins(&self) -> Result<Vec<Origin>> {
+        let mut origins = vec![];
+        for member in self.members.iter() {
+            origins.extend(member.repo.get_lyric_summaries().await?.into_iter().map(tag(&member.name)));
+        }
+        Ok(first_by_id(origins))
+    }
+
+    pub async fn playlist_origins(&self) -> Result<Vec<Origin>> {
+        let mut origins = vec![];
+        for member in self.members.iter() {
+            origins.extend(member.repo.get_playlist_summaries().await?.into_iter().map(tag(&member.name)));
+        }
+        Ok(first_by_id(origins))
+    }
+
+    /// Index of the first member that holds the lyric, together with the lyric
+    async fn lyric_owner(&self, id: Uuid) -> Result<(usize, Lyric)> {
+        for (index, member) in self.members.iter().enumerate() {
+            match member.repo.get_lyric(id).await {
+                Ok(lyric) => return Ok((index, lyric)),
+                Err(error) if error.is_not_found() => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Err(Error::NotFound(id))
+    }
+
+    /// Index of the first member that holds the playlist, together with the playlist
+    async fn playlist_owner(&self, id: Uuid) -> Result<(usize, Playlist)> {
+        for (index, member) in self.members.iter().enumerate() {
+            match member.repo.get_playlist(id).await {
+                Ok(playlist) => return Ok((index, playlist)),
+                Err(error) if error.is_not_found() => continue,
+                Err(error) => return Err(error),
+            }
+        }
+        Err(Error::NotFound(id))
+    }
+}
+
+impl HasSummary for Origin {
+    fn summary(&self) -> Summary {
+        Summary {
+            id: self.id,
+            title: self.title.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl LiplRepo for FederatedRepo {
+    async fn get_lyrics(&self) -> Result<Vec<Lyric>> {
+        let mut lyrics = vec![];
+        for member in self.members.iter() {
+            lyrics.extend(member.repo.get_lyrics().await?);
+        }
+        Ok(first_by_id(lyrics))
+    }
+
+    async fn get_lyric_summaries(&self) -> Result<Vec<Summary>> {
+        self.lyric_origins()
+            .await
+            .map(crate::to_summaries)
+    }
+
+    async fn get_lyric(&self, id: Uuid) -> Result<Lyric> {
+        self.lyric_owner(id).await.map(|(_, lyric)| lyric)
+    }
+
+    async fn upsert_lyric(&self, lyric: Lyric) -> Result<Lyric> {
+        self.write().upsert_lyric(lyric).await
+    }
+
+    async fn delete_lyric(&self, id: Uuid) -> Result<()> {
+        match self.lyric_owner(id).await? {
+            (0, _) => self.write().delete_lyric(id).await,
+            _ => Err(Error::NotOwned(id)),
+        }
+    }
+
+    async fn get_playlists(&self) -> Result<Vec<Playlist>> {
+        let mut playlists = vec![];
+        for member in self.members.iter() {
+            playlists.extend(member.repo.get_playlists().await?);
+        }
+        Ok(first_by_id(playlists))
+    }
+
+    async fn get_playlist_summaries(&self) -> Result<Vec<Summary>> {
+        self.playlist_origins()
+            .await
+            .map(crate::to_summaries)
+    }
+
+    async fn get_playlist(&self, id: Uuid) -> Result<Playlist> {
+        self.playlist_owner(id).await.map(|(_, playlist)| playlist)
+    }
+
+    async fn upsert_playlist(&self, playlist: Playlist) -> Result<Playlist> {
+        let ids = crate::ids(self.lyric_origins().await?.into_iter());
+        if let Some(member) = playlist.members.iter().find(|member| !ids.contains(member)) {
+            return Err(Error::PlaylistInvalidMember(playlist.id.to_string(), member.to_string()));
+        }
+        self.write().upsert_playlist(playlist).await
+    }
+
+    async fn delete_playlist(&self, id: Uuid) -> Result<()> {
+        match self.playlist_owner(id).await? {
+            (0, _) => self.write().delete_playlist(id).await,
+            _ => Err(Error::NotOwned(id)),
+        }
+    }
+
+    async fn stop(&self) -> Result<()> {
+        for member in self.members.iter() {
+            member.repo.stop().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Opens `federated:[name=url,...]` with the members opened by a registry of other schemes
+pub struct FederatedScheme {
+    members: RepoRegistry,
+}
+
+impl FederatedScheme {
+    pub fn new(members: RepoRegistry) -> Self {
+        Self { members }
+    }
+}
+
+/// Name and url of a member, `name=url` or a url named after its scheme
+fn member(s: &str) -> Result<(String, RepoUrl)> {
+    let (name, url) = match s.split_once('=') {
+        Some((name, url)) if !name.contains(':') => (Some(name.trim()), url),
+        _ => (None, s),
+    };
+    let url = url.parse::<RepoUrl>()?;
+    Ok((name.map(str::to_owned).unwrap_or_else(|| url.scheme.clone()), url))
+}
+
+#[async_trait]
+impl RepoScheme for FederatedScheme {
+    fn schemes(&self) -> &[&'static str] {
+        &["federated"]
+    }
+
+    async fn open(&self, url: RepoUrl) -> Result<Arc<dyn LiplRepo>> {
+        url.finish()?;
+        let members =
+            url.location
+            .strip_prefix('[')
+            .and_then(|location| location.strip_suffix(']'))
+            .ok_or(Error::Argument("federated members must be like [name=url,name=url]"))?
+            .split(',')
+            .filter(|member| !member.trim().is_empty())
+            .map(member)
+            .collect::<Result<Vec<_>>>()?;
+        let mut members = members.into_iter();
+        let (name, url) = members.next().ok_or(Error::Argument("federation without members"))?;
+        let mut repo = FederatedRepo::new(&name, self.members.open_url(url).await?);
+        for (name, url) in members {
+            repo = repo.member(&name, self.members.open_url(url).await?);
+        }
+        Ok(Arc::new(repo))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use super::{FederatedRepo, FederatedScheme};
+    use crate::layer::tests::Store;
+    use crate::registry::{RepoRegistry, RepoScheme, RepoUrl};
+    use crate::{by_title, Error, LiplRepo, Playlist, Result, Uuid};
+    use crate::tests::lyric;
+
+    async fn federation() -> (FederatedRepo, Arc<Store>, Arc<Store>) {
+        let local = Arc::new(Store::default());
+        let shared = Arc::new(Store::default());
+        shared.upsert_lyric(lyric("Catootje", &[])).await.unwrap();
+        shared.upsert_lyric(lyric("Sofietje", &[])).await.unwrap();
+        local.upsert_lyric(lyric("Faria", &[])).await.unwrap();
+        let repo = FederatedRepo::new("local", local.clone()).member("shared", shared.clone());
+        (repo, local, shared)
+    }
+
+    #[tokio::test]
+    async fn lyrics_merged_by_title() {
+        let (repo, _, _) = federation().await;
+        let titles = repo.get_lyrics().await.unwrap().into_iter().map(|l| l.title).collect::<Vec<_>>();
+        assert_eq!(titles, vec!["Catootje", "Faria", "Sofietje"]);
+
+        let origins = repo.lyric_origins().await.unwrap();
+        assert!(origins.windows(2).all(|w| by_title(&w[0], &w[1]).is_le()));
+        assert_eq!(origins.iter().map(|o| o.origin.as_str()).collect::<Vec<_>>(), vec!["shared", "local", "shared"]);
+    }
+
+    #[tokio::test]
+    async fn writes_go_to_first_member() {
+        let (repo, local, shared) = federation().await;
+        let mut catootje = shared.get_lyrics().await.unwrap().into_iter().find(|l| l.title == "Catootje").unwrap();
+
+        catootje.parts = vec![vec!["Catootje, Catootje".to_owned()]];
+        repo.upsert_lyric(catootje.clone()).await.unwrap();
+        assert_eq!(local.get_lyric(catootje.id).await.unwrap().parts.len(), 1);
+        assert!(shared.get_lyric(catootje.id).await.unwrap().parts.is_empty());
+        assert_eq!(repo.get_lyric(catootje.id).await.unwrap().parts.len(), 1);
+        assert_eq!(repo.get_lyrics().await.unwrap().len(), 3);
+
+        let sofietje = shared.get_lyrics().await.unwrap().into_iter().find(|l| l.title == "Sofietje").unwrap();
+        assert!(matches!(repo.delete_lyric(sofietje.id).await, Err(Error::NotOwned(id)) if id == sofietje.id));
+        assert!(matches!(repo.delete_lyric(Uuid::default()).await, Err(Error::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn playlist_with_members_from_any_source() {
+        let (repo, local, _) = federation().await;
+        let members = repo.get_lyric_summaries().await.unwrap().into_iter().map(|s| s.id).collect::<Vec<_>>();
+
+        let playlist = Playlist { id: Uuid::default(), title: "Alles".to_owned(), members: members.clone() };
+        repo.upsert_playlist(playlist.clone()).await.unwrap();
+        assert_eq!(local.get_playlist(playlist.id).await.unwrap().members, members);
+
+        let invalid = Playlist { id: Uuid::default(), title: "Niets".to_owned(), members: vec![Uuid::default()] };
+        assert!(matches!(repo.upsert_playlist(invalid).await, Err(Error::PlaylistInvalidMember(_, _))));
+    }
+
+    struct StoreScheme;
+
+    #[async_trait]
+    impl RepoScheme for StoreScheme {
+        fn schemes(&self) -> &[&'static str] {
+            &["store"]
+        }
+
+        async fn open(&self, url: RepoUrl) -> Result<Arc<dyn LiplRepo>> {
+            url.finish()?;
+            Ok(Arc::new(Store::default()))
+        }
+    }
+
+    #[tokio::test]
+    async fn open_by_url() {
+        let registry = RepoRegistry::new().scheme(FederatedScheme::new(RepoRegistry::new().scheme(StoreScheme)));
+        let repo = registry.open("federated:[team=store:,store:]").await.unwrap();
+        let lyric = repo.upsert_lyric(lyric("Faria", &[])).await.unwrap();
+        assert_eq!(repo.get_lyric(lyric.id).await.unwrap().title, "Faria");
+
+        assert!(registry.open("federated:team=store:").await.is_err());
+        assert!(registry.open("federated:[]").await.is_err());
+        assert!(matches!(registry.open("federated:[store:?clear]").await, Err(Error::UnknownOption(key)) if key == "clear"));
+        assert!(matches!(registry.open("federated:[file:./team]").await, Err(Error::UnknownScheme(_, _))));
+    }
+}
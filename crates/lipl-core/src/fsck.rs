@@ -0,0 +1,257 @@
+/*!
+ Integrity check of a repository.
+
+ [`check`] reads every lyric and playlist through [`LiplRepo`], so it works for every backend, and finds
+ - playlist members that are not a lyric, and members that are in a playlist more than once
+ - lyrics or playlists with the same title, ignoring case and surrounding whitespace
+ - orphans: lyrics or playlists that are listed in the summaries but are not found
+ - lyrics or playlists that cannot be read for another reason, like a lost connection
+
+ [`repair`] removes dangling and duplicate members and deletes orphans.
+ Duplicate titles and read errors are only reported, merging lyrics is up to the user
+ and a read error may be gone when checking again.
+ Backends can have problems of their own, like files the file repository cannot parse.
+ */
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use crate::{Error, LiplRepo, Result, Summary, Uuid};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Lyric,
+    Playlist,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Kind::Lyric => write!(f, "lyric"),
+            Kind::Playlist => write!(f, "playlist"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    DanglingMember { playlist: Uuid, member: Uuid },
+    DuplicateMember { playlist: Uuid, member: Uuid },
+    DuplicateTitle { kind: Kind, title: String, ids: Vec<Uuid> },
+    Orphan { kind: Kind, id: Uuid, error: String },
+    Unreadable { kind: Kind, id: Uuid, error: String },
+}
+
+impl Problem {
+    pub fn repairable(&self) -> bool {
+        !matches!(self, Problem::DuplicateTitle { .. } | Problem::Unreadable { .. })
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Problem::DanglingMember { playlist, member } => write!(f, "playlist {playlist} has member {member} that is not a lyric"),
+            Problem::DuplicateMember { playlist, member } => write!(f, "playlist {playlist} has member {member} more than once"),
+            Problem::DuplicateTitle { kind, title, ids } => {
+                write!(f, "{kind}s {} have the same title {title}", ids.iter().map(Uuid::to_string).collect::<Vec<_>>().join(", "))
+            },
+            Problem::Orphan { kind, id, error } => write!(f, "{kind} {id} is listed but not found: {error}"),
+            Problem::Unreadable { kind, id, error } => write!(f, "{kind} {id} cannot be read: {error}"),
+        }
+    }
+}
+
+fn duplicate_titles(kind: Kind, summaries: &[Summary]) -> Vec<Problem> {
+    let mut by_title = BTreeMap::<String, Vec<&Summary>>::new();
+    for summary in summaries {
+        by_title.entry(summary.title.trim().to_lowercase()).or_default().push(summary);
+    }
+    by_title
+        .into_values()
+        .filter(|summaries| summaries.len() > 1)
+        .map(|summaries| Problem::DuplicateTitle { kind, title: summaries[0].title.clone(), ids: summaries.iter().map(|summary| summary.id).collect() })
+        .collect()
+}
+
+fn unreadable(kind: Kind, id: Uuid, error: Error) -> Problem {
+    if error.is_not_found() {
+        Problem::Orphan { kind, id, error: error.to_string() }
+    }
+    else {
+        Problem::Unreadable { kind, id, error: error.to_string() }
+    }
+}
+
+pub async fn check<R: LiplRepo + ?Sized>(repo: &R) -> Result<Vec<Problem>> {
+    let mut problems = vec![];
+
+    let mut lyrics = vec![];
+    for summary in repo.get_lyric_summaries().await? {
+        match repo.get_lyric(summary.id).await {
+            Ok(_) => lyrics.push(summary),
+            Err(error) => problems.push(unreadable(Kind::Lyric, summary.id, error)),
+        }
+    }
+    let lyric_ids = lyrics.iter().map(|summary| summary.id).collect::<HashSet<_>>();
+
+    let mut playlists = vec![];
+    for summary in repo.get_playlist_summaries().await? {
+        match repo.get_playlist(summary.id).await {
+            Ok(playlist) => {
+                let mut seen = HashSet::new();
+                for member in playlist.members.iter().copied() {
+                    if !lyric_ids.contains(&member) {
+                        problems.push(Problem::DanglingMember { playlist: playlist.id, member });
+                    }
+                    else if !seen.insert(member) {
+                        problems.push(Problem::DuplicateMember { playlist: playlist.id, member });
+                    }
+                }
+                playlists.push(summary);
+            },
+            Err(error) => problems.push(unreadable(Kind::Playlist, summary.id, error)),
+        }
+    }
+
+    problems.extend(duplicate_titles(Kind::Lyric, &lyrics));
+    problems.extend(duplicate_titles(Kind::Playlist, &playlists));
+    Ok(problems)
+}
+
+/// Repairs the problems that are repairable, returns the number of repaired problems
+pub async fn repair<R: LiplRepo + ?Sized>(repo: &R, problems: &[Problem]) -> Result<usize> {
+    let mut repaired = 0;
+    for problem in problems {
+        if let Problem::Orphan { kind, id, .. } = problem {
+            match kind {
+                Kind::Lyric => repo.delete_lyric(*id).await?,
+                Kind::Playlist => repo.delete_playlist(*id).await?,
+            };
+            repaired += 1;
+        }
+    }
+
+    let mut members = BTreeMap::<Uuid, usize>::new();
+    for problem in problems {
+        if let Problem::DanglingMember { playlist, .. } | Problem::DuplicateMember { playlist, .. } = problem {
+            *members.entry(*playlist).or_default() += 1;
+        }
+    }
+    if !members.is_empty() {
+        let lyric_ids = repo.get_lyric_summaries().await?.into_iter().map(|summary| summary.id).collect::<HashSet<_>>();
+        for (id, count) in members {
+            let mut playlist = repo.get_playlist(id).await?;
+            let mut seen = HashSet::new();
+            playlist.members.retain(|member| lyric_ids.contains(member) && seen.insert(*member));
+            repo.upsert_playlist(playlist).await?;
+            repaired += count;
+        }
+    }
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use async_trait::async_trait;
+    use super::{check, repair, Kind, Problem};
+    use crate::layer::{Forward, tests::Store};
+    use crate::{Error, LiplRepo, Lyric, Playlist, Result, Summary, Uuid};
+    use crate::tests::lyric;
+
+    /// Lists a lyric it cannot read
+    struct Orphaned {
+        inner: Arc<Store>,
+        orphan: Uuid,
+    }
+
+    #[async_trait]
+    impl Forward for Orphaned {
+        fn inner(&self) -> &dyn LiplRepo {
+            self.inner.as_ref()
+        }
+
+        async fn get_lyric_summaries(&self) -> Result<Vec<Summary>> {
+            let mut summaries = self.inner.get_lyric_summaries().await?;
+            summaries.push(Summary { id: self.orphan, title: "Kwijt".to_owned() });
+            Ok(summaries)
+        }
+
+        async fn delete_lyric(&self, _id: Uuid) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Lists a lyric it cannot find, fails to read one lyric and to delete any, like a repository losing its connection
+    struct Flaky {
+        inner: Arc<Store>,
+        orphan: Uuid,
+        flaky: Uuid,
+    }
+
+    fn reset() -> Error {
+        Error::IOError(std::io::ErrorKind::ConnectionReset.into())
+    }
+
+    #[async_trait]
+    impl Forward for Flaky {
+        fn inner(&self) -> &dyn LiplRepo {
+            self.inner.as_ref()
+        }
+
+        async fn get_lyric_summaries(&self) -> Result<Vec<Summary>> {
+            let mut summaries = self.inner.get_lyric_summaries().await?;
+            summaries.push(Summary { id: self.orphan, title: "Kwijt".to_owned() });
+            Ok(summaries)
+        }
+
+        async fn get_lyric(&self, id: Uuid) -> Result<Lyric> {
+            if id == self.flaky { Err(reset()) } else { self.inner.get_lyric(id).await }
+        }
+
+        async fn delete_lyric(&self, _id: Uuid) -> Result<()> {
+            Err(reset())
+        }
+    }
+
+    #[tokio::test]
+    async fn check_and_repair() {
+        let store = Arc::new(Store::default());
+        let a = store.upsert_lyric(lyric("Zuiderzee", &[])).await.unwrap();
+        let b = store.upsert_lyric(lyric("zuiderzee ", &[])).await.unwrap();
+        let gone = Uuid::default();
+        let playlist = store.upsert_playlist(Playlist { id: Uuid::default(), title: "Kermis".to_owned(), members: vec![a.id, gone, b.id, a.id] }).await.unwrap();
+        let orphan = Uuid::default();
+        let repo = Orphaned { inner: store.clone(), orphan };
+
+        let problems = check(&repo).await.unwrap();
+        assert_eq!(problems.len(), 4);
+        assert!(matches!(problems[0], Problem::Orphan { kind: Kind::Lyric, id, .. } if id == orphan));
+        assert_eq!(problems[1], Problem::DanglingMember { playlist: playlist.id, member: gone });
+        assert_eq!(problems[2], Problem::DuplicateMember { playlist: playlist.id, member: a.id });
+        assert_eq!(problems[3], Problem::DuplicateTitle { kind: Kind::Lyric, title: "Zuiderzee".to_owned(), ids: vec![a.id, b.id] });
+        assert!(!problems[3].repairable());
+
+        assert_eq!(repair(&repo, &problems).await.unwrap(), 3);
+        assert_eq!(store.get_playlist(playlist.id).await.unwrap().members, vec![a.id, b.id]);
+        assert!(check(store.as_ref()).await.unwrap().iter().all(|problem| !problem.repairable()));
+    }
+
+    #[tokio::test]
+    async fn read_errors_are_not_orphans() {
+        let store = Arc::new(Store::default());
+        let flaky = store.upsert_lyric(lyric("Zuiderzee", &[])).await.unwrap();
+        let orphan = Uuid::default();
+        let repo = Flaky { inner: store.clone(), orphan, flaky: flaky.id };
+
+        let problems = check(&repo).await.unwrap();
+        assert_eq!(problems.len(), 2);
+        assert!(matches!(&problems[0], Problem::Unreadable { kind: Kind::Lyric, id, .. } if *id == flaky.id));
+        assert!(!problems[0].repairable());
+        assert!(matches!(problems[1], Problem::Orphan { kind: Kind::Lyric, id, .. } if id == orphan));
+
+        assert!(repair(&repo, &problems[..1]).await.is_ok());
+        assert!(repair(&repo, &problems).await.is_err());
+        assert!(store.get_lyric(flaky.id).await.is_ok());
+    }
+}
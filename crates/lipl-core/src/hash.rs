@@ -0,0 +1,240 @@
+/*!
+ Canonical content hashes, used for ETags and the `hash` in the frontmatter of lyric files.
+
+ A hash is the [`VERSION`] and the SHA-256 of a canonical encoding of the content, like `v1:9f86…`.
+ The encoding does not depend on a serializer or the order of fields:
+ text is Unicode NFC, every string is prefixed with its length, fields are stored by name
+ and maps are sorted by key. Ids are not part of the content, they name it.
+
+ Hashes written before the canonical hash are etags of the bincode serialization,
+ [`verify`] recognizes them as [`Verification::Legacy`] so they can be migrated.
+ [`if_none_match`] compares an ETag made with [`Etag`](crate::Etag) with the `If-None-Match` header of a request.
+ */
+
+use std::collections::BTreeMap;
+use sha2::{Digest, Sha256};
+use serde::Serialize;
+use unicode_normalization::UnicodeNormalization;
+use crate::{LineTime, Lyric, Playlist, Uuid};
+
+/// Version of the canonical encoding, changing the encoding needs a new version
+pub const VERSION: &str = "v1";
+
+/// Content that has a canonical encoding
+pub trait Canonical {
+    fn encode(&self, encoder: &mut Encoder);
+}
+
+#[derive(Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    fn prefix(&mut self, tag: char, len: usize) {
+        self.bytes.extend(format!("{tag}{len}:").into_bytes());
+    }
+
+    pub fn str(&mut self, s: &str) {
+        let s = s.nfc().collect::<String>();
+        self.prefix('s', s.len());
+        self.bytes.extend(s.into_bytes());
+    }
+
+    pub fn list<T: Canonical>(&mut self, items: &[T]) {
+        self.prefix('l', items.len());
+        items.iter().for_each(|item| item.encode(self));
+    }
+
+    /// Entries sorted by the encoding of their key
+    pub fn map<K: Canonical, V: Canonical>(&mut self, entries: &[(K, V)]) {
+        let mut encoded = entries.iter().map(|(key, value)| (encoding(key), encoding(value))).collect::<Vec<_>>();
+        encoded.sort();
+        self.prefix('m', encoded.len());
+        for (key, value) in encoded {
+            self.bytes.extend(key);
+            self.bytes.extend(value);
+        }
+    }
+
+    /// Fields by name
+    pub fn record(&mut self, fields: &[(&str, &dyn Canonical)]) {
+        self.map(fields)
+    }
+}
+
+fn encoding<T: Canonical + ?Sized>(t: &T) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    t.encode(&mut encoder);
+    encoder.bytes
+}
+
+/// Versioned SHA-256 of the canonical encoding
+pub fn hash<T: Canonical + ?Sized>(t: &T) -> String {
+    format!("{VERSION}:{:x}", Sha256::digest(encoding(t)))
+}
+
+/// Etag of the bincode serialization, the hash before the canonical hash
+pub fn legacy<T: Serialize>(t: &T) -> Option<String> {
+    bincode::serialize(t)
+        .map(|bytes| etag::EntityTag::const_from_data(&bytes).to_string())
+        .ok()
+}
+
+/// Whether an `If-None-Match` header value names the etag or is `*`, weak tags compare by their value
+pub fn if_none_match(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verification {
+    /// The stored hash is the canonical hash
+    Current,
+    /// The stored hash is a legacy hash of the same content
+    Legacy,
+    Mismatch,
+}
+
+/// Compares the content with a stored hash
+pub fn verify<T: Canonical + Serialize>(t: &T, stored: &str) -> Verification {
+    if stored == hash(t) {
+        Verification::Current
+    }
+    else if legacy(t).as_deref() == Some(stored) {
+        Verification::Legacy
+    }
+    else {
+        Verification::Mismatch
+    }
+}
+
+impl<T: Canonical + ?Sized> Canonical for &T {
+    fn encode(&self, encoder: &mut Encoder) {
+        (*self).encode(encoder)
+    }
+}
+
+impl Canonical for str {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.str(self)
+    }
+}
+
+impl Canonical for String {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.str(self)
+    }
+}
+
+impl<T: Canonical> Canonical for Vec<T> {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.list(self)
+    }
+}
+
+impl<K: Canonical, V: Canonical> Canonical for BTreeMap<K, V> {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.map(&self.iter().collect::<Vec<_>>())
+    }
+}
+
+impl Canonical for Uuid {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.str(&self.inner().hyphenated().to_string())
+    }
+}
+
+impl Canonical for serde_yaml::Value {
+    fn encode(&self, encoder: &mut Encoder) {
+        use serde_yaml::Value;
+        match self {
+            Value::Null => encoder.bytes.push(b'n'),
+            Value::Bool(b) => encoder.bytes.push(if *b { b't' } else { b'f' }),
+            Value::Number(n) => {
+                let n = n.to_string();
+                encoder.prefix('d', n.len());
+                encoder.bytes.extend(n.into_bytes());
+            },
+            Value::String(s) => encoder.str(s),
+            Value::Sequence(items) => encoder.list(items),
+            Value::Mapping(mapping) => encoder.map(&mapping.iter().collect::<Vec<_>>()),
+            Value::Tagged(tagged) => {
+                encoder.bytes.push(b'g');
+                encoder.str(&tagged.tag.to_string());
+                tagged.value.encode(encoder);
+            },
+        }
+    }
+}
+
+impl Canonical for LineTime {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.str(&self.to_string())
+    }
+}
+
+/// Lyrics without times have the hash they had before times
+impl Canonical for Lyric {
+    fn encode(&self, encoder: &mut Encoder) {
+        let mut fields: Vec<(&str, &dyn Canonical)> = vec![("title", &self.title), ("parts", &self.parts), ("extensions", &self.extensions)];
+        if let Some(times) = &self.times {
+            fields.push(("times", times));
+        }
+        encoder.record(&fields)
+    }
+}
+
+impl Canonical for Playlist {
+    fn encode(&self, encoder: &mut Encoder) {
+        encoder.record(&[("title", &self.title), ("members", &self.members)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hash, if_none_match, legacy, verify, Verification};
+    use crate::{Etag, LineTime, Lyric, Playlist, Uuid};
+    use crate::tests;
+
+    const PARTS: &[&[&str]] = &[&["Regel"], &["Tweede"]];
+
+    #[test]
+    fn stable() {
+        let mut lyric = tests::lyric("Caf\u{00E9}", PARTS);
+        assert_eq!(hash(&lyric), "v1:7727239915e380ce4b44fbd3f1eb50f1270b1479845609daa07ddb2008b337ef");
+        assert_eq!(hash(&lyric), hash(&Lyric { id: Uuid::default(), title: "Cafe\u{0301}".to_owned(), ..lyric.clone() }));
+
+        lyric.extensions.insert("source".to_owned(), serde_yaml::from_str("{ book: Kampvuur, page: 12 }").unwrap());
+        let mut reordered = lyric.clone();
+        reordered.extensions.insert("source".to_owned(), serde_yaml::from_str("{ page: 12, book: Kampvuur }").unwrap());
+        assert_eq!(hash(&lyric), hash(&reordered));
+        reordered.parts = vec![vec!["Regel".to_owned(), "Tweede".to_owned()]];
+        assert_ne!(hash(&lyric), hash(&reordered));
+        reordered.parts = lyric.parts.clone();
+        reordered.times = Some(vec![vec![LineTime(None)], vec![LineTime(None)]]);
+        assert_ne!(hash(&lyric), hash(&reordered));
+
+        let playlist = Playlist { id: Uuid::default(), title: "Kermis".to_owned(), members: vec![lyric.id] };
+        assert!(hash(&playlist).starts_with("v1:"));
+    }
+
+    #[test]
+    fn verifies_legacy() {
+        let lyric = tests::lyric("Sofietje", PARTS);
+        assert_eq!(verify(&lyric, &hash(&lyric)), Verification::Current);
+        assert_eq!(verify(&lyric, &legacy(&lyric).unwrap()), Verification::Legacy);
+        assert_eq!(verify(&lyric, "v1:00"), Verification::Mismatch);
+    }
+
+    #[test]
+    fn matches_if_none_match() {
+        let etag = tests::lyric("Sofietje", PARTS).etag();
+        assert!(if_none_match(&etag, &etag));
+        assert!(if_none_match(&format!("\"v1:00\", W/{etag}"), &etag));
+        assert!(if_none_match("*", &etag));
+        assert!(!if_none_match("\"v1:00\"", &etag));
+    }
+}
@@ -0,0 +1,79 @@
+/*!
+ What an importer creates, shared by the importers of other formats.
+
+ An importer builds a [`Report`] with new ids, [`Report::import`] writes it into any repository
+ that implements [`ToRepo`], a dry run returns the report without opening the repository.
+ [`import_error!`](crate::import_error) turns the error of an importer into [`Error::Import`](crate::Error::Import).
+ */
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use crate::{Lyric, Playlist, Result, ToRepo};
+
+/// What an import creates
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Lyrics with what the import found that has no place in a lyric, like authors
+    pub lyrics: Vec<(Lyric, Vec<String>)>,
+    pub playlists: Vec<Playlist>,
+    pub skipped: Vec<String>,
+}
+
+impl Report {
+    /// Writes the lyrics and playlists into the target, unless it is a dry run
+    pub async fn import<T: ToRepo>(self, target: T, dry_run: bool) -> Result<Self> {
+        if dry_run {
+            return Ok(self);
+        }
+        let repo = target.to_repo().await?;
+        for (lyric, _) in self.lyrics.iter() {
+            repo.upsert_lyric(lyric.clone()).await?;
+        }
+        for playlist in self.playlists.iter() {
+            repo.upsert_playlist(playlist.clone()).await?;
+        }
+        repo.stop().await?;
+        Ok(self)
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "Lyrics:")?;
+        for (lyric, found) in self.lyrics.iter() {
+            write!(f, " - {}, {} parts", lyric.title, lyric.parts.len())?;
+            if !found.is_empty() {
+                write!(f, ", {}", found.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Playlists:")?;
+        for playlist in self.playlists.iter() {
+            writeln!(f, " - {}, {} members", playlist.title, playlist.members.len())?;
+        }
+        if !self.skipped.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Skipped:")?;
+            for skipped in self.skipped.iter() {
+                writeln!(f, " - {skipped}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Implements `From<$error> for lipl_core::Error` for an importer error with a `Repo(lipl_core::Error)` variant,
+/// repository errors are passed on and the other errors become [`Error::Import`](crate::Error::Import)
+#[macro_export]
+macro_rules! import_error {
+    ($error:ident) => {
+        impl From<$error> for $crate::Error {
+            fn from(error: $error) -> Self {
+                match error {
+                    $error::Repo(error) => error,
+                    _ => $crate::Error::Import(Box::new(error)),
+                }
+            }
+        }
+    };
+}
@@ -0,0 +1,321 @@
+/*!
+ Composable decorators for [`LiplRepo`], modelled after `tower::Layer` and `tower::ServiceBuilder`.
+
+ A [`RepoLayer`] wraps a repository in another repository. A [`RepoBuilder`] stacks layers
+ so that cross-cutting concerns (logging, caching, validation, read-only mode) can be
+ assembled from configuration. Decorators implement [`Forward`] and only override
+ the operations they care about, all other operations are passed on to the inner repository.
+
+ ```
+ use std::sync::Arc;
+ use lipl_core::{LiplRepo, Lyric, Result};
+ use lipl_core::layer::{Forward, RepoBuilder};
+
+ struct Shout(Arc<dyn LiplRepo>);
+
+ #[async_trait::async_trait]
+ impl Forward for Shout {
+     fn inner(&self) -> &dyn LiplRepo {
+         self.0.as_ref()
+     }
+
+     async fn upsert_lyric(&self, mut lyric: Lyric) -> Result<Lyric> {
+         lyric.title = lyric.title.to_uppercase();
+         self.inner().upsert_lyric(lyric).await
+     }
+ }
+
+ fn build(repo: Arc<dyn LiplRepo>, shout: bool) -> Arc<dyn LiplRepo> {
+     RepoBuilder::new()
+         .option_layer(shout.then_some(|inner: Arc<dyn LiplRepo>| Arc::new(Shout(inner)) as Arc<dyn LiplRepo>))
+         .service(repo)
+ }
+ ```
+ */
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::{LiplRepo, Lyric, Playlist, Result, Summary, Uuid};
+
+/// Decorates a repository, analogous to `tower::Layer`
+pub trait RepoLayer: Send + Sync {
+    fn layer(&self, inner: Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo>;
+}
+
+impl<F> RepoLayer for F
+where
+    F: Fn(Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo> + Send + Sync,
+{
+    fn layer(&self, inner: Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo> {
+        self(inner)
+    }
+}
+
+/// Stacks layers around a repository, analogous to `tower::ServiceBuilder`
+///
+/// The layer added first is the outermost one, so it sees a request before all other layers.
+#[derive(Default)]
+pub struct RepoBuilder {
+    layers: Vec<Box<dyn RepoLayer>>,
+}
+
+impl RepoBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn layer<L>(mut self, layer: L) -> Self
+    where
+        L: RepoLayer + 'static,
+    {
+        self.layers.push(Box::new(layer));
+        self
+    }
+
+    pub fn option_layer<L>(self, layer: Option<L>) -> Self
+    where
+        L: RepoLayer + 'static,
+    {
+        match layer {
+            Some(layer) => self.layer(layer),
+            None => self,
+        }
+    }
+
+    pub fn service(&self, repo: Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo> {
+        self.layers
+            .iter()
+            .rev()
+            .fold(repo, |inner, layer| layer.layer(inner))
+    }
+}
+
+/// Forwards every operation to an inner repository
+///
+/// Every type implementing `Forward` implements [`LiplRepo`].
+/// Override the operations the decorator needs to change.
+#[async_trait]
+pub trait Forward: Send + Sync {
+    fn inner(&self) -> &dyn LiplRepo;
+
+    async fn get_lyrics(&self) -> Result<Vec<Lyric>> {
+        self.inner().get_lyrics().await
+    }
+
+    async fn get_lyric_summaries(&self) -> Result<Vec<Summary>> {
+        self.inner().get_lyric_summaries().await
+    }
+
+    async fn get_lyric(&self, id: Uuid) -> Result<Lyric> {
+        self.inner().get_lyric(id).await
+    }
+
+    async fn upsert_lyric(&self, lyric: Lyric) -> Result<Lyric> {
+        self.inner().upsert_lyric(lyric).await
+    }
+
+    async fn delete_lyric(&self, id: Uuid) -> Result<()> {
+        self.inner().delete_lyric(id).await
+    }
+
+    async fn get_playlists(&self) -> Result<Vec<Playlist>> {
+        self.inner().get_playlists().await
+    }
+
+    async fn get_playlist_summaries(&self) -> Result<Vec<Summary>> {
+        self.inner().get_playlist_summaries().await
+    }
+
+    async fn get_playlist(&self, id: Uuid) -> Result<Playlist> {
+        self.inner().get_playlist(id).await
+    }
+
+    async fn upsert_playlist(&self, playlist: Playlist) -> Result<Playlist> {
+        self.inner().upsert_playlist(playlist).await
+    }
+
+    async fn delete_playlist(&self, id: Uuid) -> Result<()> {
+        self.inner().delete_playlist(id).await
+    }
+
+    async fn stop(&self) -> Result<()> {
+        self.inner().stop().await
+    }
+}
+
+#[async_trait]
+impl<T> LiplRepo for T
+where
+    T: Forward,
+{
+    async fn get_lyrics(&self) -> Result<Vec<Lyric>> {
+        Forward::get_lyrics(self).await
+    }
+
+    async fn get_lyric_summaries(&self) -> Result<Vec<Summary>> {
+        Forward::get_lyric_summaries(self).await
+    }
+
+    async fn get_lyric(&self, id: Uuid) -> Result<Lyric> {
+        Forward::get_lyric(self, id).await
+    }
+
+    async fn upsert_lyric(&self, lyric: Lyric) -> Result<Lyric> {
+        Forward::upsert_lyric(self, lyric).await
+    }
+
+    async fn delete_lyric(&self, id: Uuid) -> Result<()> {
+        Forward::delete_lyric(self, id).await
+    }
+
+    async fn get_playlists(&self) -> Result<Vec<Playlist>> {
+        Forward::get_playlists(self).await
+    }
+
+    async fn get_playlist_summaries(&self) -> Result<Vec<Summary>> {
+        Forward::get_playlist_summaries(self).await
+    }
+
+    async fn get_playlist(&self, id: Uuid) -> Result<Playlist> {
+        Forward::get_playlist(self, id).await
+    }
+
+    async fn upsert_playlist(&self, playlist: Playlist) -> Result<Playlist> {
+        Forward::upsert_playlist(self, playlist).await
+    }
+
+    async fn delete_playlist(&self, id: Uuid) -> Result<()> {
+        Forward::delete_playlist(self, id).await
+    }
+
+    async fn stop(&self) -> Result<()> {
+        Forward::stop(self).await
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::{Arc, Mutex};
+    use async_trait::async_trait;
+    use super::{Forward, RepoBuilder};
+    use crate::{Error, HasSummary, LiplRepo, Lyric, Playlist, Result, Summary, ToRepo, Uuid};
+    use crate::tests::lyric;
+
+    pub(crate) struct Empty;
+
+    #[async_trait]
+    impl LiplRepo for Empty {
+        async fn get_lyrics(&self) -> Result<Vec<Lyric>> { Ok(vec![]) }
+        async fn get_lyric_summaries(&self) -> Result<Vec<Summary>> { Ok(vec![]) }
+        async fn get_lyric(&self, id: Uuid) -> Result<Lyric> { Err(Error::NotFound(id)) }
+        async fn upsert_lyric(&self, lyric: Lyric) -> Result<Lyric> { Ok(lyric) }
+        async fn delete_lyric(&self, id: Uuid) -> Result<()> { Err(Error::NotFound(id)) }
+        async fn get_playlists(&self) -> Result<Vec<Playlist>> { Ok(vec![]) }
+        async fn get_playlist_summaries(&self) -> Result<Vec<Summary>> { Ok(vec![]) }
+        async fn get_playlist(&self, id: Uuid) -> Result<Playlist> { Err(Error::NotFound(id)) }
+        async fn upsert_playlist(&self, playlist: Playlist) -> Result<Playlist> { Ok(playlist) }
+        async fn delete_playlist(&self, id: Uuid) -> Result<()> { Err(Error::NotFound(id)) }
+        async fn stop(&self) -> Result<()> { Ok(()) }
+    }
+
+    #[derive(Default)]
+    pub(crate) struct Store {
+        lyrics: Mutex<Vec<Lyric>>,
+        playlists: Mutex<Vec<Playlist>>,
+    }
+
+    fn find<T: HasSummary + Clone>(list: &[T], id: Uuid) -> Result<T> {
+        list.iter().find(|t| t.summary().id == id).cloned().ok_or(Error::NotFound(id))
+    }
+
+    #[async_trait]
+    impl LiplRepo for Store {
+        async fn get_lyrics(&self) -> Result<Vec<Lyric>> { Ok(self.lyrics.lock().unwrap().clone()) }
+        async fn get_lyric_summaries(&self) -> Result<Vec<Summary>> { Ok(crate::to_summaries(self.get_lyrics().await?)) }
+        async fn get_lyric(&self, id: Uuid) -> Result<Lyric> { find(&self.lyrics.lock().unwrap(), id) }
+        async fn upsert_lyric(&self, lyric: Lyric) -> Result<Lyric> {
+            let mut lyrics = self.lyrics.lock().unwrap();
+            lyrics.retain(|l| l.id != lyric.id);
+            lyrics.push(lyric.clone());
+            Ok(lyric)
+        }
+        async fn delete_lyric(&self, id: Uuid) -> Result<()> { self.lyrics.lock().unwrap().retain(|l| l.id != id); Ok(()) }
+        async fn get_playlists(&self) -> Result<Vec<Playlist>> { Ok(self.playlists.lock().unwrap().clone()) }
+        async fn get_playlist_summaries(&self) -> Result<Vec<Summary>> { Ok(crate::to_summaries(self.get_playlists().await?)) }
+        async fn get_playlist(&self, id: Uuid) -> Result<Playlist> { find(&self.playlists.lock().unwrap(), id) }
+        async fn upsert_playlist(&self, playlist: Playlist) -> Result<Playlist> {
+            let mut playlists = self.playlists.lock().unwrap();
+            playlists.retain(|p| p.id != playlist.id);
+            playlists.push(playlist.clone());
+            Ok(playlist)
+        }
+        async fn delete_playlist(&self, id: Uuid) -> Result<()> { self.playlists.lock().unwrap().retain(|p| p.id != id); Ok(()) }
+        async fn stop(&self) -> Result<()> { Ok(()) }
+    }
+
+    #[async_trait]
+    impl ToRepo for Arc<Store> {
+        async fn to_repo(self) -> Result<Arc<dyn LiplRepo>> { Ok(self) }
+    }
+
+    struct Suffix {
+        inner: Arc<dyn LiplRepo>,
+        suffix: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    #[async_trait]
+    impl Forward for Suffix {
+        fn inner(&self) -> &dyn LiplRepo {
+            self.inner.as_ref()
+        }
+
+        async fn upsert_lyric(&self, mut lyric: Lyric) -> Result<Lyric> {
+            self.log.lock().unwrap().push(self.suffix);
+            lyric.title.push_str(self.suffix);
+            self.inner().upsert_lyric(lyric).await
+        }
+    }
+
+    fn suffix(suffix: &'static str, log: Arc<Mutex<Vec<&'static str>>>) -> impl Fn(Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo> {
+        move |inner| Arc::new(Suffix { inner, suffix, log: log.clone() })
+    }
+
+    #[tokio::test]
+    async fn first_layer_is_outermost() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let repo =
+            RepoBuilder::new()
+            .layer(suffix(" 1", log.clone()))
+            .layer(suffix(" 2", log.clone()))
+            .service(Arc::new(Empty));
+
+        let lyric = repo.upsert_lyric(lyric("Roodkapje", &[])).await.unwrap();
+        assert_eq!(lyric.title, "Roodkapje 1 2".to_owned());
+        assert_eq!(*log.lock().unwrap(), vec![" 1", " 2"]);
+    }
+
+    #[tokio::test]
+    async fn option_layer_none_is_identity() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let repo =
+            RepoBuilder::new()
+            .option_layer(None::<fn(Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo>>)
+            .option_layer(Some(suffix(" 1", log.clone())))
+            .service(Arc::new(Empty));
+
+        let lyric = repo.upsert_lyric(lyric("Roodkapje", &[])).await.unwrap();
+        assert_eq!(lyric.title, "Roodkapje 1".to_owned());
+    }
+
+    #[tokio::test]
+    async fn not_overridden_is_forwarded() {
+        let log = Arc::new(Mutex::new(vec![]));
+        let repo = RepoBuilder::new().layer(suffix(" 1", log.clone())).service(Arc::new(Empty));
+        let id = Uuid::default();
+
+        assert!(matches!(repo.get_lyric(id).await, Err(Error::NotFound(uuid)) if uuid == id));
+        assert!(repo.get_playlists().await.unwrap().is_empty());
+        assert!(log.lock().unwrap().is_empty());
+    }
+}
@@ -9,16 +9,35 @@
  */
 
 use core::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::collections::BTreeMap;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{cmp::Ordering};
 use async_trait::{async_trait};
 use serde::{Deserialize, Serialize};
+use serde_with::{DeserializeFromStr, SerializeDisplay};
 pub use crate::uuid::Uuid;
 pub use error::Error;
+pub use parts::Frontmatter;
 
 mod disk_format;
+pub mod dump;
 pub mod error;
+pub mod federated;
+pub mod fsck;
+pub mod hash;
+pub mod import;
+pub mod layer;
+pub mod lint;
+pub mod normalize;
+#[cfg(feature = "openlyrics")]
+pub mod openlyrics;
+#[cfg(feature = "opensong")]
+pub mod opensong;
+pub mod read_only;
 pub mod reexport;
+pub mod registry;
 #[cfg(feature = "transaction")]
 pub mod transaction;
 mod uuid;
@@ -49,11 +68,95 @@ pub trait HasSummary {
     fn summary(&self) -> Summary;
 }
 
+/// Frontmatter keys other than title, hash and times, like notes, tags or a source URL, written back unchanged
+pub type Extensions = BTreeMap<String, serde_yaml::Value>;
+
+/// Frontmatter keys of lipl itself, an extension with one of these keys is dropped
+pub const RESERVED_KEYS: &[&str] = &["title", "hash", "times"];
+
+/// Extensions without the [reserved keys](RESERVED_KEYS)
+pub fn without_reserved(extensions: Extensions) -> Extensions {
+    extensions.into_iter().filter(|(key, _)| !RESERVED_KEYS.contains(&key.as_str())).collect()
+}
+
+fn has_null(value: &serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::Null => true,
+        serde_yaml::Value::Sequence(values) => values.iter().any(has_null),
+        serde_yaml::Value::Mapping(mapping) => mapping.iter().any(|(key, value)| has_null(key) || has_null(value)),
+        serde_yaml::Value::Tagged(tagged) => has_null(&tagged.value),
+        _ => false,
+    }
+}
+
+/// TOML has no null, fails naming the first extension that holds one
+pub(crate) fn check_toml(title: &str, extensions: &Extensions) -> Result<()> {
+    match extensions.iter().find(|(_, value)| has_null(value)) {
+        Some((key, _)) => Err(Error::Toml(format!("extension {key} of {title} is null, TOML cannot hold null").into())),
+        None => Ok(()),
+    }
+}
+
+fn deserialize_extensions<'de, D>(deserializer: D) -> std::result::Result<Extensions, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Extensions::deserialize(deserializer).map(without_reserved)
+}
+
+/// Time of a line from the start of the backing track, written like an LRC time tag as `01:02.50`, empty without time
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, DeserializeFromStr, SerializeDisplay)]
+pub struct LineTime(pub Option<Duration>);
+
+impl Display for LineTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.0 {
+            Some(time) => write!(f, "{}", parts::lrc::format_time(time)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for LineTime {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "" => Ok(LineTime(None)),
+            tag => parts::lrc::parse_time(tag).map(|time| LineTime(Some(time))).ok_or(Error::Argument("line time must be like 01:02.50")),
+        }
+    }
+}
+
+/// Times of the lines of a lyric, by part like the lines, see [`parts::lrc`]
+pub type Times = Vec<Vec<LineTime>>;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Lyric {
     pub id: Uuid,
     pub title: String,
     pub parts: Vec<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialize_extensions", skip_serializing_if = "Extensions::is_empty")]
+    pub extensions: Extensions,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub times: Option<Times>,
+}
+
+impl Lyric {
+    /// Fails for a lyric with line times, for repositories that cannot keep them
+    pub fn untimed(&self) -> Result<()> {
+        if self.times.iter().flatten().flatten().any(|time| time.0.is_some()) {
+            Err(Error::TimesNotKept(self.id))
+        }
+        else {
+            Ok(())
+        }
+    }
+
+    /// Lines with their time
+    pub fn timed(&self) -> Vec<Vec<parts::lrc::TimedLine>> {
+        let times = self.times.iter().flatten().map(|part| part.iter().map(|time| time.0).collect()).collect::<Vec<_>>();
+        parts::lrc::join(&self.parts, &times)
+    }
 }
 
 impl HasSummary for Lyric {
@@ -76,6 +179,10 @@ where
 pub struct LyricPost {
     pub title: String,
     pub parts: Vec<Vec<String>>,
+    #[serde(default, deserialize_with = "deserialize_extensions", skip_serializing_if = "Extensions::is_empty")]
+    pub extensions: Extensions,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub times: Option<Times>,
 }
 
 impl From<(Option<Uuid>, LyricPost)> for Lyric {
@@ -84,6 +191,8 @@ impl From<(Option<Uuid>, LyricPost)> for Lyric {
             id: data.0.unwrap_or_default(),
             title: data.1.title,
             parts: data.1.parts,
+            extensions: data.1.extensions,
+            times: data.1.times,
         }
     }
 }
@@ -94,13 +203,15 @@ impl From<LyricPost> for Lyric {
             id: Default::default(),
             title: lyric_post.title,
             parts: lyric_post.parts,
+            extensions: lyric_post.extensions,
+            times: lyric_post.times,
         }
     }
 }
 
 impl From<Lyric> for LyricPost {
     fn from(lyric: Lyric) -> Self {
-        Self { title: lyric.title, parts: lyric.parts }
+        Self { title: lyric.title, parts: lyric.parts, extensions: lyric.extensions, times: lyric.times }
     }
 }
 
@@ -109,6 +220,66 @@ impl From<(&str, &str)> for LyricPost {
         Self {
             title: value.0.to_owned(),
             parts: parts::to_parts(value.1.to_owned()),
+            extensions: Default::default(),
+            times: None,
+        }
+    }
+}
+
+impl From<parts::chordpro::ChordPro> for LyricPost {
+    fn from(song: parts::chordpro::ChordPro) -> Self {
+        Self {
+            parts: song.parts(true),
+            title: song.title.unwrap_or_default(),
+            extensions: Default::default(),
+            times: None,
+        }
+    }
+}
+
+/// Line of a timed lyric, time in milliseconds from the start of the backing track
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TimedLine {
+    pub time: Option<u64>,
+    pub text: String,
+}
+
+/// Lyric with the time of every line next to its text
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TimedLyric {
+    pub id: Uuid,
+    pub title: String,
+    pub parts: Vec<Vec<TimedLine>>,
+}
+
+impl From<Lyric> for TimedLyric {
+    fn from(lyric: Lyric) -> Self {
+        Self {
+            parts: 
+                lyric.timed()
+                .into_iter()
+                .map(|part| 
+                    part
+                    .into_iter()
+                    .map(|line| TimedLine { time: line.time.map(|time| time.as_millis() as u64), text: line.text })
+                    .collect()
+                )
+                .collect(),
+            id: lyric.id,
+            title: lyric.title,
+        }
+    }
+}
+
+impl From<parts::lrc::Lrc> for LyricPost {
+    fn from(lrc: parts::lrc::Lrc) -> Self {
+        let (parts, times) = parts::lrc::split(&lrc.parts);
+        let timed = times.iter().flatten().any(Option::is_some);
+        Self {
+            parts,
+            title: lrc.title.unwrap_or_default(),
+            extensions: Default::default(),
+            times: timed.then(|| times.into_iter().map(|part| part.into_iter().map(LineTime).collect()).collect()),
         }
     }
 }
@@ -212,27 +383,32 @@ where
 pub struct LyricMeta {
     pub title: String,
     pub hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub times: Option<Times>,
+    #[serde(flatten)]
+    pub extensions: Extensions,
 }
 
 impl From<&Lyric> for LyricMeta {
     fn from(l: &Lyric) -> Self {
+        let extensions = without_reserved(l.extensions.clone());
         LyricMeta {
             title: l.title.clone(),
-            hash: l.etag()
+            hash: Some(hash::hash(&Lyric { extensions: extensions.clone(), ..l.clone() })),
+            times: l.times.clone(),
+            extensions,
         }
     }
 }
 
+/// Strong ETag of the canonical [`hash`](hash::hash) of the content
 pub trait Etag {
-    fn etag(&self) -> Option<String>;
+    fn etag(&self) -> String;
 }
 
-impl<T: Serialize> Etag for T {
-    fn etag(&self) -> Option<String> {
-        bincode::serialize(self)
-        .map(|bytes| etag::EntityTag::const_from_data(&bytes))
-        .map(|etag| etag.to_string())
-        .ok()
+impl<T: hash::Canonical> Etag for T {
+    fn etag(&self) -> String {
+        format!("\"{}\"", hash::hash(self))
     }
 }
 
@@ -256,6 +432,11 @@ impl RepoDb {
         self.lyrics.iter().find(|lyric| lyric.title == *title).cloned()
     }
 
+    /// All lyrics and playlists of a repository
+    pub async fn from_repo<R: LiplRepo + ?Sized>(repo: &R) -> Result<Self> {
+        Ok(Self { lyrics: repo.get_lyrics().await?, playlists: repo.get_playlists().await? })
+    }
+
     pub fn to_yaml(&self) -> Result<String> {
         let s = serde_yaml::to_string(self)?;
         Ok(s)
@@ -295,7 +476,33 @@ impl std::fmt::Display for RepoDb {
     }
 }
 
+/// YAML dump, see [`dump::Dump`] for other formats
 pub trait Yaml {
     fn load<R>(r: R) -> Result<Self> where R: std::io::Read, Self: Sized;
     fn save<W>(&self, w: W) -> Result<()> where W: std::io::Write;
 }
+
+impl<T: dump::Dump> Yaml for T {
+    fn load<R>(r: R) -> Result<Self> where R: std::io::Read, Self: Sized {
+        T::load(dump::DumpFormat::Yaml, r)
+    }
+
+    fn save<W>(&self, w: W) -> Result<()> where W: std::io::Write {
+        dump::Dump::save(self, dump::DumpFormat::Yaml, w)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::{Lyric, Uuid};
+
+    pub(crate) fn lyric(title: &str, parts: &[&[&str]]) -> Lyric {
+        Lyric {
+            id: Uuid::default(),
+            title: title.to_owned(),
+            parts: parts.iter().map(|part| part.iter().map(|line| line.to_string()).collect()).collect(),
+            extensions: Default::default(),
+            times: None,
+        }
+    }
+}
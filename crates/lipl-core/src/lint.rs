@@ -0,0 +1,320 @@
+/*!
+ Lint rules for lyrics, with automatic fixes.
+
+ | rule | finds | fix |
+ |------|-------|-----|
+ | `trailing-space` | lines ending in whitespace | trims the line |
+ | `header-part` | parts of one line like `Refrein:` or `[Couplet 2]` | leaves the part out |
+ | `duplicate-part` | a part repeated right after itself | leaves the repetition out |
+ | `capitalization` | lines starting in lowercase where most lines start in uppercase | capitalizes the line |
+
+ Every rule has a [`Severity`], a rule that is [`Severity::Off`] is neither checked nor fixed.
+ [`diff`] shows what a fix changes.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use parts::normalize::Normalizer;
+use crate::Lyric;
+
+/// Labels of parts that are headers, in lowercase
+const HEADERS: &[&str] = &["refrein", "chorus", "couplet", "verse", "vers", "strofe", "bridge", "brug", "intro", "outro", "pre-chorus", "coda", "slot"];
+const CONTEXT: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rule {
+    TrailingSpace,
+    HeaderPart,
+    DuplicatePart,
+    Capitalization,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Rule {
+    pub const ALL: &'static [Rule] = &[Rule::TrailingSpace, Rule::HeaderPart, Rule::DuplicatePart, Rule::Capitalization];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::TrailingSpace => "trailing-space",
+            Rule::HeaderPart => "header-part",
+            Rule::DuplicatePart => "duplicate-part",
+            Rule::Capitalization => "capitalization",
+        }
+    }
+}
+
+impl Severity {
+    pub const ALL: &'static [Severity] = &[Severity::Off, Severity::Warning, Severity::Error];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Off => "off",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+macro_rules! named {
+    ($named:ident, $what:literal) => {
+        impl Display for $named {
+            fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+                write!(f, "{}", self.name())
+            }
+        }
+
+        impl FromStr for $named {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $named::ALL
+                    .iter()
+                    .find(|named| named.name() == s.trim().to_lowercase())
+                    .copied()
+                    .ok_or_else(|| format!("unknown {} {s}, expected one of {}", $what, $named::ALL.iter().map($named::name).collect::<Vec<_>>().join(", ")))
+            }
+        }
+    };
+}
+
+named!(Rule, "rule");
+named!(Severity, "severity");
+
+/// Severity of a rule, parsed from `rule=severity`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RuleSeverity(pub Rule, pub Severity);
+
+impl FromStr for RuleSeverity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (rule, severity) = s.split_once('=').ok_or_else(|| format!("expected rule=severity, got {s}"))?;
+        Ok(RuleSeverity(rule.parse()?, severity.parse()?))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    /// Index of the part
+    pub part: usize,
+    /// Index of the line in the part, none for the whole part
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{} {} part {}", self.severity, self.rule, self.part + 1)?;
+        if let Some(line) = self.line {
+            write!(f, " line {}", line + 1)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+fn is_header(part: &[String]) -> bool {
+    match part {
+        [line] => {
+            let label = line.trim().trim_matches(|c: char| "*_[]():".contains(c)).trim().to_lowercase();
+            let word = label.split(|c: char| c.is_whitespace() || c.is_ascii_digit()).next().unwrap_or_default();
+            (HEADERS.contains(&word) && label.split_whitespace().count() <= 2)
+                || (line.trim().starts_with('[') && line.trim().ends_with(']'))
+        },
+        _ => false,
+    }
+}
+
+/// First letter after indentation and markup
+fn first_letter(line: &str) -> Option<char> {
+    line.chars().find(|c| !c.is_whitespace() && *c != '*').filter(|c| c.is_alphabetic())
+}
+
+/// Whether most lines with a cased first letter start in uppercase
+fn mostly_capitalized(lyric: &Lyric) -> bool {
+    let (upper, lower) = lyric.parts
+        .iter()
+        .flatten()
+        .filter_map(|line| first_letter(line))
+        .fold((0, 0), |(upper, lower), c| (upper + c.is_uppercase() as usize, lower + c.is_lowercase() as usize));
+    upper > lower
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Linter {
+    severities: BTreeMap<Rule, Severity>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self {
+            severities: Rule::ALL.iter().map(|rule| (*rule, Severity::Warning)).collect(),
+        }
+    }
+}
+
+impl Linter {
+    pub fn severity(mut self, rule: Rule, severity: Severity) -> Self {
+        self.severities.insert(rule, severity);
+        self
+    }
+
+    fn severity_of(&self, rule: Rule) -> Severity {
+        self.severities.get(&rule).copied().unwrap_or(Severity::Off)
+    }
+
+    fn on(&self, rule: Rule) -> bool {
+        self.severity_of(rule) != Severity::Off
+    }
+
+    pub fn check(&self, lyric: &Lyric) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let mut diagnostic = |rule: Rule, part: usize, line: Option<usize>, message: String| {
+            if self.on(rule) {
+                diagnostics.push(Diagnostic { rule, severity: self.severity_of(rule), part, line, message });
+            }
+        };
+        let capitalized = mostly_capitalized(lyric);
+        for (p, part) in lyric.parts.iter().enumerate() {
+            if is_header(part) {
+                diagnostic(Rule::HeaderPart, p, None, format!("{} is a header", part[0].trim()));
+            }
+            if p > 0 && lyric.parts[p - 1] == *part {
+                diagnostic(Rule::DuplicatePart, p, None, format!("part repeats part {p}"));
+            }
+            for (l, line) in part.iter().enumerate() {
+                if line.trim_end() != line {
+                    diagnostic(Rule::TrailingSpace, p, Some(l), "line ends in whitespace".to_owned());
+                }
+                if capitalized && first_letter(line).map(char::is_lowercase).unwrap_or_default() {
+                    diagnostic(Rule::Capitalization, p, Some(l), "line starts in lowercase".to_owned());
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Lyric with the fixes of every rule that is not off
+    pub fn fix(&self, lyric: &Lyric) -> Lyric {
+        let capitalize = self.on(Rule::Capitalization) && mostly_capitalized(lyric);
+        let capitalizer = Normalizer { capitalize: true, ..Normalizer::none() };
+        let mut parts: Vec<Vec<String>> = vec![];
+        let mut times = vec![];
+        for (p, part) in lyric.parts.iter().enumerate() {
+            if self.on(Rule::HeaderPart) && is_header(part) {
+                continue;
+            }
+            let part = part
+                .iter()
+                .map(|line| if self.on(Rule::TrailingSpace) { line.trim_end().to_owned() } else { line.clone() })
+                .map(|line| if capitalize { capitalizer.line(&line) } else { line })
+                .collect::<Vec<_>>();
+            if self.on(Rule::DuplicatePart) && parts.last() == Some(&part) {
+                continue;
+            }
+            parts.push(part);
+            times.push(lyric.times.as_ref().and_then(|times| times.get(p)).cloned().unwrap_or_default());
+        }
+        Lyric { parts, times: lyric.times.as_ref().map(|_| times), ..lyric.clone() }
+    }
+}
+
+/// Line diff of two texts with [`CONTEXT`] lines of context, empty if they are the same
+pub fn diff(old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] { common[i + 1][j + 1] + 1 } else { common[i + 1][j].max(common[i][j + 1]) };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i]));
+            i += 1;
+            j += 1;
+        }
+        else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i]));
+            i += 1;
+        }
+        else {
+            lines.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    let changed = lines.iter().enumerate().filter(|(_, (marker, _))| *marker != ' ').map(|(index, _)| index).collect::<Vec<_>>();
+    let shown = |index: usize| changed.iter().any(|changed| changed.abs_diff(index) <= CONTEXT);
+    let mut diff = String::new();
+    let mut gap = false;
+    for (index, (marker, line)) in lines.iter().enumerate() {
+        if shown(index) {
+            if gap && !diff.is_empty() {
+                diff.push_str("@@\n");
+            }
+            diff.push_str(&format!("{marker}{line}\n"));
+            gap = false;
+        }
+        else {
+            gap = true;
+        }
+    }
+    diff
+}
+
+/// Diff of the text of the parts of two lyrics
+pub fn diff_lyric(old: &Lyric, new: &Lyric) -> String {
+    diff(&parts::to_text(&old.parts), &parts::to_text(&new.parts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, Linter, Rule, RuleSeverity, Severity};
+    use crate::tests;
+
+    #[test]
+    fn check_and_fix() {
+        let lyric = tests::lyric("Zilveren maan", &[
+            &["Refrein:"],
+            &["Zilveren maan ", "boven de zee"],
+            &["Zilveren maan ", "boven de zee"],
+            &["'t Is stil", "De golven slapen"],
+        ]);
+        let linter = Linter::default();
+        let rules = linter.check(&lyric).into_iter().map(|diagnostic| diagnostic.rule).collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            vec![Rule::HeaderPart, Rule::TrailingSpace, Rule::Capitalization, Rule::DuplicatePart, Rule::TrailingSpace, Rule::Capitalization]
+        );
+
+        let fixed = linter.fix(&lyric);
+        assert_eq!(fixed.parts, vec![vec!["Zilveren maan", "Boven de zee"], vec!["'t Is stil", "De golven slapen"]]);
+        assert!(linter.check(&fixed).is_empty());
+
+        let RuleSeverity(rule, severity) = "header-part=off".parse().unwrap();
+        let linter = Linter::default().severity(rule, severity).severity(Rule::DuplicatePart, Severity::Error);
+        assert_eq!(linter.fix(&lyric).parts.len(), 3);
+        assert_eq!(linter.check(&lyric).iter().find(|diagnostic| diagnostic.rule == Rule::DuplicatePart).unwrap().to_string(), "error duplicate-part part 3: part repeats part 2");
+        assert!("spelling=error".parse::<RuleSeverity>().is_err());
+    }
+
+    #[test]
+    fn diffs() {
+        assert_eq!(diff("a\nb\nc\nd\ne\nf\ng", "a\nb\nc\nd\ne\nF\ng"), " d\n e\n-f\n+F\n g\n");
+        assert_eq!(diff("a\nb", "a\nb"), "");
+        assert_eq!(diff("1\n2\n3\n4\n5\n6\n7\n8", "0\n1\n2\n3\n4\n5\n6\n7"), "+0\n 1\n 2\n@@\n 6\n 7\n-8\n");
+    }
+}
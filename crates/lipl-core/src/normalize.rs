@@ -0,0 +1,132 @@
+/*!
+ Text normalisation of lyrics and playlist titles, see [`parts::normalize`] for the pipeline.
+
+ The [`NormalizeLayer`] normalizes every upsert, [`normalize_repo`] normalizes a whole repository at once.
+ */
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use crate::layer::{Forward, RepoLayer};
+use crate::{LiplRepo, Lyric, Playlist, Result};
+
+pub use parts::normalize::{Dashes, Normalizer, Quotes};
+
+/// Lines of a lyric with times are normalized one by one, so every line keeps its time
+pub fn lyric(normalizer: &Normalizer, lyric: Lyric) -> Lyric {
+    let parts = match lyric.times {
+        Some(_) => lyric.parts.iter().map(|part| part.iter().map(|line| normalizer.line(line)).collect()).collect(),
+        None => normalizer.parts(&lyric.parts),
+    };
+    Lyric {
+        id: lyric.id,
+        title: normalizer.title(&lyric.title),
+        parts,
+        extensions: lyric.extensions,
+        times: lyric.times,
+    }
+}
+
+pub fn playlist(normalizer: &Normalizer, playlist: Playlist) -> Playlist {
+    Playlist {
+        title: normalizer.title(&playlist.title),
+        ..playlist
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct NormalizeLayer {
+    normalizer: Arc<Normalizer>,
+}
+
+impl NormalizeLayer {
+    pub fn new(normalizer: Normalizer) -> Self {
+        Self { normalizer: Arc::new(normalizer) }
+    }
+}
+
+impl RepoLayer for NormalizeLayer {
+    fn layer(&self, inner: Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo> {
+        Arc::new(
+            NormalizeRepo { inner, normalizer: self.normalizer.clone() }
+        )
+    }
+}
+
+pub struct NormalizeRepo {
+    inner: Arc<dyn LiplRepo>,
+    normalizer: Arc<Normalizer>,
+}
+
+#[async_trait]
+impl Forward for NormalizeRepo {
+    fn inner(&self) -> &dyn LiplRepo {
+        self.inner.as_ref()
+    }
+
+    async fn upsert_lyric(&self, lyric: Lyric) -> Result<Lyric> {
+        self.inner.upsert_lyric(self::lyric(&self.normalizer, lyric)).await
+    }
+
+    async fn upsert_playlist(&self, playlist: Playlist) -> Result<Playlist> {
+        self.inner.upsert_playlist(self::playlist(&self.normalizer, playlist)).await
+    }
+}
+
+/// Normalizes every lyric and playlist, only changed ones are upserted unless `dry_run` is set.
+/// Returns the number of changed lyrics and playlists.
+pub async fn normalize_repo<R: LiplRepo + ?Sized>(repo: &R, normalizer: &Normalizer, dry_run: bool) -> Result<(usize, usize)> {
+    let mut count = (0, 0);
+    for summary in repo.get_lyric_summaries().await? {
+        let original = repo.get_lyric(summary.id).await?;
+        let normalized = lyric(normalizer, original.clone());
+        if normalized.title != original.title || normalized.parts != original.parts {
+            if !dry_run {
+                repo.upsert_lyric(normalized).await?;
+            }
+            count.0 += 1;
+        }
+    }
+    for original in repo.get_playlists().await? {
+        let normalized = playlist(normalizer, original.clone());
+        if normalized.title != original.title {
+            if !dry_run {
+                repo.upsert_playlist(normalized).await?;
+            }
+            count.1 += 1;
+        }
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::{normalize_repo, NormalizeLayer, Normalizer};
+    use crate::layer::{RepoBuilder, tests::Store};
+    use crate::{LiplRepo, Playlist, Uuid};
+    use crate::tests;
+
+    const TITLE: &str = "\u{2019}t Knaapje ";
+    const PARTS: &[&[&str]] = &[&["\u{2019}t Knaapje\u{00A0}zat", "op de\u{200B}\r\nmarkt"]];
+
+    #[tokio::test]
+    async fn upserts_normalized() {
+        let repo = RepoBuilder::new().layer(NormalizeLayer::new(Normalizer::default())).service(Arc::new(Store::default()));
+        let lyric = repo.upsert_lyric(tests::lyric(TITLE, PARTS)).await.unwrap();
+        assert_eq!(lyric.title, "'t Knaapje");
+        assert_eq!(lyric.parts, vec![vec!["'t Knaapje zat".to_owned(), "op de".to_owned(), "markt".to_owned()]]);
+    }
+
+    #[tokio::test]
+    async fn bulk() {
+        let repo = Store::default();
+        let lyric = repo.upsert_lyric(tests::lyric(TITLE, PARTS)).await.unwrap();
+        repo.upsert_playlist(Playlist { id: Uuid::default(), title: "Kermis".to_owned(), members: vec![lyric.id] }).await.unwrap();
+
+        assert_eq!(normalize_repo(&repo, &Normalizer::default(), true).await.unwrap(), (1, 0));
+        assert_eq!(repo.get_lyric(lyric.id).await.unwrap().title, lyric.title);
+        assert_eq!(normalize_repo(&repo, &Normalizer::default(), false).await.unwrap(), (1, 0));
+        assert_eq!(repo.get_lyric(lyric.id).await.unwrap().title, "'t Knaapje");
+        assert_eq!(normalize_repo(&repo, &Normalizer::default(), false).await.unwrap(), (0, 0));
+    }
+}
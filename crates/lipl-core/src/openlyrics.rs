@@ -0,0 +1,342 @@
+/*!
+ OpenLyrics XML, the exchange format of OpenLP, FreeWorship and other presentation software.
+
+ A [`Song`] holds the parts of an OpenLyrics document lipl knows about:
+ titles, authors, verses and the verse order.
+ Converting a song to a [`LyricPost`] takes the first title and plays the verses in verse order,
+ or in document order without a verse order. Only the verses in the language of the first verse are played,
+ translations are left out. The authors are kept in the extension [`AUTHORS`].
+ Converting a [`Lyric`] to a song names the parts `v1`, `v2`, ... and writes the authors back.
+
+ [`is_openlyrics`] tells whether an `Accept` or `Content-Type` header names OpenLyrics,
+ [`lyric_post`] reads a request body with OpenLyrics.
+
+ Chords and comments inside the lines are skipped when reading, text within a chord element is kept.
+ */
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::str::FromStr;
+use roxmltree::{Document, Node};
+use serde_yaml::Value;
+use crate::{Error, Extensions, Lyric, LyricPost};
+
+pub const CONTENT_TYPE: &str = "application/openlyrics+xml";
+pub const EXTENSION: &str = "xml";
+
+/// Extension with the authors of a song, a list of names
+pub const AUTHORS: &str = "authors";
+
+const NAMESPACE: &str = "http://openlyrics.info/namespace/2009/song";
+const VERSION: &str = "0.9";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Song {
+    pub titles: Vec<String>,
+    pub authors: Vec<String>,
+    pub verse_order: Vec<String>,
+    pub verses: Vec<Verse>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Verse {
+    pub name: String,
+    pub lang: Option<String>,
+    pub lines: Vec<String>,
+}
+
+/// Whether one of the media types in a header value like `Accept` or `Content-Type` is OpenLyrics
+pub fn is_openlyrics(header: &str) -> bool {
+    header
+        .split(',')
+        .any(|media_type| media_type.split(';').next().map(str::trim) == Some(CONTENT_TYPE))
+}
+
+/// Lyric post from OpenLyrics xml in UTF-8
+pub fn lyric_post(bytes: &[u8]) -> Result<LyricPost, Error> {
+    core::str::from_utf8(bytes)
+        .map_err(|error| Error::OpenLyrics(error.to_string()))?
+        .parse::<Song>()
+        .map(LyricPost::from)
+}
+
+fn children<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children().filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn descendants<'a, 'input>(node: Node<'a, 'input>, name: &'a str) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.descendants().filter(move |child| child.is_element() && child.tag_name().name() == name)
+}
+
+fn text(node: Node) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Text of a `<lines>` element, split on `<br/>`
+fn lines(node: Node, acc: &mut Vec<String>) {
+    for child in node.children() {
+        if child.is_text() {
+            if let Some(last) = acc.last_mut() {
+                last.push_str(child.text().unwrap_or_default());
+            }
+        }
+        else if child.is_element() {
+            match child.tag_name().name() {
+                "br" => acc.push(String::new()),
+                "comment" => {},
+                _ => lines(child, acc),
+            }
+        }
+    }
+}
+
+fn verse(node: Node) -> Verse {
+    let mut acc = vec![];
+    for line in children(node, "lines").chain(children(node, "line")) {
+        acc.push(String::new());
+        lines(line, &mut acc);
+    }
+    Verse {
+        name: node.attribute("name").unwrap_or_default().to_owned(),
+        lang: node.attribute("lang").map(String::from),
+        lines:
+            acc
+            .into_iter()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect(),
+    }
+}
+
+/// Authors in the extensions, a single name is accepted as well
+fn authors(extensions: &Extensions) -> Vec<String> {
+    match extensions.get(AUTHORS) {
+        Some(Value::Sequence(names)) => names.iter().filter_map(Value::as_str).map(String::from).collect(),
+        Some(Value::String(name)) => vec![name.clone()],
+        _ => vec![],
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.chars().fold(String::with_capacity(s.len()), |mut acc, c| {
+        match c {
+            '&' => acc.push_str("&amp;"),
+            '<' => acc.push_str("&lt;"),
+            '>' => acc.push_str("&gt;"),
+            '"' => acc.push_str("&quot;"),
+            '\'' => acc.push_str("&apos;"),
+            _ => acc.push(c),
+        };
+        acc
+    })
+}
+
+impl FromStr for Song {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let document = Document::parse(s).map_err(|error| Error::OpenLyrics(error.to_string()))?;
+        let song = document.root_element();
+        if song.tag_name().name() != "song" {
+            return Err(Error::OpenLyrics("root element is not song".to_owned()));
+        }
+        let properties = children(song, "properties").next();
+        Ok(
+            Self {
+                titles: properties.into_iter().flat_map(|node| descendants(node, "title")).map(text).collect(),
+                authors: properties.into_iter().flat_map(|node| descendants(node, "author")).map(text).collect(),
+                verse_order:
+                    properties
+                    .into_iter()
+                    .flat_map(|node| children(node, "verseOrder"))
+                    .flat_map(|node| text(node).split_whitespace().map(String::from).collect::<Vec<_>>())
+                    .collect(),
+                verses: children(song, "lyrics").flat_map(|node| children(node, "verse")).map(verse).collect(),
+            }
+        )
+    }
+}
+
+impl Display for Song {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        writeln!(f, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(f, "<song xmlns=\"{NAMESPACE}\" version=\"{VERSION}\" createdIn=\"lipl\" modifiedIn=\"lipl\">")?;
+        writeln!(f, "  <properties>")?;
+        writeln!(f, "    <titles>")?;
+        for title in self.titles.iter() {
+            writeln!(f, "      <title>{}</title>", escape(title))?;
+        }
+        writeln!(f, "    </titles>")?;
+        if !self.authors.is_empty() {
+            writeln!(f, "    <authors>")?;
+            for author in self.authors.iter() {
+                writeln!(f, "      <author>{}</author>", escape(author))?;
+            }
+            writeln!(f, "    </authors>")?;
+        }
+        if !self.verse_order.is_empty() {
+            writeln!(f, "    <verseOrder>{}</verseOrder>", escape(&self.verse_order.join(" ")))?;
+        }
+        writeln!(f, "  </properties>")?;
+        writeln!(f, "  <lyrics>")?;
+        for verse in self.verses.iter() {
+            let lines = verse.lines.iter().map(|line| escape(line)).collect::<Vec<_>>().join("<br/>");
+            match &verse.lang {
+                Some(lang) => writeln!(f, "    <verse name=\"{}\" lang=\"{}\">", escape(&verse.name), escape(lang))?,
+                None => writeln!(f, "    <verse name=\"{}\">", escape(&verse.name))?,
+            }
+            writeln!(f, "      <lines>{lines}</lines>")?;
+            writeln!(f, "    </verse>")?;
+        }
+        writeln!(f, "  </lyrics>")?;
+        write!(f, "</song>")
+    }
+}
+
+impl From<Song> for LyricPost {
+    fn from(song: Song) -> Self {
+        let lang = song.verses.first().and_then(|verse| verse.lang.clone());
+        let verses = song.verses.iter().filter(|verse| verse.lang == lang).collect::<Vec<_>>();
+        let parts =
+            if song.verse_order.is_empty() {
+                verses.iter().map(|verse| verse.lines.clone()).collect()
+            }
+            else {
+                song.verse_order
+                    .iter()
+                    .filter_map(|name| verses.iter().find(|verse| verse.name.eq_ignore_ascii_case(name)))
+                    .map(|verse| verse.lines.clone())
+                    .collect()
+            };
+        let mut extensions = Extensions::new();
+        if !song.authors.is_empty() {
+            extensions.insert(AUTHORS.to_owned(), Value::Sequence(song.authors.into_iter().map(Value::String).collect()));
+        }
+        Self {
+            title: song.titles.into_iter().next().unwrap_or_default(),
+            parts,
+            extensions,
+            times: None,
+        }
+    }
+}
+
+impl From<&Lyric> for Song {
+    fn from(lyric: &Lyric) -> Self {
+        let verses =
+            lyric.parts
+            .iter()
+            .enumerate()
+            .map(|(index, part)| Verse { name: format!("v{}", index + 1), lang: None, lines: part.clone() })
+            .collect::<Vec<_>>();
+        Self {
+            titles: vec![lyric.title.clone()],
+            authors: authors(&lyric.extensions),
+            verse_order: verses.iter().map(|verse| verse.name.clone()).collect(),
+            verses,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_openlyrics, Song, AUTHORS};
+    use crate::{Lyric, LyricPost, Uuid};
+
+    const AMAZING_GRACE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<song xmlns="http://openlyrics.info/namespace/2009/song" version="0.8">
+  <properties>
+    <titles>
+      <title>Amazing Grace</title>
+      <title lang="nl">Verwonderlijk genade</title>
+    </titles>
+    <authors>
+      <author>John Newton</author>
+    </authors>
+    <verseOrder>v1 c v2 c</verseOrder>
+  </properties>
+  <lyrics>
+    <verse name="v1">
+      <lines>
+        <chord name="G"/>Amazing grace how sweet the sound<br/>
+        That saved a wretch like me<comment>softly</comment>
+      </lines>
+    </verse>
+    <verse name="v2">
+      <lines>'Twas <chord name="D">grace</chord> that taught my heart to fear<br/>And grace my fears relieved</lines>
+    </verse>
+    <verse name="v2" lang="nl">
+      <lines>'Twas grace that taught my heart to fear<br/>And grace my fears relieved</lines>
+    </verse>
+    <verse name="c">
+      <lines>Refrain &amp; more</lines>
+    </verse>
+  </lyrics>
+</song>"#;
+
+    #[test]
+    fn parse_in_verse_order() {
+        let song = AMAZING_GRACE.parse::<Song>().unwrap();
+        assert_eq!(song.authors, vec!["John Newton".to_owned()]);
+        assert_eq!(song.titles.len(), 2);
+
+        let lyric_post = LyricPost::from(song);
+        assert_eq!(lyric_post.title, "Amazing Grace");
+        assert_eq!(lyric_post.extensions[AUTHORS], serde_yaml::from_str::<serde_yaml::Value>("[John Newton]").unwrap());
+        assert_eq!(
+            lyric_post.parts,
+            vec![
+                vec!["Amazing grace how sweet the sound".to_owned(), "That saved a wretch like me".to_owned()],
+                vec!["Refrain & more".to_owned()],
+                vec!["'Twas grace that taught my heart to fear".to_owned(), "And grace my fears relieved".to_owned()],
+                vec!["Refrain & more".to_owned()],
+            ]
+        );
+    }
+
+    #[test]
+    fn one_language_without_verse_order() {
+        let xml = AMAZING_GRACE.replace("<verseOrder>v1 c v2 c</verseOrder>", "").replace("<verse name=\"v1\">", "<verse name=\"v1\" lang=\"en\">");
+        let xml = xml.replace("<verse name=\"v2\">", "<verse name=\"v2\" lang=\"en\">").replace("<verse name=\"c\">", "<verse name=\"c\" lang=\"en\">");
+        let lyric_post = LyricPost::from(xml.parse::<Song>().unwrap());
+        assert_eq!(lyric_post.parts.len(), 3);
+        assert_eq!(lyric_post.parts[2], vec!["Refrain & more".to_owned()]);
+    }
+
+    #[test]
+    fn accepts_openlyrics() {
+        assert!(is_openlyrics("text/html, application/openlyrics+xml;q=0.9"));
+        assert!(is_openlyrics("application/openlyrics+xml; charset=utf-8"));
+        assert!(!is_openlyrics("application/json"));
+    }
+
+    #[test]
+    fn lyric_round_trip() {
+        let lyric = Lyric {
+            id: Uuid::default(),
+            title: "Jan <Klaassen> & Katrijn".to_owned(),
+            parts: vec![vec!["Regel 1".to_owned(), "Regel 2".to_owned()], vec!["Regel 3".to_owned()]],
+            extensions: [(AUTHORS.to_owned(), serde_yaml::from_str("[Anoniem, Jan & Co]").unwrap())].into(),
+            times: None,
+        };
+        let xml = Song::from(&lyric).to_string();
+        let lyric_post = LyricPost::from(xml.parse::<Song>().unwrap());
+        assert_eq!(lyric_post.title, lyric.title);
+        assert_eq!(lyric_post.parts, lyric.parts);
+        assert_eq!(lyric_post.extensions, lyric.extensions);
+
+        let lyric = Lyric { extensions: [(AUTHORS.to_owned(), "Anoniem".into())].into(), ..lyric };
+        assert_eq!(Song::from(&lyric).authors, vec!["Anoniem".to_owned()]);
+    }
+
+    #[test]
+    fn not_a_song() {
+        assert!("<hymn/>".parse::<Song>().is_err());
+        assert!("<song>".parse::<Song>().is_err());
+    }
+}
@@ -0,0 +1,420 @@
+/*!
+ OpenSong song and set files.
+
+ The lyrics of an OpenSong song are one text with section markers like `[V1]` and `[C]`,
+ lines starting with `.` hold chords, lines starting with `;` are comments and lyric lines start with a space.
+ A section can hold several verses by numbering its lines, `1` and `2` in section `[V]` become `V1` and `V2`.
+ The presentation order plays the sections, without one they are played in order of appearance.
+ A presentation entry without a section of its own plays the numbered sections, `V` plays `V1` and `V2`.
+
+ Converting a [`Lyric`] to a song labels the parts that occur more than once as chorus `C1`, `C2`, ...
+ and the others as verse `V1`, `V2`, ..., so the presentation order keeps the repeats.
+
+ [`import_dir`] reads an OpenSong directory with `Songs` and `Sets` into a [`Report`],
+ a set becomes a [`Playlist`] with the songs it refers to as members.
+ */
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::str::FromStr;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use roxmltree::{Document, Node};
+use crate::{Error, Lyric, LyricPost, Playlist, Result, Uuid};
+use crate::import::Report;
+
+pub const SONGS: &str = "Songs";
+pub const SETS: &str = "Sets";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Song {
+    pub title: String,
+    pub author: String,
+    pub presentation: Vec<String>,
+    pub sections: Vec<Section>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Section {
+    pub label: String,
+    pub lines: Vec<String>,
+}
+
+/// Song in a set, `path` is the folder below `Songs`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SetItem {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Set {
+    pub name: String,
+    pub songs: Vec<SetItem>,
+}
+
+fn child_text(node: Node, name: &str) -> String {
+    node.children()
+        .find(|child| child.is_element() && child.tag_name().name() == name)
+        .and_then(|child| child.text())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn clean(line: &str) -> String {
+    line.replace("||", " ")
+        .replace(['|', '_'], " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn section<'a>(sections: &'a mut Vec<Section>, label: &str) -> &'a mut Section {
+    match sections.iter().position(|section| section.label == label) {
+        Some(index) => &mut sections[index],
+        None => {
+            sections.push(Section { label: label.to_owned(), lines: vec![] });
+            sections.last_mut().unwrap()
+        }
+    }
+}
+
+fn parse_lyrics(text: &str) -> Vec<Section> {
+    let mut sections = vec![];
+    let mut label = "V".to_owned();
+    for line in text.lines() {
+        if let Some(marker) = line.trim().strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            label = marker.trim().to_owned();
+            section(&mut sections, &label);
+            continue;
+        }
+        match line.chars().next() {
+            None | Some('.') | Some(';') => {},
+            Some(digit) if digit.is_ascii_digit() => {
+                let numbered = format!("{label}{digit}");
+                let line = clean(&line[1..]);
+                if !line.is_empty() {
+                    section(&mut sections, &numbered).lines.push(line);
+                }
+            },
+            _ => {
+                let line = clean(line);
+                if !line.is_empty() {
+                    section(&mut sections, &label).lines.push(line);
+                }
+            },
+        }
+    }
+    sections.retain(|section| !section.lines.is_empty());
+    sections
+}
+
+/// The section with the label, or else the sections numbered after it in order of appearance
+fn played<'a>(sections: &'a [Section], label: &str) -> Vec<&'a Section> {
+    match sections.iter().find(|section| section.label.eq_ignore_ascii_case(label)) {
+        Some(section) => vec![section],
+        None =>
+            sections
+            .iter()
+            .filter(|section|
+                section.label.len() > label.len()
+                && section.label.is_char_boundary(label.len())
+                && section.label[..label.len()].eq_ignore_ascii_case(label)
+                && section.label[label.len()..].chars().all(|c| c.is_ascii_digit())
+            )
+            .collect(),
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn parse_error(error: roxmltree::Error) -> Error {
+    Error::OpenSong(error.to_string())
+}
+
+impl FromStr for Song {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let document = Document::parse(s).map_err(parse_error)?;
+        let song = document.root_element();
+        if song.tag_name().name() != "song" {
+            return Err(Error::OpenSong("root element is not song".to_owned()));
+        }
+        Ok(
+            Self {
+                title: child_text(song, "title").trim().to_owned(),
+                author: child_text(song, "author").trim().to_owned(),
+                presentation: child_text(song, "presentation").split_whitespace().map(String::from).collect(),
+                sections: parse_lyrics(&child_text(song, "lyrics")),
+            }
+        )
+    }
+}
+
+impl Display for Song {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let lyrics =
+            self.sections
+            .iter()
+            .map(|section|
+                core::iter::once(format!("[{}]", section.label))
+                .chain(section.lines.iter().map(|line| format!(" {line}")))
+                .collect::<Vec<_>>()
+                .join("\n")
+            )
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        writeln!(f, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(f, "<song>")?;
+        writeln!(f, "  <title>{}</title>", escape(&self.title))?;
+        writeln!(f, "  <author>{}</author>", escape(&self.author))?;
+        writeln!(f, "  <presentation>{}</presentation>", escape(&self.presentation.join(" ")))?;
+        writeln!(f, "  <lyrics>{}</lyrics>", escape(&lyrics))?;
+        write!(f, "</song>")
+    }
+}
+
+impl From<Song> for LyricPost {
+    fn from(song: Song) -> Self {
+        let parts =
+            if song.presentation.is_empty() {
+                song.sections.iter().map(|section| section.lines.clone()).collect()
+            }
+            else {
+                song.presentation
+                    .iter()
+                    .flat_map(|label| played(&song.sections, label))
+                    .map(|section| section.lines.clone())
+                    .collect()
+            };
+        Self {
+            title: song.title,
+            parts,
+            extensions: Default::default(),
+            times: None,
+        }
+    }
+}
+
+impl From<&Lyric> for Song {
+    fn from(lyric: &Lyric) -> Self {
+        let mut sections: Vec<Section> = vec![];
+        let mut presentation = vec![];
+        let (mut verses, mut choruses) = (0, 0);
+        for part in lyric.parts.iter() {
+            match sections.iter().find(|section| section.lines == *part) {
+                Some(section) => presentation.push(section.label.clone()),
+                None => {
+                    let label =
+                        if lyric.parts.iter().filter(|other| *other == part).count() > 1 {
+                            choruses += 1;
+                            format!("C{choruses}")
+                        }
+                        else {
+                            verses += 1;
+                            format!("V{verses}")
+                        };
+                    presentation.push(label.clone());
+                    sections.push(Section { label, lines: part.clone() });
+                }
+            }
+        }
+        Self {
+            title: lyric.title.clone(),
+            author: String::new(),
+            presentation,
+            sections,
+        }
+    }
+}
+
+impl FromStr for Set {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let document = Document::parse(s).map_err(parse_error)?;
+        let set = document.root_element();
+        if set.tag_name().name() != "set" {
+            return Err(Error::OpenSong("root element is not set".to_owned()));
+        }
+        Ok(
+            Self {
+                name: set.attribute("name").unwrap_or_default().to_owned(),
+                songs:
+                    set.descendants()
+                    .filter(|node| node.is_element() && node.tag_name().name() == "slide_group")
+                    .filter(|node| node.attribute("type") == Some("song"))
+                    .map(|node| SetItem {
+                        name: node.attribute("name").unwrap_or_default().to_owned(),
+                        path: node.attribute("path").unwrap_or_default().trim_matches('/').to_owned(),
+                    })
+                    .collect(),
+            }
+        )
+    }
+}
+
+/// Files in a directory and its subdirectories, sorted
+fn files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut acc = vec![];
+    if dir.is_dir() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                acc.extend(files(&path)?);
+            }
+            else {
+                acc.push(path);
+            }
+        }
+    }
+    acc.sort();
+    Ok(acc)
+}
+
+/// Reports every song below `Songs` as a new lyric, with its author, and every set below `Sets` as a new playlist.
+/// Files that are no song or set and set items without a song are reported as skipped.
+pub fn import_dir(root: &Path) -> Result<Report> {
+    let songs_dir = root.join(SONGS);
+    let mut report = Report::default();
+    let mut by_path: HashMap<String, Uuid> = HashMap::new();
+
+    for file in files(&songs_dir)? {
+        match std::fs::read_to_string(&file).map_err(Error::from).and_then(|s| s.parse::<Song>()) {
+            Ok(song) => {
+                let authors = if song.author.is_empty() { vec![] } else { vec![song.author.clone()] };
+                let lyric = Lyric::from((None, LyricPost::from(song)));
+                let relative = file.strip_prefix(&songs_dir).unwrap_or(&file).to_string_lossy().replace('\\', "/");
+                by_path.insert(relative, lyric.id);
+                report.lyrics.push((lyric, authors));
+            },
+            Err(error) => report.skipped.push(format!("{}: {error}", file.to_string_lossy())),
+        }
+    }
+
+    for file in files(&root.join(SETS))? {
+        match std::fs::read_to_string(&file).map_err(Error::from).and_then(|s| s.parse::<Set>()) {
+            Ok(set) => {
+                let mut members = vec![];
+                for item in set.songs.iter() {
+                    let relative = if item.path.is_empty() { item.name.clone() } else { format!("{}/{}", item.path, item.name) };
+                    match by_path.get(&relative) {
+                        Some(id) => members.push(*id),
+                        None => report.skipped.push(format!("{}: {relative} not found", set.name)),
+                    }
+                }
+                report.playlists.push(Playlist { id: Uuid::default(), title: set.name, members });
+            },
+            Err(error) => report.skipped.push(format!("{}: {error}", file.to_string_lossy())),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{import_dir, Set, Song, SETS, SONGS};
+    use crate::layer::tests::Store;
+    use crate::{LiplRepo, Lyric, LyricPost, Uuid};
+    use std::sync::Arc;
+
+    const AMAZING_GRACE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<song>
+  <title>Amazing Grace</title>
+  <author>John Newton</author>
+  <presentation>V1 C V2 C</presentation>
+  <lyrics>[V]
+.G        C          G
+1 Amazing grace how sweet | the sound
+1 That saved a wretch like me
+2 'Twas grace that taught my heart to fear
+;comment
+[C]
+ Refrain &amp; more</lyrics>
+</song>"#;
+
+    const SUNDAY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<set name="Sunday">
+  <slide_groups>
+    <slide_group name="Amazing Grace" type="song" path="Hymns/"/>
+    <slide_group name="Welcome" type="custom"><slides/></slide_group>
+    <slide_group name="Missing" type="song" path=""/>
+  </slide_groups>
+</set>"#;
+
+    #[test]
+    fn parse_numbered_verses_in_presentation_order() {
+        let song = AMAZING_GRACE.parse::<Song>().unwrap();
+        assert_eq!(song.sections.iter().map(|s| s.label.as_str()).collect::<Vec<_>>(), vec!["V1", "V2", "C"]);
+        let lyric_post = LyricPost::from(song);
+        assert_eq!(lyric_post.parts.len(), 4);
+        assert_eq!(lyric_post.parts[0], vec!["Amazing grace how sweet the sound".to_owned(), "That saved a wretch like me".to_owned()]);
+        assert_eq!(lyric_post.parts[1], vec!["Refrain & more".to_owned()]);
+        assert_eq!(lyric_post.parts[1], lyric_post.parts[3]);
+    }
+
+    #[test]
+    fn presentation_plays_numbered_verses() {
+        let song = AMAZING_GRACE.replace("V1 C V2 C", "V C").parse::<Song>().unwrap();
+        let lyric_post = LyricPost::from(song);
+        assert_eq!(lyric_post.parts.len(), 3);
+        assert_eq!(lyric_post.parts[0][0], "Amazing grace how sweet the sound");
+        assert_eq!(lyric_post.parts[1], vec!["'Twas grace that taught my heart to fear".to_owned()]);
+        assert_eq!(lyric_post.parts[2], vec!["Refrain & more".to_owned()]);
+    }
+
+    #[test]
+    fn lyric_round_trip_keeps_repeats() {
+        let chorus = vec!["Refrein".to_owned()];
+        let lyric = Lyric {
+            id: Uuid::default(),
+            title: "Heer & <Knecht>".to_owned(),
+            parts: vec![vec!["Een".to_owned()], chorus.clone(), vec!["Twee".to_owned()], chorus],
+            extensions: Default::default(),
+            times: None,
+        };
+        let song = Song::from(&lyric);
+        assert_eq!(song.presentation, vec!["V1", "C1", "V2", "C1"]);
+
+        let lyric_post = LyricPost::from(song.to_string().parse::<Song>().unwrap());
+        assert_eq!(lyric_post.title, lyric.title);
+        assert_eq!(lyric_post.parts, lyric.parts);
+    }
+
+    #[test]
+    fn parse_set() {
+        let set = SUNDAY.parse::<Set>().unwrap();
+        assert_eq!(set.name, "Sunday");
+        assert_eq!(set.songs.len(), 2);
+        assert_eq!(set.songs[0].path, "Hymns");
+    }
+
+    #[tokio::test]
+    async fn import_songs_and_sets() {
+        let root = std::env::temp_dir().join(format!("lipl-opensong-{}", Uuid::default()));
+        std::fs::create_dir_all(root.join(SONGS).join("Hymns")).unwrap();
+        std::fs::create_dir_all(root.join(SETS)).unwrap();
+        std::fs::write(root.join(SONGS).join("Hymns").join("Amazing Grace"), AMAZING_GRACE).unwrap();
+        std::fs::write(root.join(SONGS).join("notes.txt"), "no song").unwrap();
+        std::fs::write(root.join(SETS).join("Sunday"), SUNDAY).unwrap();
+
+        let report = import_dir(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let report = report.unwrap();
+        assert_eq!(report.lyrics.len(), 1);
+        assert_eq!(report.lyrics[0].1, vec!["John Newton".to_owned()]);
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(report.playlists[0].title, "Sunday");
+        assert_eq!(report.playlists[0].members, vec![report.lyrics[0].0.id]);
+
+        let repo = Arc::new(Store::default());
+        let report = report.import(repo.clone(), true).await.unwrap();
+        assert!(repo.get_lyric_summaries().await.unwrap().is_empty());
+        report.import(repo.clone(), false).await.unwrap();
+        assert_eq!(repo.get_playlist_summaries().await.unwrap().len(), 1);
+    }
+}
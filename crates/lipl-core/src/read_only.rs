@@ -0,0 +1,142 @@
+/*!
+ Read-only mode for a [`LiplRepo`].
+
+ While the [`ReadOnlySwitch`] is on, every upsert and delete is rejected with [`Error::ReadOnly`],
+ reads are passed on to the inner repository. The switch can be cloned and toggled at runtime,
+ for example with [`switch_on_signal`] during a backup or migration.
+ */
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use async_trait::async_trait;
+use crate::layer::{Forward, RepoLayer};
+use crate::{Error, LiplRepo, Lyric, Playlist, Result, Uuid};
+
+#[derive(Clone, Debug, Default)]
+pub struct ReadOnlySwitch(Arc<AtomicBool>);
+
+impl ReadOnlySwitch {
+    pub fn new(read_only: bool) -> Self {
+        Self(Arc::new(AtomicBool::new(read_only)))
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    pub fn set(&self, read_only: bool) {
+        self.0.store(read_only, Ordering::SeqCst);
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.is_read_only() {
+            Err(Error::ReadOnly)
+        }
+        else {
+            Ok(())
+        }
+    }
+}
+
+/// Turns the switch on at SIGUSR1 and off at SIGUSR2, as long as signals can be received
+#[cfg(all(feature = "signal", unix))]
+pub async fn switch_on_signal(switch: ReadOnlySwitch) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let signals =
+        signal(SignalKind::user_defined1())
+        .and_then(|usr1| signal(SignalKind::user_defined2()).map(|usr2| (usr1, usr2)));
+
+    match signals {
+        Ok((mut usr1, mut usr2)) => {
+            loop {
+                let on = tokio::select! {
+                    Some(_) = usr1.recv() => true,
+                    Some(_) = usr2.recv() => false,
+                    else => break,
+                };
+                switch.set(on);
+                tracing::info!("{}", if on { "Read-only mode" } else { "Read-write mode" });
+            }
+        },
+        Err(error) => {
+            tracing::error!("Error receiving signal: {}", error);
+        }
+    }
+}
+
+#[cfg(all(feature = "signal", not(unix)))]
+pub async fn switch_on_signal(_switch: ReadOnlySwitch) { }
+
+#[derive(Clone, Default)]
+pub struct ReadOnlyLayer {
+    switch: ReadOnlySwitch,
+}
+
+impl ReadOnlyLayer {
+    pub fn new(switch: ReadOnlySwitch) -> Self {
+        Self { switch }
+    }
+}
+
+impl RepoLayer for ReadOnlyLayer {
+    fn layer(&self, inner: Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo> {
+        Arc::new(
+            ReadOnlyRepo { inner, switch: self.switch.clone() }
+        )
+    }
+}
+
+pub struct ReadOnlyRepo {
+    inner: Arc<dyn LiplRepo>,
+    switch: ReadOnlySwitch,
+}
+
+#[async_trait]
+impl Forward for ReadOnlyRepo {
+    fn inner(&self) -> &dyn LiplRepo {
+        self.inner.as_ref()
+    }
+
+    async fn upsert_lyric(&self, lyric: Lyric) -> Result<Lyric> {
+        self.switch.check()?;
+        self.inner.upsert_lyric(lyric).await
+    }
+
+    async fn delete_lyric(&self, id: Uuid) -> Result<()> {
+        self.switch.check()?;
+        self.inner.delete_lyric(id).await
+    }
+
+    async fn upsert_playlist(&self, playlist: Playlist) -> Result<Playlist> {
+        self.switch.check()?;
+        self.inner.upsert_playlist(playlist).await
+    }
+
+    async fn delete_playlist(&self, id: Uuid) -> Result<()> {
+        self.switch.check()?;
+        self.inner.delete_playlist(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::{ReadOnlyLayer, ReadOnlySwitch};
+    use crate::layer::{RepoBuilder, tests::Empty};
+    use crate::{Error, Uuid};
+    use crate::tests::lyric;
+
+    #[tokio::test]
+    async fn writes_rejected_while_read_only() {
+        let switch = ReadOnlySwitch::new(true);
+        let repo = RepoBuilder::new().layer(ReadOnlyLayer::new(switch.clone())).service(Arc::new(Empty));
+
+        assert!(matches!(repo.upsert_lyric(lyric("Sofietje", &[])).await, Err(Error::ReadOnly)));
+        assert!(matches!(repo.delete_playlist(Uuid::default()).await, Err(Error::ReadOnly)));
+        assert!(repo.get_lyrics().await.unwrap().is_empty());
+
+        switch.set(false);
+        assert_eq!(repo.upsert_lyric(lyric("Sofietje", &[])).await.unwrap().title, "Sofietje".to_owned());
+    }
+}
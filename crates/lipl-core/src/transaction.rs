@@ -1,12 +1,19 @@
 use std::{io::{BufReader, BufRead}, thread::JoinHandle};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::mpsc::Sender;
 
+use async_trait::async_trait;
 use chrono::SecondsFormat;
 use serde::{Deserialize, Serialize};
 use crate::{Lyric, Playlist, Summary, Uuid, LiplRepo};
+use crate::layer::{Forward, RepoLayer};
 
 pub type ResultSender<T> = futures::channel::oneshot::Sender<crate::Result<T>>;
 pub type OptionalTransaction = Option<Transaction>;
 type LogRecord = (String, Transaction);
+type LogWriter = (Sender<Transaction>, JoinHandle<crate::Result<()>>);
 
 #[derive(Debug)]
 pub enum Request {
@@ -75,42 +82,51 @@ fn write<W>(w: &mut W, json: String) -> crate::Result<()>
 where
     W: std::io::Write,
 {
-    w.write_fmt(format_args!("{}\n", json))?;
+    w.write_all(format!("{json}\n").as_bytes())?;
     w.flush()?;
     Ok(())
 }
 
+fn is_blank(line: &std::io::Result<String>) -> bool {
+    line.as_ref().map(|s| s.trim().is_empty()).unwrap_or_default()
+}
+
 fn line_to_transaction(line: std::io::Result<String>) -> crate::Result<Transaction> {
     line.map_err(crate::Error::from)
         .and_then(|s| s.parse::<Transaction>())
 }
 
-pub async fn build_from_log<R, DB>(r: R, db: DB) -> crate::Result<()>
+/// Transactions in a log, oldest first, blank lines are skipped
+pub fn read_log<R>(r: R) -> crate::Result<Vec<Transaction>>
 where
     R: std::io::Read,
-    DB: LiplRepo,
 {
-    let transactions = BufReader::new(r)
+    BufReader::new(r)
         .lines()
+        .filter(|line| !is_blank(line))
         .map(line_to_transaction)
-        .collect::<crate::Result<Vec<_>>>()?;
-    
-    for transaction in transactions {
-        match transaction {
-            Transaction::LyricDelete(id) => {
-                db.delete_lyric(id).await?;
-            },
-            Transaction::LyricUpsert(lyric) => {
-                db.upsert_lyric(lyric).await?;
-            },
-            Transaction::PlaylistDelete(id) => {
-                db.delete_playlist(id).await?;
-            },
-            Transaction::PlaylistUpsert(playlist) => {
-                db.upsert_playlist(playlist).await?;
-            }
+        .collect()
+}
 
-        }
+pub async fn apply<DB>(transaction: Transaction, db: &DB) -> crate::Result<()>
+where
+    DB: LiplRepo + ?Sized,
+{
+    match transaction {
+        Transaction::LyricDelete(id) => db.delete_lyric(id).await,
+        Transaction::LyricUpsert(lyric) => db.upsert_lyric(lyric).await.map(|_| ()),
+        Transaction::PlaylistDelete(id) => db.delete_playlist(id).await,
+        Transaction::PlaylistUpsert(playlist) => db.upsert_playlist(playlist).await.map(|_| ()),
+    }
+}
+
+pub async fn build_from_log<R, DB>(r: R, db: &DB) -> crate::Result<()>
+where
+    R: std::io::Read,
+    DB: LiplRepo + ?Sized,
+{
+    for transaction in read_log(r)? {
+        apply(transaction, db).await?;
     }
     Ok(())
 }
@@ -137,4 +153,318 @@ where
         Ok::<(), crate::Error>(())
     });
     (join_handle, log_tx)
-}
\ No newline at end of file
+}
+
+/// Log written by a thread, shared by everything that logs to it
+#[derive(Clone)]
+pub struct TransactionLog {
+    writer: Arc<Mutex<Option<LogWriter>>>,
+}
+
+impl TransactionLog {
+    pub fn start<W>(log: W) -> Self
+    where
+        W: std::io::Write + Send + Sync + 'static,
+    {
+        let (join_handle, log_tx) = start_log_thread(log);
+        Self { writer: Arc::new(Mutex::new(Some((log_tx, join_handle)))) }
+    }
+
+    pub fn send(&self, transaction: Transaction) {
+        match self.writer.lock().unwrap_or_else(PoisonError::into_inner).as_ref() {
+            Some((log_tx, _)) => {
+                if let Err(error) = log_tx.send(transaction) {
+                    tracing::error!("Error transaction logging: {error}");
+                }
+            },
+            None => tracing::error!("Error transaction logging: log is closed"),
+        }
+    }
+
+    /// Waits until the transactions sent so far are written and stops the thread
+    pub fn close(&self) -> crate::Result<()> {
+        let writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner).take();
+        match writer {
+            Some((log_tx, join_handle)) => {
+                drop(log_tx);
+                join_handle.join().map_err(|_| crate::Error::SendFailed("transaction log".to_owned()))?
+            },
+            None => Ok(()),
+        }
+    }
+}
+
+/// Destination of the transaction log
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LogSink {
+    Stdout,
+    File(PathBuf),
+    Rotating { path: PathBuf, max_bytes: u64, keep: usize },
+}
+
+impl LogSink {
+    /// Turns a file sink into a rotating file sink, other sinks are returned unchanged
+    pub fn rotate(self, max_bytes: u64, keep: usize) -> Self {
+        match self {
+            LogSink::File(path) => LogSink::Rotating { path, max_bytes, keep },
+            sink => sink,
+        }
+    }
+
+    pub fn writer(&self) -> crate::Result<Box<dyn std::io::Write + Send + Sync>> {
+        match self {
+            LogSink::Stdout => Ok(Box::new(std::io::stdout())),
+            LogSink::File(path) => Ok(Box::new(append(path)?)),
+            LogSink::Rotating { path, max_bytes, keep } => {
+                RotatingFile::new(path.clone(), *max_bytes, *keep)
+                    .map(|file| Box::new(file) as Box<dyn std::io::Write + Send + Sync>)
+            },
+        }
+    }
+}
+
+impl std::str::FromStr for LogSink {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" => Err(crate::Error::Argument("transaction log must be - or a path")),
+            "-" => Ok(LogSink::Stdout),
+            path => Ok(LogSink::File(path.into())),
+        }
+    }
+}
+
+fn append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    name.into()
+}
+
+/// Log file that is moved to `<path>.1` when it grows beyond `max_bytes`,
+/// keeping at most `keep` older files
+///
+/// The file is only rotated after a line is complete, so a record never spans two files.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    written: u64,
+    line_complete: bool,
+}
+
+impl RotatingFile {
+    pub fn new(path: PathBuf, max_bytes: u64, keep: usize) -> crate::Result<Self> {
+        let file = append(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self { path, max_bytes, keep, file, written, line_complete: true })
+    }
+
+    /// Files holding the log, oldest first
+    pub fn files(path: &Path, keep: usize) -> Vec<PathBuf> {
+        (1..=keep)
+            .rev()
+            .map(|n| rotated(path, n))
+            .chain(std::iter::once(path.to_path_buf()))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.keep == 0 {
+            self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        }
+        else {
+            for n in (1..self.keep).rev() {
+                let from = rotated(&self.path, n);
+                if from.exists() {
+                    std::fs::rename(from, rotated(&self.path, n + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated(&self.path, 1))?;
+            self.file = append(&self.path)?;
+        }
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl std::io::Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.line_complete && self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let count = self.file.write(buf)?;
+        self.written += count as u64;
+        if let Some(last) = buf[..count].last() {
+            self.line_complete = *last == b'\n';
+        }
+        Ok(count)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Logs every successful upsert and delete of the inner repository
+///
+/// The log can be replayed into any repository with [`build_from_log`].
+/// Stopping the repository waits until the log is written.
+#[derive(Clone)]
+pub struct TransactionLogLayer {
+    log: TransactionLog,
+}
+
+impl TransactionLogLayer {
+    pub fn new(sink: &LogSink) -> crate::Result<Self> {
+        Ok(Self::from(TransactionLog::start(sink.writer()?)))
+    }
+}
+
+impl From<TransactionLog> for TransactionLogLayer {
+    fn from(log: TransactionLog) -> Self {
+        Self { log }
+    }
+}
+
+impl RepoLayer for TransactionLogLayer {
+    fn layer(&self, inner: Arc<dyn LiplRepo>) -> Arc<dyn LiplRepo> {
+        Arc::new(
+            TransactionLogRepo { inner, log: self.log.clone() }
+        )
+    }
+}
+
+pub struct TransactionLogRepo {
+    inner: Arc<dyn LiplRepo>,
+    log: TransactionLog,
+}
+
+impl TransactionLogRepo {
+    fn log(&self, transaction: Transaction) {
+        self.log.send(transaction);
+    }
+}
+
+#[async_trait]
+impl Forward for TransactionLogRepo {
+    fn inner(&self) -> &dyn LiplRepo {
+        self.inner.as_ref()
+    }
+
+    async fn upsert_lyric(&self, lyric: Lyric) -> crate::Result<Lyric> {
+        let lyric = self.inner.upsert_lyric(lyric).await?;
+        self.log(Transaction::LyricUpsert(lyric.clone()));
+        Ok(lyric)
+    }
+
+    async fn delete_lyric(&self, id: Uuid) -> crate::Result<()> {
+        self.inner.delete_lyric(id).await?;
+        self.log(Transaction::LyricDelete(id));
+        Ok(())
+    }
+
+    async fn upsert_playlist(&self, playlist: Playlist) -> crate::Result<Playlist> {
+        let playlist = self.inner.upsert_playlist(playlist).await?;
+        self.log(Transaction::PlaylistUpsert(playlist.clone()));
+        Ok(playlist)
+    }
+
+    async fn delete_playlist(&self, id: Uuid) -> crate::Result<()> {
+        self.inner.delete_playlist(id).await?;
+        self.log(Transaction::PlaylistDelete(id));
+        Ok(())
+    }
+
+    async fn stop(&self) -> crate::Result<()> {
+        let stopped = self.inner.stop().await;
+        let closed = self.log.close();
+        stopped.and(closed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use super::{build_from_log, log_to_transaction, RotatingFile, Transaction, TransactionLog, TransactionLogLayer};
+    use crate::layer::{RepoBuilder, tests::Store};
+    use crate::{LiplRepo, Playlist, Uuid};
+    use crate::tests;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("lipl-{name}-{}", Uuid::default()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("transaction.log")
+    }
+
+    #[tokio::test]
+    async fn replay_log_into_other_repo() {
+        let path = temp_path("replay");
+        let log = TransactionLog::start(std::fs::File::create(&path).unwrap());
+        let repo = RepoBuilder::new().layer(TransactionLogLayer::from(log)).service(Arc::new(Store::default()));
+        let lyric = repo.upsert_lyric(tests::lyric("Faria", &[])).await.unwrap();
+        let removed = repo.upsert_lyric(tests::lyric("Catootje", &[])).await.unwrap();
+        repo.upsert_playlist(Playlist { id: Uuid::default(), title: "Alles".to_owned(), members: vec![lyric.id] }).await.unwrap();
+        repo.delete_lyric(removed.id).await.unwrap();
+        repo.stop().await.unwrap();
+
+        let target = Store::default();
+        build_from_log(std::fs::File::open(&path).unwrap(), &target).await.unwrap();
+        assert_eq!(target.get_lyrics().await.unwrap().len(), 1);
+        assert_eq!(target.get_lyric(lyric.id).await.unwrap().title, "Faria".to_owned());
+        assert_eq!(target.get_playlists().await.unwrap()[0].members, vec![lyric.id]);
+    }
+
+    #[test]
+    fn rotating_file_keeps_older_files() {
+        let path = temp_path("rotate");
+        let mut file = RotatingFile::new(path.clone(), 10, 2).unwrap();
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+
+        let files = RotatingFile::files(&path, 2);
+        assert_eq!(files.len(), 3);
+        let contents = files.iter().map(|f| std::fs::read_to_string(f).unwrap()).collect::<Vec<_>>();
+        assert_eq!(contents, vec!["second\n", "third\n", "fourth\n"]);
+    }
+
+    #[tokio::test]
+    async fn replay_rotated_files_in_order() {
+        let path = temp_path("rotate-replay");
+        let titles = ["Faria", "Catootje", "Sofietje", "Roodkapje", "Daar bij die molen"];
+        let lyrics = titles.iter().map(|title| tests::lyric(title, &[])).collect::<Vec<_>>();
+        {
+            let mut write = log_to_transaction(RotatingFile::new(path.clone(), 100, 10).unwrap());
+            for lyric in lyrics.iter().cloned() {
+                write(Transaction::LyricUpsert(lyric)).unwrap();
+            }
+            write(Transaction::LyricDelete(lyrics[1].id)).unwrap();
+        }
+
+        let files = RotatingFile::files(&path, 10);
+        assert!(files.len() > 1);
+        let target = Store::default();
+        for file in files {
+            build_from_log(std::fs::File::open(file).unwrap(), &target).await.unwrap();
+        }
+        let mut replayed = target.get_lyrics().await.unwrap().into_iter().map(|lyric| lyric.title).collect::<Vec<_>>();
+        replayed.sort();
+        assert_eq!(replayed, vec!["Daar bij die molen", "Faria", "Roodkapje", "Sofietje"]);
+    }
+
+    #[test]
+    fn read_log_skips_blank_lines() {
+        let lyric = tests::lyric("Faria", &[]);
+        let log = format!("\n{}\n\n", Transaction::LyricUpsert(lyric));
+        assert_eq!(super::read_log(log.as_bytes()).unwrap().len(), 1);
+    }
+}
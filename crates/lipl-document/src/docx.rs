@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use roxmltree::{Document, Node};
+use crate::Paragraph;
+
+pub const DOCUMENT: &str = "word/document.xml";
+pub const STYLES: &str = "word/styles.xml";
+
+const W: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+/// Outline level of body text
+const BODY_TEXT: usize = 9;
+const MAX_BASED_ON: usize = 10;
+
+struct Style<'a> {
+    name: &'a str,
+    outline: Option<usize>,
+    based_on: Option<&'a str>,
+}
+
+fn is<'a>(node: &Node<'a, 'a>, name: &str) -> bool {
+    node.is_element() && node.tag_name().namespace() == Some(W) && node.tag_name().name() == name
+}
+
+fn child<'a>(node: &Node<'a, 'a>, name: &str) -> Option<Node<'a, 'a>> {
+    node.children().find(|child| is(child, name))
+}
+
+fn val<'a>(node: &Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|child| child.attribute((W, "val")))
+}
+
+fn outline<'a>(node: &Node<'a, 'a>) -> Option<usize> {
+    val(node, "outlineLvl").and_then(|level| level.parse().ok())
+}
+
+/// Styles by id, style ids are translated in some versions of Word, style names of headings are not
+fn styles<'a>(document: &'a Document<'a>) -> HashMap<&'a str, Style<'a>> {
+    document
+        .descendants()
+        .filter(|node| is(node, "style"))
+        .filter_map(|node| {
+            let id = node.attribute((W, "styleId"))?;
+            let outline = child(&node, "pPr").and_then(|properties| outline(&properties));
+            Some((id, Style { name: val(&node, "name").unwrap_or(id), outline, based_on: val(&node, "basedOn") }))
+        })
+        .collect()
+}
+
+/// Outline level of a style, from the style it is based on if it has none
+fn level(styles: &HashMap<&str, Style>, id: &str) -> Option<usize> {
+    let mut id = id;
+    for _ in 0..MAX_BASED_ON {
+        let style = styles.get(id)?;
+        if let Some(outline) = style.outline {
+            return (outline < BODY_TEXT).then_some(outline + 1);
+        }
+        if let Some(level) = style.name.to_lowercase().strip_prefix("heading ").and_then(|level| level.parse().ok()) {
+            return Some(level);
+        }
+        id = style.based_on?;
+    }
+    None
+}
+
+fn text(node: Node, acc: &mut String) {
+    for child in node.children() {
+        match child.tag_name().name() {
+            _ if child.is_text() => {},
+            "t" => acc.push_str(child.text().unwrap_or_default()),
+            "tab" => acc.push('\t'),
+            "br" | "cr" => acc.push('\n'),
+            "pPr" | "rPr" | "del" | "instrText" => {},
+            _ => text(child, acc),
+        }
+    }
+}
+
+fn paragraph(node: Node, styles: &HashMap<&str, Style>) -> Paragraph {
+    let properties = child(&node, "pPr");
+    let level = match properties.as_ref().and_then(outline) {
+        Some(outline) => (outline < BODY_TEXT).then_some(outline + 1),
+        None => properties.as_ref().and_then(|properties| val(properties, "pStyle")).and_then(|id| level(styles, id)),
+    };
+    let mut acc = String::new();
+    text(node, &mut acc);
+    Paragraph { level, text: acc }
+}
+
+/// Paragraphs of the body, content controls included, tables left out
+fn blocks(node: Node, styles: &HashMap<&str, Style>, paragraphs: &mut Vec<Paragraph>) {
+    for child in node.children().filter(|child| child.is_element()) {
+        match child.tag_name().name() {
+            "p" => paragraphs.push(paragraph(child, styles)),
+            "sdt" | "sdtContent" | "customXml" => blocks(child, styles, paragraphs),
+            _ => {},
+        }
+    }
+}
+
+pub fn paragraphs(document: &Document, styles: Option<&Document>) -> Vec<Paragraph> {
+    let styles = styles.map(self::styles).unwrap_or_default();
+    let mut paragraphs = vec![];
+    if let Some(body) = document.descendants().find(|node| is(node, "body")) {
+        blocks(body, &styles, &mut paragraphs);
+    }
+    paragraphs
+}
@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Document: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Xml of {0}: {1}")]
+    Xml(String, roxmltree::Error),
+
+    #[error("Unknown document type {0}, expected docx or odt")]
+    UnknownType(String),
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Repo: {0}")]
+    Repo(#[from] lipl_core::Error),
+}
+
+lipl_core::import_error!(Error);
@@ -0,0 +1,233 @@
+/*!
+ Import songbooks from Word (`.docx`) and LibreOffice (`.odt`) documents.
+
+ Every heading of the chosen level starts a song with the heading as title, the paragraphs up to the next
+ heading of that level are its text. Headings of other levels and text before the first song are left out.
+ A paragraph is a line and empty paragraphs separate parts, a paragraph with line breaks is a part of its own.
+ [`parts::to_parts`] turns the text into parts.
+
+ [`import`] writes into any repository that implements [`ToRepo`], optionally with a playlist
+ of the songs of every document in document order, named after the document.
+ A dry run reports what would be created without opening the repository.
+ */
+
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use lipl_core::{Lyric, Playlist, ToRepo, Uuid};
+use zip::ZipArchive;
+
+pub use error::Error;
+pub use lipl_core::import::Report;
+
+mod docx;
+mod error;
+mod odt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Paragraph {
+    /// Outline level of a heading, starting at 1
+    pub level: Option<usize>,
+    /// Text with line breaks as `\n`
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Document {
+    pub name: String,
+    pub paragraphs: Vec<Paragraph>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Outline level of the headings that start a song
+    pub level: usize,
+    /// Create a playlist for every document
+    pub playlist: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self { level: 1, playlist: false }
+    }
+}
+
+fn read_entry<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Result<Option<String>> {
+    match archive.by_name(name) {
+        Ok(mut file) => {
+            let mut s = String::new();
+            file.read_to_string(&mut s)?;
+            Ok(Some(s))
+        },
+        Err(zip::result::ZipError::FileNotFound) => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn xml<'a>(name: &str, s: &'a str) -> Result<roxmltree::Document<'a>> {
+    roxmltree::Document::parse(s).map_err(|error| Error::Xml(name.to_owned(), error))
+}
+
+impl Document {
+    /// Reads a `.docx` or `.odt` file, named after the file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match extension.as_str() {
+            "docx" => Self::read_docx(&name, File::open(path)?),
+            "odt" => Self::read_odt(&name, File::open(path)?),
+            _ => Err(Error::UnknownType(path.to_string_lossy().to_string())),
+        }
+    }
+
+    pub fn read_docx<R: Read + Seek>(name: &str, r: R) -> Result<Self> {
+        let mut archive = ZipArchive::new(r)?;
+        let document = read_entry(&mut archive, docx::DOCUMENT)?.ok_or(zip::result::ZipError::FileNotFound)?;
+        let styles = read_entry(&mut archive, docx::STYLES)?;
+        let styles = styles.as_deref().map(|styles| xml(docx::STYLES, styles)).transpose()?;
+        Ok(
+            Self {
+                name: name.to_owned(),
+                paragraphs: docx::paragraphs(&xml(docx::DOCUMENT, &document)?, styles.as_ref()),
+            }
+        )
+    }
+
+    pub fn read_odt<R: Read + Seek>(name: &str, r: R) -> Result<Self> {
+        let mut archive = ZipArchive::new(r)?;
+        let content = read_entry(&mut archive, odt::CONTENT)?.ok_or(zip::result::ZipError::FileNotFound)?;
+        Ok(
+            Self {
+                name: name.to_owned(),
+                paragraphs: odt::paragraphs(&xml(odt::CONTENT, &content)?),
+            }
+        )
+    }
+
+    /// Title and text of every song, and what was left out
+    pub fn songs(&self, level: usize) -> (Vec<(String, String)>, Vec<String>) {
+        let mut songs: Vec<(String, Vec<String>)> = vec![];
+        let mut skipped = vec![];
+        for paragraph in self.paragraphs.iter() {
+            match (paragraph.level, songs.last_mut()) {
+                (Some(l), _) if l == level => songs.push((paragraph.text.trim().to_owned(), vec![])),
+                (Some(_), _) => {},
+                (None, Some((_, lines))) if paragraph.text.contains('\n') => {
+                    lines.push(String::new());
+                    lines.push(paragraph.text.clone());
+                    lines.push(String::new());
+                },
+                (None, Some((_, lines))) => lines.push(paragraph.text.clone()),
+                (None, None) if !paragraph.text.trim().is_empty() => skipped.push(format!("{}: {} before the first song", self.name, paragraph.text.trim())),
+                (None, None) => {},
+            }
+        }
+        (songs.into_iter().map(|(title, lines)| (title, lines.join("\n"))).collect(), skipped)
+    }
+}
+
+/// Lyrics and playlists with new ids
+pub fn report(documents: &[Document], options: &Options) -> Report {
+    let mut report = Report::default();
+    for document in documents {
+        let (songs, skipped) = document.songs(options.level);
+        report.skipped.extend(skipped);
+        let mut members = vec![];
+        for (title, text) in songs {
+            let parts = parts::to_parts(text);
+            if title.is_empty() || parts.is_empty() {
+                report.skipped.push(format!("{}: song {title} without title or text", document.name));
+                continue;
+            }
+            let lyric = Lyric { id: Uuid::default(), title, parts, extensions: Default::default(), times: None };
+            members.push(lyric.id);
+            report.lyrics.push((lyric, vec![]));
+        }
+        if options.playlist {
+            report.playlists.push(Playlist { id: Uuid::default(), title: document.name.clone(), members });
+        }
+    }
+    report
+}
+
+pub async fn import<T: ToRepo>(documents: &[Document], target: T, options: &Options, dry_run: bool) -> Result<Report> {
+    Ok(report(documents, options).import(target, dry_run).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+    use lipl_repo_memory::MemoryRepoConfig;
+    use zip::write::{FileOptions, ZipWriter};
+    use super::{import, report, Document, Options};
+
+    fn zip(entries: &[(&str, &str)]) -> Cursor<Vec<u8>> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        for (name, contents) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        Cursor::new(zip.finish().unwrap().into_inner())
+    }
+
+    const STYLES: &str = r#"<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+        <w:style w:type="paragraph" w:styleId="Kop1"><w:name w:val="heading 1"/></w:style>
+        <w:style w:type="paragraph" w:styleId="Lied"><w:name w:val="Lied"/><w:basedOn w:val="Kop1"/></w:style>
+        <w:style w:type="paragraph" w:styleId="Kop2"><w:name w:val="heading 2"/></w:style>
+    </w:styles>"#;
+
+    const DOCUMENT: &str = r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+        <w:p><w:r><w:t>Kampvuurliederen</w:t></w:r></w:p>
+        <w:p><w:pPr><w:pStyle w:val="Kop1"/></w:pPr><w:r><w:t>Zilveren </w:t></w:r><w:r><w:t>maan</w:t></w:r></w:p>
+        <w:p><w:r><w:t>Zilveren maan</w:t></w:r></w:p>
+        <w:p><w:r><w:t>boven de zee</w:t></w:r></w:p>
+        <w:p/>
+        <w:p><w:pPr><w:pStyle w:val="Kop2"/></w:pPr><w:r><w:t>Refrein</w:t></w:r></w:p>
+        <w:p><w:r><w:t>De golven</w:t><w:br/><w:t>slapen</w:t></w:r><w:del><w:r><w:delText>weg</w:delText></w:r></w:del></w:p>
+        <w:p><w:pPr><w:pStyle w:val="Lied"/></w:pPr><w:r><w:t>Zuiderzee</w:t></w:r></w:p>
+        <w:p><w:r><w:tab/><w:t>Regel</w:t></w:r></w:p>
+        <w:sectPr/>
+    </w:body></w:document>"#;
+
+    const CONTENT: &str = r#"<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+        <office:body><office:text>
+        <text:h text:outline-level="1">Zilveren maan</text:h>
+        <text:p>Zilveren<text:s/>maan<text:note><text:note-body><text:p>Voetnoot</text:p></text:note-body></text:note></text:p>
+        <text:p>boven<text:s text:c="2"/>de zee</text:p>
+        <text:p/>
+        <text:list><text:list-item><text:p><text:span>De golven</text:span><text:line-break/>slapen</text:p></text:list-item></text:list>
+        <text:h text:outline-level="1">Zuiderzee</text:h>
+        <text:p>Regel</text:p>
+        </office:text></office:body>
+    </office:document-content>"#;
+
+    #[test]
+    fn docx_and_odt_agree() {
+        let docx = Document::read_docx("Kampvuur", zip(&[("word/document.xml", DOCUMENT), ("word/styles.xml", STYLES)])).unwrap();
+        let odt = Document::read_odt("Kampvuur", zip(&[("content.xml", CONTENT)])).unwrap();
+        let options = Options { level: 1, playlist: true };
+        let docx = report(&[docx], &options);
+        let odt = report(&[odt], &options);
+
+        assert_eq!(docx.lyrics.iter().map(|(lyric, _)| lyric.title.as_str()).collect::<Vec<_>>(), vec!["Zilveren maan", "Zuiderzee"]);
+        assert_eq!(docx.lyrics[0].0.parts, vec![vec!["Zilveren maan", "boven de zee"], vec!["De golven", "slapen"]]);
+        assert_eq!(docx.lyrics[1].0.parts, vec![vec!["\tRegel"]]);
+        assert_eq!(docx.skipped.len(), 1);
+        assert_eq!(docx.playlists[0].members, docx.lyrics.iter().map(|(lyric, _)| lyric.id).collect::<Vec<_>>());
+
+        assert_eq!(odt.lyrics.iter().map(|(lyric, _)| (&lyric.title, lyric.parts.len())).collect::<Vec<_>>(), vec![(&"Zilveren maan".to_owned(), 2), (&"Zuiderzee".to_owned(), 1)]);
+        assert_eq!(odt.lyrics[0].0.parts, vec![vec!["Zilveren maan", "boven  de zee"], vec!["De golven", "slapen"]]);
+    }
+
+    #[tokio::test]
+    async fn import_into_repo() {
+        let odt = Document::read_odt("Kampvuur", zip(&[("content.xml", CONTENT)])).unwrap();
+        let config = MemoryRepoConfig { sample_data: false, transaction_log: None };
+        let report = import(&[odt], config, &Options::default(), false).await.unwrap();
+        assert!(report.playlists.is_empty());
+        assert!(report.to_string().contains("Zilveren maan, 2 parts"));
+    }
+}
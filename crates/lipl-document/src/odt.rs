@@ -0,0 +1,57 @@
+use roxmltree::{Document, Node};
+use crate::Paragraph;
+
+pub const CONTENT: &str = "content.xml";
+
+const TEXT: &str = "urn:oasis:names:tc:opendocument:xmlns:text:1.0";
+const OFFICE: &str = "urn:oasis:names:tc:opendocument:xmlns:office:1.0";
+
+fn name<'a>(node: &Node<'a, 'a>) -> Option<&'a str> {
+    (node.is_element() && node.tag_name().namespace() == Some(TEXT)).then(|| node.tag_name().name())
+}
+
+fn text(node: Node, acc: &mut String) {
+    for child in node.children() {
+        if child.is_text() {
+            acc.push_str(child.text().unwrap_or_default());
+            continue;
+        }
+        match name(&child) {
+            Some("s") => acc.push_str(&" ".repeat(child.attribute((TEXT, "c")).and_then(|c| c.parse().ok()).unwrap_or(1))),
+            Some("tab") => acc.push('\t'),
+            Some("line-break") => acc.push('\n'),
+            Some("note") | Some("annotation") => {},
+            _ => text(child, acc),
+        }
+    }
+}
+
+/// Paragraphs and headings, in lists and sections too
+fn blocks(node: Node, paragraphs: &mut Vec<Paragraph>) {
+    for child in node.children() {
+        match name(&child) {
+            Some("h") => {
+                let mut acc = String::new();
+                text(child, &mut acc);
+                let level = child.attribute((TEXT, "outline-level")).and_then(|level| level.parse().ok()).unwrap_or(1);
+                paragraphs.push(Paragraph { level: Some(level), text: acc });
+            },
+            Some("p") => {
+                let mut acc = String::new();
+                text(child, &mut acc);
+                paragraphs.push(Paragraph { level: None, text: acc });
+            },
+            Some("list") | Some("list-item") | Some("list-header") | Some("section") => blocks(child, paragraphs),
+            _ => {},
+        }
+    }
+}
+
+pub fn paragraphs(content: &Document) -> Vec<Paragraph> {
+    let mut paragraphs = vec![];
+    let body = content.descendants().find(|node| node.is_element() && node.tag_name().namespace() == Some(OFFICE) && node.tag_name().name() == "text");
+    if let Some(body) = body {
+        blocks(body, &mut paragraphs);
+    }
+    paragraphs
+}
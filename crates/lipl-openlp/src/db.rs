@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use rusqlite::Connection;
+use crate::{lyrics, Error, Result, Song, SongBook};
+
+const SONGS: &str = "SELECT id, title, lyrics, verse_order FROM songs ORDER BY title";
+const AUTHORS: &str = 
+    "SELECT authors_songs.song_id, authors.display_name FROM authors_songs \
+     JOIN authors ON authors.id = authors_songs.author_id ORDER BY authors.display_name";
+const HAS_SONGS_SONGBOOKS: &str = "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = 'songs_songbooks'";
+/// Since OpenLP 2.4 a song can be in several song books
+const SONG_BOOKS: &str = 
+    "SELECT song_books.name, songs_songbooks.entry, songs_songbooks.song_id FROM songs_songbooks \
+     JOIN song_books ON song_books.id = songs_songbooks.songbook_id";
+const SONG_BOOKS_LEGACY: &str = 
+    "SELECT song_books.name, songs.song_number, songs.id FROM songs \
+     JOIN song_books ON song_books.id = songs.song_book_id";
+
+fn entry_order(entry: &str) -> (u64, String) {
+    let digits = entry.chars().take_while(char::is_ascii_digit).collect::<String>();
+    (digits.parse().unwrap_or(u64::MAX), entry.to_owned())
+}
+
+/// Songs, and a message for every song whose lyrics cannot be read
+pub fn songs(connection: &Connection) -> Result<(Vec<Song>, Vec<String>)> {
+    let mut authors: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut statement = connection.prepare(AUTHORS)?;
+    for row in statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))? {
+        let (song_id, author) = row?;
+        authors.entry(song_id).or_default().push(author);
+    }
+
+    let mut statement = connection.prepare(SONGS)?;
+    let rows = statement.query_map([], |row| 
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+            row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+        ))
+    )?;
+    let mut songs = vec![];
+    let mut skipped = vec![];
+    for row in rows {
+        let (id, title, xml, verse_order) = row?;
+        match lyrics::parts(&xml, &verse_order) {
+            Ok(parts) => songs.push(
+                Song {
+                    id,
+                    title,
+                    authors: authors.remove(&id).unwrap_or_default(),
+                    parts,
+                }
+            ),
+            Err(error) => skipped.push(format!("{title}: {}", Error::Lyrics(id, error))),
+        }
+    }
+    Ok((songs, skipped))
+}
+
+pub fn song_books(connection: &Connection) -> Result<Vec<SongBook>> {
+    let query = 
+        if connection.query_row(HAS_SONGS_SONGBOOKS, [], |row| row.get::<_, i64>(0))? > 0 {
+            SONG_BOOKS
+        }
+        else {
+            SONG_BOOKS_LEGACY
+        };
+    let mut books: Vec<SongBook> = vec![];
+    let mut statement = connection.prepare(query)?;
+    for row in statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?, row.get::<_, i64>(2)?)))? {
+        let (name, entry, song_id) = row?;
+        let entry = entry.unwrap_or_default();
+        match books.iter_mut().find(|book| book.name == name) {
+            Some(book) => book.entries.push((entry, song_id)),
+            None => books.push(SongBook { name, entries: vec![(entry, song_id)] }),
+        }
+    }
+    for book in books.iter_mut() {
+        book.entries.sort_by_key(|(entry, _)| entry_order(entry));
+    }
+    books.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(books)
+}
@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Sqlite: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("Lyrics of song {0}: {1}")]
+    Lyrics(i64, roxmltree::Error),
+
+    #[error("Service file: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Service file without service data")]
+    NoServiceData,
+
+    #[error("Service data: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("IO Error: {0}")]
+    IOError(#[from] std::io::Error),
+
+    #[error("Repo: {0}")]
+    Repo(#[from] lipl_core::Error),
+}
+
+lipl_core::import_error!(Error);
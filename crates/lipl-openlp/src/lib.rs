@@ -0,0 +1,191 @@
+/*!
+ Import from OpenLP.
+
+ The songs of an OpenLP `songs.sqlite` database become lyrics, played in their verse order.
+ Song books and service files (`.osz`) become playlists, songs in a service are found by title.
+ Lyric has no place for authors, they show up in the [`Report`] only.
+ A song whose lyrics cannot be read is skipped and shows up in the report with its id.
+
+ [`OpenLp::import`] writes into any repository that implements [`ToRepo`],
+ a dry run reports what would be created without opening the repository.
+ */
+
+use std::fs::File;
+use std::path::Path;
+use lipl_core::{Lyric, Playlist, ToRepo, Uuid};
+use rusqlite::{Connection, OpenFlags};
+
+pub use error::Error;
+pub use lipl_core::import::Report;
+pub use service::{read_service, Service};
+
+mod db;
+mod error;
+mod lyrics;
+mod service;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug)]
+pub struct Song {
+    pub id: i64,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub parts: Vec<Vec<String>>,
+}
+
+/// Song book with its entries, ordered by entry number
+#[derive(Clone, Debug)]
+pub struct SongBook {
+    pub name: String,
+    pub entries: Vec<(String, i64)>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OpenLp {
+    pub songs: Vec<Song>,
+    pub song_books: Vec<SongBook>,
+    pub services: Vec<Service>,
+    /// Songs that could not be read
+    pub skipped: Vec<String>,
+}
+
+impl OpenLp {
+    /// Reads songs and song books from a `songs.sqlite` file, without changing it
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Self::from_connection(&connection)
+    }
+
+    pub fn from_connection(connection: &Connection) -> Result<Self> {
+        let (songs, skipped) = db::songs(connection)?;
+        Ok(
+            Self {
+                songs,
+                song_books: db::song_books(connection)?,
+                services: vec![],
+                skipped,
+            }
+        )
+    }
+
+    /// Adds a service file, the playlist is named after the file
+    pub fn service<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        let name = path.as_ref().file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        self.services.push(read_service(&name, File::open(path)?)?);
+        Ok(self)
+    }
+
+    /// Lyrics and playlists with new ids
+    pub fn report(&self) -> Report {
+        let mut report = Report { skipped: self.skipped.clone(), ..Default::default() };
+        let mut ids = vec![];
+        for song in self.songs.iter() {
+            let lyric = Lyric { id: Uuid::default(), title: song.title.clone(), parts: song.parts.clone(), extensions: Default::default(), times: None };
+            ids.push((song.id, lyric.id));
+            report.lyrics.push((lyric, song.authors.clone()));
+        }
+        let by_song_id = |song_id: i64| ids.iter().find(|(id, _)| *id == song_id).map(|(_, uuid)| *uuid);
+
+        for book in self.song_books.iter() {
+            report.playlists.push(
+                Playlist {
+                    id: Uuid::default(),
+                    title: book.name.clone(),
+                    members: book.entries.iter().filter_map(|(_, song_id)| by_song_id(*song_id)).collect(),
+                }
+            );
+        }
+
+        for service in self.services.iter() {
+            let mut members = vec![];
+            for title in service.titles.iter() {
+                match self.songs.iter().find(|song| song.title.eq_ignore_ascii_case(title.trim())).and_then(|song| by_song_id(song.id)) {
+                    Some(id) => members.push(id),
+                    None => report.skipped.push(format!("{}: song {title} not found", service.name)),
+                }
+            }
+            report.playlists.push(Playlist { id: Uuid::default(), title: service.name.clone(), members });
+        }
+        report
+    }
+
+    pub async fn import<T: ToRepo>(&self, target: T, dry_run: bool) -> Result<Report> {
+        Ok(self.report().import(target, dry_run).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+    use lipl_repo_memory::MemoryRepoConfig;
+    use rusqlite::Connection;
+    use zip::write::{FileOptions, ZipWriter};
+    use super::{read_service, OpenLp};
+
+    const SCHEMA: &str = "
+        CREATE TABLE songs (id INTEGER PRIMARY KEY, title VARCHAR(255), lyrics TEXT, verse_order VARCHAR(128));
+        CREATE TABLE authors (id INTEGER PRIMARY KEY, first_name VARCHAR(128), last_name VARCHAR(128), display_name VARCHAR(255));
+        CREATE TABLE authors_songs (author_id INTEGER, song_id INTEGER, author_type VARCHAR(255));
+        CREATE TABLE song_books (id INTEGER PRIMARY KEY, name VARCHAR(128), publisher VARCHAR(128));
+        CREATE TABLE songs_songbooks (songbook_id INTEGER, song_id INTEGER, entry VARCHAR(255));
+        INSERT INTO songs VALUES (1, 'Amazing Grace', '<song version=\"1.0\"><lyrics><verse label=\"1\" type=\"v\"><![CDATA[Amazing grace]]></verse><verse label=\"1\" type=\"c\"><![CDATA[Refrain]]></verse></lyrics></song>', 'v1 c1 v1');
+        INSERT INTO songs VALUES (2, 'Be Thou My Vision', '<song version=\"1.0\"><lyrics><verse label=\"1\" type=\"v\"><![CDATA[Be Thou my vision]]></verse></lyrics></song>', '');
+        INSERT INTO authors VALUES (1, 'John', 'Newton', 'John Newton');
+        INSERT INTO authors_songs VALUES (1, 1, 'words');
+        INSERT INTO song_books VALUES (1, 'Hymns', '');
+        INSERT INTO songs_songbooks VALUES (1, 1, '10');
+        INSERT INTO songs_songbooks VALUES (1, 2, '9');
+    ";
+
+    fn service() -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        zip.start_file("sunday.osj", FileOptions::default()).unwrap();
+        zip.write_all(br#"[{"openlp_core": {}}, {"serviceitem": {"header": {"name": "songs", "title": "Be Thou My Vision"}}}, {"serviceitem": {"header": {"name": "custom", "title": "Welcome"}}}, {"serviceitem": {"header": {"name": "songs", "title": "Unknown"}}}]"#).unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn openlp() -> OpenLp {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(SCHEMA).unwrap();
+        let mut openlp = OpenLp::from_connection(&connection).unwrap();
+        openlp.services.push(read_service("Sunday", Cursor::new(service())).unwrap());
+        openlp
+    }
+
+    #[test]
+    fn report_songs_books_and_services() {
+        let report = openlp().report();
+        assert_eq!(report.lyrics.len(), 2);
+        assert_eq!(report.lyrics[0].0.parts.len(), 3);
+        assert_eq!(report.lyrics[0].1, vec!["John Newton".to_owned()]);
+
+        assert_eq!(report.playlists[0].title, "Hymns");
+        assert_eq!(report.playlists[0].members, vec![report.lyrics[1].0.id, report.lyrics[0].0.id]);
+        assert_eq!(report.playlists[1].title, "Sunday");
+        assert_eq!(report.playlists[1].members, vec![report.lyrics[1].0.id]);
+        assert_eq!(report.skipped.len(), 1);
+    }
+
+    #[test]
+    fn skip_song_with_broken_lyrics() {
+        let connection = Connection::open_in_memory().unwrap();
+        connection.execute_batch(SCHEMA).unwrap();
+        connection.execute_batch("INSERT INTO songs VALUES (3, 'Broken', '<song><lyrics><verse>', '');").unwrap();
+        let openlp = OpenLp::from_connection(&connection).unwrap();
+        assert_eq!(openlp.songs.len(), 2);
+
+        let report = openlp.report();
+        assert_eq!(report.lyrics.len(), 2);
+        assert_eq!(report.skipped.len(), 1);
+        assert!(report.skipped[0].starts_with("Broken: Lyrics of song 3: "));
+    }
+
+    #[tokio::test]
+    async fn import_into_repo() {
+        let config = MemoryRepoConfig { sample_data: false, transaction_log: None };
+        let report = openlp().import(config, false).await.unwrap();
+        assert_eq!(report.playlists.len(), 2);
+        assert!(report.to_string().contains("Amazing Grace, 3 parts, John Newton"));
+    }
+}
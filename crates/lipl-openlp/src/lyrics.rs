@@ -0,0 +1,78 @@
+use roxmltree::Document;
+
+struct Verse {
+    name: String,
+    lines: Vec<String>,
+}
+
+/// Line without formatting tags like `{r}` and `{/r}` and without slide splits `[---]`
+fn clean(line: &str) -> String {
+    let mut acc = String::with_capacity(line.len());
+    let mut in_tag = false;
+    for c in line.replace("[---]", "").replace("[--}{--]", "").chars() {
+        match c {
+            '{' => in_tag = true,
+            '}' if in_tag => in_tag = false,
+            _ if !in_tag => acc.push(c),
+            _ => {},
+        }
+    }
+    acc.trim().to_owned()
+}
+
+/// Parts of the lyrics xml of an OpenLP song, played in verse order when there is one
+pub fn parts(xml: &str, verse_order: &str) -> Result<Vec<Vec<String>>, roxmltree::Error> {
+    let document = Document::parse(xml)?;
+    let verses =
+        document
+        .descendants()
+        .filter(|node| node.is_element() && node.tag_name().name() == "verse")
+        .map(|node| Verse {
+            name: format!("{}{}", node.attribute("type").unwrap_or("v"), node.attribute("label").unwrap_or("1")).to_lowercase(),
+            lines:
+                node.descendants()
+                .filter_map(|node| node.text().filter(|_| node.is_text()))
+                .collect::<String>()
+                .lines()
+                .map(clean)
+                .filter(|line| !line.is_empty())
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let order = verse_order.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>();
+    let parts = 
+        if order.is_empty() {
+            verses.iter().map(|verse| verse.lines.clone()).collect()
+        }
+        else {
+            order
+            .iter()
+            .filter_map(|name| verses.iter().find(|verse| verse.name == *name))
+            .map(|verse| verse.lines.clone())
+            .collect()
+        };
+    Ok(parts)
+}
+
+#[cfg(test)]
+mod tests {
+    const LYRICS: &str = r#"<?xml version='1.0' encoding='UTF-8'?>
+<song version="1.0"><lyrics><verse label="1" type="v"><![CDATA[{r}Amazing grace{/r} how sweet the sound
+[---]
+That saved a wretch like me]]></verse><verse label="1" type="c"><![CDATA[Refrain]]></verse><verse label="2" type="v"><![CDATA[[G]'Twas grace]]></verse></lyrics></song>"#;
+
+    #[test]
+    fn parts_in_verse_order() {
+        let parts = super::parts(LYRICS, "V1 C1 V2 C1").unwrap();
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0], vec!["Amazing grace how sweet the sound".to_owned(), "That saved a wretch like me".to_owned()]);
+        assert_eq!(parts[2], vec!["[G]'Twas grace".to_owned()]);
+        assert_eq!(parts[1], parts[3]);
+    }
+
+    #[test]
+    fn parts_without_verse_order() {
+        assert_eq!(super::parts(LYRICS, "").unwrap().len(), 3);
+    }
+}
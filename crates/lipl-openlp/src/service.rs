@@ -0,0 +1,37 @@
+use std::io::{Read, Seek};
+use serde_json::Value;
+use crate::{Error, Result};
+
+const SERVICE_DATA_EXTENSION: &str = ".osj";
+const SONGS_PLUGIN: &str = "songs";
+
+/// Titles of the songs in an OpenLP service file (`.osz`), in service order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Service {
+    pub name: String,
+    pub titles: Vec<String>,
+}
+
+pub fn read_service<R: Read + Seek>(name: &str, r: R) -> Result<Service> {
+    let mut archive = zip::ZipArchive::new(r)?;
+    let data = 
+        archive
+        .file_names()
+        .find(|file_name| file_name.ends_with(SERVICE_DATA_EXTENSION))
+        .map(String::from)
+        .ok_or(Error::NoServiceData)?;
+    let items: Vec<Value> = serde_json::from_reader(archive.by_name(&data)?)?;
+    Ok(
+        Service {
+            name: name.to_owned(),
+            titles:
+                items
+                .iter()
+                .map(|item| &item["serviceitem"]["header"])
+                .filter(|header| header["name"] == SONGS_PLUGIN)
+                .filter_map(|header| header["title"].as_str())
+                .map(String::from)
+                .collect(),
+        }
+    )
+}
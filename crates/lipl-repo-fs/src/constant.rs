@@ -1,2 +1,3 @@
 pub const YAML_EXTENSION: &str = "yaml";
-pub const LYRIC_EXTENSION: &str = "md";
\ No newline at end of file
+pub const LYRIC_EXTENSION: &str = "md";
+pub const TRANSACTION_LOG: &str = ".transaction.log";
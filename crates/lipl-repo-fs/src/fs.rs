@@ -7,10 +7,11 @@ use futures::{Stream, StreamExt, TryStreamExt, TryFutureExt};
 use futures::future::{ready, Ready};
 use tokio::fs::{read_dir, File, remove_file};
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio_stream::wrappers::{LinesStream, ReadDirStream};
+use tokio_stream::wrappers::{ReadDirStream};
 
 use lipl_core::error::FileRepoError;
 use lipl_core::{Uuid};
+use parts::frontmatter::Scanner;
 
 type Result<T> = std::result::Result<T, FileRepoError>;
 
@@ -41,20 +42,17 @@ where
     }
 
     async fn read_frontmatter(&self) -> Result<String> {
-        File::open(self)
-        .map_ok(BufReader::new)
-        .map_ok(|buf_reader| buf_reader.lines())
-        .map_ok(LinesStream::new)
-        .and_then(|stream|
-            stream
-            .try_skip_while(|l| ready(Ok(l.trim() != "---")))
-            .try_skip_while(|l| ready(Ok(l.trim() == "---")))
-            .try_take_while(|l| ready(Ok(l.trim() != "---")))
-            .try_collect::<Vec<String>>()
-        )
-        .err_into()
-        .map_ok(|parts| parts.join("\n"))
-        .await
+        let mut lines = BufReader::new(File::open(self).await?).lines();
+        let mut scanner = Scanner::default();
+        let mut frontmatter = vec![];
+        while let Some(line) = lines.next_line().await? {
+            let more = scanner.line(&line);
+            frontmatter.push(line);
+            if !more {
+                break;
+            }
+        }
+        Ok(frontmatter.join("\n"))
     }
 
     async fn remove(&self) -> Result<()> {
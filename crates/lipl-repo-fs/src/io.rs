@@ -2,8 +2,10 @@ use std::str::FromStr;
 use std::path::{Path, PathBuf};
 use futures::{TryFuture, TryStreamExt};
 
-use lipl_core::{Lyric, LyricPost, Playlist, PlaylistPost, Summary, LyricMeta, Uuid};
+use lipl_core::hash::{verify, Verification};
+use lipl_core::{Frontmatter, Lyric, LyricPost, Playlist, PlaylistPost, Summary, LyricMeta, Uuid};
 use crate::fs::IO;
+use crate::status::FileStatus;
 
 use crate::FileRepoError;
 
@@ -57,6 +59,73 @@ where
     path.write_string(d.to_string()).await
 }
 
+/// Lyric as it reads back from its file, the hash in the frontmatter is the hash of this lyric
+fn on_disk(lyric: &Lyric) -> Result<Lyric> {
+    get_item::<LyricPost, Lyric>(lyric.to_string(), lyric.id)
+}
+
+fn same(lyric: &Lyric, other: &Lyric) -> bool {
+    lyric.title == other.title && lyric.parts == other.parts && lyric.extensions == other.extensions && lyric.times == other.times
+}
+
+/// Lyric in a file and whether it still matches the hash in the frontmatter
+fn lyric_status(s: &str, id: Uuid) -> Result<(Lyric, FileStatus)> {
+    let hash = s.parse::<LyricMeta>().map_err(|_| FileRepoError::Parse(format!("{id}")))?.hash;
+    let lyric = get_item::<LyricPost, Lyric>(s.to_owned(), id)?;
+    let status = match hash.map(|hash| verify(&lyric, &hash)) {
+        None => FileStatus::Untracked,
+        Some(Verification::Current) => FileStatus::Unchanged,
+        Some(Verification::Legacy) => FileStatus::Legacy,
+        Some(Verification::Mismatch) => FileStatus::Modified,
+    };
+    Ok((lyric, status))
+}
+
+/// A modified file or a file without hash is changed by hand or by another program
+fn external(status: FileStatus) -> bool {
+    matches!(status, FileStatus::Modified | FileStatus::Untracked)
+}
+
+/// Writes a lyric with the frontmatter style of the file it replaces, YAML for a new file.
+/// With `protect` a file modified outside lipl is only replaced by the same lyric.
+pub async fn post_lyric<P>(path: P, lyric: &Lyric, protect: bool) -> Result<()>
+where
+    P: AsRef<Path> + Send + Sync,
+{
+    let lyric = on_disk(lyric)?;
+    let existing = path.read_string().await.ok();
+    if let Some(existing) = existing.as_deref().filter(|_| protect) {
+        let (current, status) = lyric_status(existing, lyric.id)?;
+        if external(status) && !same(&current, &lyric) {
+            return Err(FileRepoError::Modified(lyric.id.to_string()));
+        }
+    }
+    let style = existing.as_deref().and_then(Frontmatter::of).unwrap_or_default();
+    let markdown = lyric.to_markdown(style).map_err(|e| FileRepoError::Parse(format!("{} {e}", lyric.id)))?;
+    path.write_string(markdown).await
+}
+
+/// Removes a lyric file, with `protect` a file modified outside lipl is kept
+pub async fn delete_lyric<P>(path: P, protect: bool) -> Result<()>
+where
+    P: AsRef<Path> + Send + Sync,
+{
+    if protect {
+        let (lyric, status) = lyric_status(&path.read_string().await?, path.id()?)?;
+        if external(status) {
+            return Err(FileRepoError::Modified(lyric.id.to_string()));
+        }
+    }
+    path.remove().await
+}
+
+pub async fn get_lyric_status<P>(path: P) -> Result<(Summary, FileStatus)>
+where P: AsRef<Path> + Send + Sync,
+{
+    let (lyric, status) = lyric_status(&path.read_string().await?, path.id()?)?;
+    Ok((Summary { id: lyric.id, title: lyric.title }, status))
+}
+
 pub async fn get_lyric<P>(path: P) -> Result<Lyric>
 where P: AsRef<Path> + Send + Sync,
 {
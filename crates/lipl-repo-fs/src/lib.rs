@@ -3,7 +3,8 @@ use std::fs::{OpenOptions};
 use std::str::FromStr;
 use std::path::{PathBuf, Path};
 use std::sync::Arc;
-use lipl_core::transaction::{OptionalTransaction, start_log_thread, build_from_log};
+use lipl_core::registry::{RepoScheme, RepoUrl};
+use lipl_core::transaction::{apply, read_log, Transaction, TransactionLog};
 use tokio::task::JoinHandle;
 
 use async_trait::async_trait;
@@ -12,22 +13,30 @@ pub use lipl_core::error::FileRepoError;
 use fs::IO;
 use futures::{channel::mpsc};
 use futures::{FutureExt, StreamExt, TryStreamExt, TryFutureExt};
+use tracing::warn;
 use lipl_core::{
     transaction::Request,
     LiplRepo, Lyric, Playlist, Summary, Uuid, ToRepo,
 };
 use lipl_util::VecExt;
 use request::{delete_by_id, post, select, select_by_id};
-use constant::{LYRIC_EXTENSION, YAML_EXTENSION};
+use constant::{LYRIC_EXTENSION, TRANSACTION_LOG, YAML_EXTENSION};
+
+pub use status::{accept, migrate, quarantine, status, unreadable, FileStatus, Unreadable};
 
 mod constant;
 mod fs;
 mod io;
 mod request;
+mod status;
 
 #[derive(Clone)]
 pub struct FileRepoConfig {
     pub path: String,
+    /// Refuse to overwrite lyric files modified outside lipl
+    pub protect: bool,
+    /// Accept playlist members that are not lyrics of this repository
+    pub foreign: bool,
 }
 
 impl FromStr for FileRepoConfig {
@@ -35,24 +44,64 @@ impl FromStr for FileRepoConfig {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         s.is_dir()
             .map_err(lipl_core::Error::from)
-            .map(|_| FileRepoConfig { path: s.into() })
+            .map(|_| FileRepoConfig { path: s.into(), protect: false, foreign: false })
     }
 }
 
 #[async_trait]
 impl ToRepo for FileRepoConfig {
     async fn to_repo(self) -> lipl_core::Result<Arc<dyn LiplRepo>> {
-        let repo = FileRepo::new(self.path).await?;
+        let repo = FileRepo::open_with(self.path, self.protect, self.foreign).await?;
         Ok(
             Arc::new(repo)
         )
     }
 }
 
+/// Opens `file:///path` or `file:path`, with `?protect` lyric files modified outside lipl are not overwritten or deleted,
+/// with `?foreign` playlists may hold lyrics of other repositories, as the write repository of a federation needs
+pub struct FileScheme;
+
+#[async_trait]
+impl RepoScheme for FileScheme {
+    fn schemes(&self) -> &[&'static str] {
+        &["file"]
+    }
+
+    async fn open(&self, mut url: RepoUrl) -> lipl_core::Result<Arc<dyn LiplRepo>> {
+        let protect = url.take_flag("protect")?;
+        let foreign = url.take_flag("foreign")?;
+        url.finish()?;
+        FileRepoConfig { protect, foreign, ..url.path().parse::<FileRepoConfig>()? }
+            .to_repo()
+            .await
+    }
+}
+
+/// How the requests of a repository are handled
+#[derive(Clone)]
+struct Handling {
+    /// Refuse to overwrite or delete lyric files modified outside lipl
+    protect: bool,
+    /// Accept playlist members that are not lyrics of this repository
+    foreign: bool,
+    /// Log of the changes that succeeded
+    log: Option<TransactionLog>,
+}
+
+impl Handling {
+    fn log(&self, transaction: Transaction) {
+        if let Some(log) = &self.log {
+            log.send(transaction);
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct FileRepo {
     tx: mpsc::Sender<Request>,
     path: String,
+    log: Option<TransactionLog>,
     _join_handle: Arc<JoinHandle<bool>>,
 }
 
@@ -62,8 +111,8 @@ impl Debug for FileRepo {
     }
 }
 
-fn check_members(playlist: &Playlist, lyric_ids: &[Uuid]) -> impl futures::Future<Output = Result<(), FileRepoError>> {
-    if let Some(member) = playlist.members.iter().find(|member| !lyric_ids.contains(member))
+fn check_members(playlist: &Playlist, lyric_ids: &[Uuid], foreign: bool) -> impl futures::Future<Output = Result<(), FileRepoError>> {
+    if let Some(member) = playlist.members.iter().find(|member| !foreign && !lyric_ids.contains(member))
     {
         futures::future::ready(Err(FileRepoError::PlaylistInvalidMember(playlist.id.to_string(), member.to_string())))
     }
@@ -73,7 +122,7 @@ fn check_members(playlist: &Playlist, lyric_ids: &[Uuid]) -> impl futures::Futur
 }
 
 
-async fn handle_request<P, Q>(request: Request, source_dir: String, lyric_path: P, playlist_path: Q) -> Result<(), lipl_core::Error> 
+async fn handle_request<P, Q>(request: Request, source_dir: String, handling: Handling, lyric_path: P, playlist_path: Q) -> Result<(), lipl_core::Error> 
 where P: Fn(&Uuid) -> PathBuf, Q: Fn(&Uuid) -> PathBuf
 {
     match request {
@@ -118,8 +167,7 @@ where P: Fn(&Uuid) -> PathBuf, Q: Fn(&Uuid) -> PathBuf
         Request::LyricDelete(uuid, sender) => {
             async {
                 let playlists =
-                    lyric_path(&uuid)
-                    .remove()
+                    io::delete_lyric(lyric_path(&uuid), handling.protect)
                     .and_then(|_|
                         io::get_list(
                             &source_dir,
@@ -140,19 +188,30 @@ where P: Fn(&Uuid) -> PathBuf, Q: Fn(&Uuid) -> PathBuf
                 }
                 Ok::<(), lipl_core::Error>(())
             }
-            .map(|v| sender.send(v))
+            .map(|v| {
+                if v.is_ok() {
+                    handling.log(Transaction::LyricDelete(uuid));
+                }
+                sender.send(v)
+            })
             .map_err(|_| lipl_core::Error::SendFailed(format!("LyricDelete {uuid}")))
             .await
         }
         Request::LyricPost(lyric, sender) => {
             let path = lyric_path(&lyric.id);
-            io::post_item(
+            io::post_lyric(
                 &path,
-                lyric,
+                &lyric,
+                handling.protect,
             )
             .and_then(|_| io::get_lyric(&path))
             .map_err(lipl_core::Error::from)
-            .map(|v| sender.send(v))
+            .map(|v| {
+                if let Ok(lyric) = &v {
+                    handling.log(Transaction::LyricUpsert(lyric.clone()));
+                }
+                sender.send(v)
+            })
             .map_err(|e| lipl_core::Error::SendFailed(format!("LyricPost {}", e.unwrap().title)))
             .await
         }
@@ -191,7 +250,12 @@ where P: Fn(&Uuid) -> PathBuf, Q: Fn(&Uuid) -> PathBuf
             path
             .remove()
             .map_err(lipl_core::Error::from)
-            .map(|v| sender.send(v))
+            .map(|v| {
+                if v.is_ok() {
+                    handling.log(Transaction::PlaylistDelete(uuid));
+                }
+                sender.send(v)
+            })
             .map_err(|_| lipl_core::Error::SendFailed(format!("PlaylistDelete {uuid}")))
             .await
         }
@@ -202,7 +266,7 @@ where P: Fn(&Uuid) -> PathBuf, Q: Fn(&Uuid) -> PathBuf
                 io::get_lyric_summary,
             )
             .map_ok(|summaries| lipl_core::ids(summaries.into_iter()))
-            .and_then(|ids| check_members(&playlist, &ids))
+            .and_then(|ids| check_members(&playlist, &ids, handling.foreign))
             .and_then(
                 |_| io::post_item(
                     playlist_path(&playlist.id),
@@ -214,13 +278,31 @@ where P: Fn(&Uuid) -> PathBuf, Q: Fn(&Uuid) -> PathBuf
                 )
             )
             .map_err(lipl_core::Error::from)
-            .map(|v| sender.send(v))
+            .map(|v| {
+                if let Ok(playlist) = &v {
+                    handling.log(Transaction::PlaylistUpsert(playlist.clone()));
+                }
+                sender.send(v)
+            })
             .map_err(|e| lipl_core::Error::SendFailed(format!("PlaylistPost {}", e.unwrap().title)))
             .await
         }
     }
 }
 
+/// Replays the transaction log without logging it again.
+/// Lyric files modified outside lipl are kept, they are written again after [`accept`].
+/// Playlist members are not checked again, the log only holds changes that succeeded.
+async fn replay<R: std::io::Read>(log: R, repo: &FileRepo) -> lipl_core::Result<()> {
+    for transaction in read_log(log)? {
+        match apply(transaction, repo).await {
+            Err(lipl_core::Error::File(FileRepoError::Modified(id))) => warn!("Replay skipped lyric {id}, it was modified outside lipl"),
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
 fn path(source_dir: String, extension: &'static str) -> impl Fn(&Uuid) -> PathBuf {
     move |uuid| source_dir.full_path(&uuid.to_string(), extension)
 }
@@ -229,28 +311,52 @@ impl FileRepo {
     pub async fn new(
         source_dir: String,
     ) -> lipl_core::Result<FileRepo> {
-        let dir = source_dir.clone();
-        let (tx, rx) = mpsc::channel::<Request>(10);
-        let transaction_log: PathBuf = PathBuf::from(source_dir.clone()).join(".transaction.log");
+        FileRepo::open(source_dir, false).await
+    }
+
+    /// Opens the repository, with `protect` upserts and deletes do not touch lyric files modified outside lipl,
+    /// see [`status`] and [`accept`]
+    pub async fn open(
+        source_dir: String,
+        protect: bool,
+    ) -> lipl_core::Result<FileRepo> {
+        FileRepo::open_with(source_dir, protect, false).await
+    }
+
+    /// Opens the repository like [`FileRepo::open`], with `foreign` playlists may hold lyrics of other repositories
+    pub async fn open_with(
+        source_dir: String,
+        protect: bool,
+        foreign: bool,
+    ) -> lipl_core::Result<FileRepo> {
+        let transaction_log: PathBuf = PathBuf::from(source_dir.clone()).join(TRANSACTION_LOG);
 
         let log = OpenOptions::new().append(true).open(&transaction_log)?;
 
-        let (_log_join_handle, log_tx) = start_log_thread(log);
+        if Path::exists(&transaction_log) {
+            let replaying = FileRepo::spawn(source_dir.clone(), Handling { protect: true, foreign: true, log: None });
+            let file = OpenOptions::new().read(true).open(&transaction_log)?;
+            let replayed = replay(file, &replaying).await;
+            replaying.stop().await?;
+            replayed?;
+        }
+
+        Ok(FileRepo::spawn(source_dir, Handling { protect, foreign, log: Some(TransactionLog::start(log)) }))
+    }
+
+    fn spawn(source_dir: String, handling: Handling) -> FileRepo {
+        let dir = source_dir.clone();
+        let log = handling.log.clone();
+        let (tx, rx) = mpsc::channel::<Request>(10);
 
         let join_handle = tokio::spawn(async move {
             rx
             .map(Ok)
-            .inspect_ok(move |request| {
-                if let Some(transaction) = OptionalTransaction::from(request) {
-                    if let Err(error) = log_tx.send(transaction) {
-                        tracing::error!("Error transaction logging: {error}");
-                    }
-                }
-            })
             .try_for_each(|request| 
                 handle_request(
                     request,
                     source_dir.clone(),
+                    handling.clone(),
                     path(source_dir.clone(), LYRIC_EXTENSION),
                     path(source_dir.clone(), YAML_EXTENSION),
                 )
@@ -259,20 +365,17 @@ impl FileRepo {
             .is_ok()
         });
 
-        let file_repo = FileRepo {
+        FileRepo {
             path: dir,
             tx,
+            log,
             _join_handle: Arc::new(join_handle),
-        };
-
-        if Path::exists(&transaction_log) {
-            let file = OpenOptions::new().read(true).open(&transaction_log)?;
-            build_from_log(file, file_repo.clone()).await?;
         }
-
-        Ok(file_repo.clone())
     }
 
+    pub async fn status(&self) -> lipl_core::Result<Vec<(Summary, FileStatus)>> {
+        status(&self.path).await
+    }
 }
 
 #[async_trait]
@@ -338,13 +441,157 @@ impl LiplRepo for FileRepo {
     }
 
     async fn stop(&self) -> lipl_core::Result<()> {
-        select(self.tx.clone(), Request::Stop)
-        .err_into()
-        .await
+        select(self.tx.clone(), Request::Stop).await?;
+        self.log.as_ref().map(TransactionLog::close).unwrap_or(Ok(()))
     }
 }
 
 #[cfg(test)]
 mod test {
+    use lipl_core::{LiplRepo, Lyric, Playlist, Uuid};
+    use lipl_core::federated::FederatedScheme;
+    use lipl_core::registry::RepoRegistry;
+    use super::{accept, migrate, quarantine, status, unreadable, FileRepo, FileScheme, FileStatus};
+
+    #[tokio::test]
+    async fn keeps_frontmatter_style() {
+        let dir = std::env::temp_dir().join(format!("lipl-repo-fs-{}", Uuid::default()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".transaction.log"), "").unwrap();
+        let id = Uuid::default();
+        let path = dir.join(format!("{id}.md"));
+        std::fs::write(&path, "+++\ntitle = \"Sofietje\"\nnotes = \"Boudewijn de Groot\"\n+++\n\nZij dronk ranja met een rietje\n").unwrap();
+        std::fs::write(dir.join(format!("{}.md", Uuid::default())), "{ \"title\": \"Zuiderzee\" }\n\nRegel\n").unwrap();
+
+        let repo = FileRepo::new(dir.to_string_lossy().to_string()).await.unwrap();
+        let mut titles = repo.get_lyric_summaries().await.unwrap().into_iter().map(|summary| summary.title).collect::<Vec<_>>();
+        titles.sort();
+        assert_eq!(titles, vec!["Sofietje", "Zuiderzee"]);
+
+        let mut lyric = repo.get_lyric(id).await.unwrap();
+        assert_eq!(lyric.parts, vec![vec!["Zij dronk ranja met een rietje"]]);
+        lyric.title = "Sofietje (1966)".to_owned();
+        repo.upsert_lyric(lyric).await.unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert!(written.starts_with("+++\ntitle = \"Sofietje (1966)\""));
+        assert!(written.contains("notes = \"Boudewijn de Groot\"\n"));
+
+        repo.stop().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn protects_external_modification() {
+        let dir = std::env::temp_dir().join(format!("lipl-repo-fs-protect-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".transaction.log"), "").unwrap();
+        let source = dir.to_string_lossy().to_string();
+
+        let repo = FileRepo::open(source.clone(), true).await.unwrap();
+        let lyric = Lyric { id: Uuid::default(), title: "Sofietje".to_owned(), parts: vec![vec!["Zij dronk ranja  ".to_owned()]], extensions: Default::default(), times: None };
+        let lyric = repo.upsert_lyric(lyric).await.unwrap();
+        assert_eq!(repo.status().await.unwrap()[0].1, FileStatus::Unchanged);
+
+        let path = dir.join(format!("{}.md", lyric.id));
+        std::fs::write(&path, format!("{}\n\nMet een rietje\n", std::fs::read_to_string(&path).unwrap())).unwrap();
+        assert_eq!(repo.status().await.unwrap()[0].1, FileStatus::Modified);
+        assert!(repo.upsert_lyric(lyric.clone()).await.is_err());
+        assert!(repo.delete_lyric(lyric.id).await.is_err());
+        assert!(path.exists());
+        repo.stop().await.unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join(".transaction.log")).unwrap().lines().count(), 1);
+
+        for protect in [true, false] {
+            let repo = FileRepo::open(source.clone(), protect).await.unwrap();
+            assert_eq!(repo.status().await.unwrap()[0].1, FileStatus::Modified);
+            assert_eq!(repo.get_lyric(lyric.id).await.unwrap().parts.len(), 2);
+            repo.stop().await.unwrap();
+        }
+        assert_eq!(std::fs::read_to_string(dir.join(".transaction.log")).unwrap().lines().count(), 1);
+
+        accept(&dir, lyric.id).await.unwrap();
+        let repo = FileRepo::open(source, true).await.unwrap();
+        assert_eq!(repo.status().await.unwrap()[0].1, FileStatus::Unchanged);
+        assert_eq!(repo.get_lyric(lyric.id).await.unwrap().parts.len(), 2);
+        let untracked = Uuid::default();
+        let untracked_path = dir.join(format!("{untracked}.md"));
+        std::fs::write(&untracked_path, "---\ntitle: Zuiderzee\n---\n\nRegel\n").unwrap();
+        assert!(repo.status().await.unwrap().iter().any(|(summary, status)| summary.title == "Zuiderzee" && *status == FileStatus::Untracked));
+        let zuiderzee = Lyric { id: untracked, title: "Zuiderzee".to_owned(), parts: vec![vec!["Andere regel".to_owned()]], extensions: Default::default(), times: None };
+        assert!(repo.upsert_lyric(zuiderzee).await.is_err());
+        assert!(repo.delete_lyric(untracked).await.is_err());
+        assert!(std::fs::read_to_string(&untracked_path).unwrap().ends_with("Regel\n"));
+
+        repo.delete_lyric(lyric.id).await.unwrap();
+        assert!(!path.exists());
+
+        repo.stop().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn writes_federated_playlist_with_foreign_members() {
+        let dir = std::env::temp_dir().join(format!("lipl-repo-fs-federated-{}", Uuid::default()));
+        let (team, shared) = (dir.join("team"), dir.join("shared"));
+        for dir in [&team, &shared] {
+            std::fs::create_dir_all(dir).unwrap();
+            std::fs::write(dir.join(".transaction.log"), "").unwrap();
+        }
+        let shared_repo = FileRepo::new(shared.to_string_lossy().to_string()).await.unwrap();
+        let sofietje = shared_repo.upsert_lyric(Lyric { id: Uuid::default(), title: "Sofietje".to_owned(), parts: vec![], extensions: Default::default(), times: None }).await.unwrap();
+        shared_repo.stop().await.unwrap();
+
+        let registry = RepoRegistry::new().scheme(FederatedScheme::new(RepoRegistry::new().scheme(FileScheme)));
+        let url = format!("federated:[team=file:{}?foreign,shared=file:{}]", team.to_string_lossy(), shared.to_string_lossy());
+        let repo = registry.open(&url).await.unwrap();
+        let faria = repo.upsert_lyric(Lyric { id: Uuid::default(), title: "Faria".to_owned(), parts: vec![], extensions: Default::default(), times: None }).await.unwrap();
+        let playlist = Playlist { id: Uuid::default(), title: "Kermis".to_owned(), members: vec![faria.id, sofietje.id] };
+        repo.upsert_playlist(playlist.clone()).await.unwrap();
+        assert_eq!(repo.get_playlist(playlist.id).await.unwrap().members, vec![faria.id, sofietje.id]);
+        repo.stop().await.unwrap();
+
+        let reopened = FileRepo::open_with(team.to_string_lossy().to_string(), false, true).await.unwrap();
+        assert_eq!(reopened.get_playlist(playlist.id).await.unwrap().members, vec![faria.id, sofietje.id]);
+        reopened.stop().await.unwrap();
+
+        let own = FileRepo::new(team.to_string_lossy().to_string()).await.unwrap();
+        assert!(own.upsert_playlist(Playlist { id: Uuid::default(), ..playlist }).await.is_err());
+        own.stop().await.unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn migrates_legacy_hash() {
+        let dir = std::env::temp_dir().join(format!("lipl-repo-fs-migrate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let lyric = Lyric { id: Uuid::default(), title: "Zuiderzee".to_owned(), parts: vec![vec!["Regel".to_owned()]], extensions: Default::default(), times: None };
+        let legacy = lipl_core::hash::legacy(&lyric).unwrap();
+        std::fs::write(dir.join(format!("{}.md", lyric.id)), format!("---\ntitle: Zuiderzee\nhash: '{legacy}'\n---\n\nRegel\n")).unwrap();
+
+        assert_eq!(status(&dir).await.unwrap()[0].1, FileStatus::Legacy);
+        assert_eq!(migrate(&dir).await.unwrap(), 1);
+        assert_eq!(status(&dir).await.unwrap()[0].1, FileStatus::Unchanged);
+        assert_eq!(migrate(&dir).await.unwrap(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 
-}
\ No newline at end of file
+    #[tokio::test]
+    async fn quarantines_unreadable() {
+        let dir = std::env::temp_dir().join(format!("lipl-repo-fs-unreadable-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{}.md", Uuid::default())), "---\ntitle: Zuiderzee\n---\n\nRegel\n").unwrap();
+        std::fs::write(dir.join("zuiderzee.md"), "---\ntitle: Zuiderzee\n---\n").unwrap();
+        std::fs::write(dir.join(format!("{}.yaml", Uuid::default())), "members: [").unwrap();
+
+        let found = unreadable(&dir).await.unwrap();
+        assert_eq!(found.len(), 2);
+        for unreadable in found {
+            assert!(quarantine(&unreadable.path).unwrap().to_string_lossy().ends_with(".broken"));
+        }
+        assert!(unreadable(&dir).await.unwrap().is_empty());
+        assert_eq!(status(&dir).await.unwrap().len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
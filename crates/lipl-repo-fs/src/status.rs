@@ -0,0 +1,118 @@
+/*!
+ Detection of lyric files edited outside lipl.
+
+ Every lyric file written by [`FileRepo`](crate::FileRepo) has the [hash](lipl_core::hash) of the lyric in its frontmatter.
+ A file whose lyric no longer matches that hash was changed by hand or by another program,
+ a file without hash was never written by lipl. Files with a legacy hash are rewritten by [`migrate`].
+
+ A file that cannot be parsed makes listing lyrics or playlists fail, [`unreadable`] finds them
+ and [`quarantine`] moves them out of the way.
+ */
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use futures::{TryFutureExt, TryStreamExt};
+use lipl_core::transaction::{log_to_transaction, Transaction};
+use lipl_core::{Lyric, Summary, Uuid};
+use crate::constant::{LYRIC_EXTENSION, TRANSACTION_LOG, YAML_EXTENSION};
+use crate::fs::{extension_filter, IO};
+use crate::io;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileStatus {
+    Unchanged,
+    Modified,
+    Untracked,
+    /// Unchanged, with a hash from before the canonical hash
+    Legacy,
+}
+
+impl Display for FileStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            FileStatus::Unchanged => write!(f, "unchanged"),
+            FileStatus::Modified => write!(f, "modified"),
+            FileStatus::Untracked => write!(f, "untracked"),
+            FileStatus::Legacy => write!(f, "legacy"),
+        }
+    }
+}
+
+/// File of a lyric or playlist that cannot be read
+#[derive(Debug)]
+pub struct Unreadable {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+impl Display for Unreadable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}: {}", self.path.to_string_lossy(), self.error)
+    }
+}
+
+/// Status of every lyric file in the directory
+pub async fn status<P>(dir: P) -> lipl_core::Result<Vec<(Summary, FileStatus)>>
+where
+    P: AsRef<Path> + Send + Sync,
+{
+    io::get_list(dir, LYRIC_EXTENSION, io::get_lyric_status)
+    .err_into()
+    .await
+}
+
+/// Takes over the change to a lyric file: the file gets a new hash and the lyric is added to the transaction log,
+/// so replaying the log when the repository opens keeps the change.
+/// The repository should not be open while accepting.
+pub async fn accept<P>(dir: P, id: Uuid) -> lipl_core::Result<Lyric>
+where
+    P: AsRef<Path> + Send + Sync,
+{
+    let path = dir.full_path(&id.to_string(), LYRIC_EXTENSION);
+    let lyric = io::get_lyric(&path).await?;
+    io::post_lyric(&path, &lyric, false).await?;
+    let log = OpenOptions::new().create(true).append(true).open(dir.as_ref().join(TRANSACTION_LOG))?;
+    log_to_transaction(log)(Transaction::LyricUpsert(lyric.clone()))?;
+    Ok(lyric)
+}
+
+/// Rewrites the lyric files with a legacy hash with the canonical hash, returns the number of files.
+/// The repository should not be open while migrating.
+pub async fn migrate<P>(dir: P) -> lipl_core::Result<usize>
+where
+    P: AsRef<Path> + Send + Sync,
+{
+    let legacy = status(&dir).await?.into_iter().filter(|(_, status)| *status == FileStatus::Legacy).collect::<Vec<_>>();
+    for (summary, _) in legacy.iter() {
+        let path = dir.full_path(&summary.id.to_string(), LYRIC_EXTENSION);
+        io::post_lyric(&path, &io::get_lyric(&path).await?, false).await?;
+    }
+    Ok(legacy.len())
+}
+
+pub async fn unreadable<P>(dir: P) -> lipl_core::Result<Vec<Unreadable>>
+where
+    P: AsRef<Path> + Send + Sync,
+{
+    let mut unreadable = vec![];
+    for path in dir.get_files(extension_filter(LYRIC_EXTENSION)).await?.try_collect::<Vec<_>>().await? {
+        if let Err(error) = io::get_lyric(&path).await {
+            unreadable.push(Unreadable { path, error: error.to_string() });
+        }
+    }
+    for path in dir.get_files(extension_filter(YAML_EXTENSION)).await?.try_collect::<Vec<_>>().await? {
+        if let Err(error) = io::get_playlist(&path).await {
+            unreadable.push(Unreadable { path, error: error.to_string() });
+        }
+    }
+    Ok(unreadable)
+}
+
+/// Renames a file to `<file>.broken`, the repository skips it
+pub fn quarantine(path: &Path) -> lipl_core::Result<PathBuf> {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".broken");
+    std::fs::rename(path, &name)?;
+    Ok(name.into())
+}
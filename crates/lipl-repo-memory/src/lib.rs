@@ -10,10 +10,11 @@ use lipl_core::{
     Result,
     Summary,
     Uuid,
-    Yaml,
     RepoDb,
-    reexport::serde_yaml, by_title, ToRepo, HasSummary,
+    by_title, ToRepo, HasSummary,
 };
+use lipl_core::dump::{Dump, DumpFormat};
+use lipl_core::registry::{RepoScheme, RepoUrl};
 use lipl_util::VecExt;
 
 #[derive(Clone)]
@@ -60,6 +61,33 @@ impl ToRepo for MemoryRepoConfig {
     }
 }
 
+/// Opens `memory:?sample=true`, `memory:true` is accepted as well.
+/// `memory:?snapshot=library.json` starts with a dump, in the format of its extension
+pub struct MemoryScheme;
+
+#[async_trait]
+impl RepoScheme for MemoryScheme {
+    fn schemes(&self) -> &[&'static str] {
+        &["memory"]
+    }
+
+    async fn open(&self, mut url: RepoUrl) -> Result<Arc<dyn LiplRepo>> {
+        let snapshot = url.take::<String>("snapshot")?;
+        let sample = url.take::<bool>("sample")?;
+        url.finish()?;
+        if let Some(snapshot) = snapshot {
+            let format = DumpFormat::from_path(&snapshot).ok_or(Error::Argument("unknown snapshot format"))?;
+            let repo = MemoryRepo::load(format, std::io::BufReader::new(std::fs::File::open(snapshot)?))?;
+            return Ok(Arc::new(repo));
+        }
+        let config = match sample {
+            Some(sample_data) => MemoryRepoConfig { sample_data, transaction_log: None },
+            None => url.path().parse::<MemoryRepoConfig>()?,
+        };
+        config.to_repo().await
+    }
+}
+
 #[derive(Clone)]
 pub struct MemoryRepo {
     db: Arc<RwLock<HashMap<Uuid, Record>>>,
@@ -122,23 +150,20 @@ impl Default for MemoryRepo {
     }
 }
 
-impl Yaml for MemoryRepo {
-    fn load<R>(r: R) -> Result<Self>
+impl Dump for MemoryRepo {
+    fn load<R>(format: DumpFormat, r: R) -> Result<Self>
     where 
         R: std::io::Read,
         Self: Sized,
     {
-        serde_yaml::from_reader::<_, RepoDb>(r)
-            .map_err(Into::into)
-            .map(MemoryRepo::from)
+        RepoDb::load(format, r).map(MemoryRepo::from)
     }
 
-    fn save<W>(&self, w: W) -> Result<()>
+    fn save<W>(&self, format: DumpFormat, w: W) -> Result<()>
     where
         W: std::io::Write,
     {
-        serde_yaml::to_writer(w, &self.to_repo_db())
-            .map_err(Into::into)
+        self.to_repo_db().save(format, w)
     }
 }
 
@@ -253,8 +278,23 @@ impl LiplRepo for MemoryRepo {
 
 #[cfg(test)]
 mod tests {
-    use super::{MemoryRepo};
-    use lipl_core::{LiplRepo, PlaylistPost, LyricPost};
+    use super::{MemoryRepo, MemoryScheme};
+    use lipl_core::{LiplRepo, PlaylistPost, LyricPost, Yaml};
+    use lipl_core::registry::RepoRegistry;
+
+    #[tokio::test]
+    async fn snapshot() {
+        let db = MemoryRepo::default();
+        let lyric_post = LyricPost { title: "Alle 13 goed".to_owned(), parts: vec![vec!["Dertien".to_owned()]], extensions: Default::default(), times: None };
+        let lyric = db.upsert_lyric((None, lyric_post).into()).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("lipl-memory-snapshot-{}.yml", lyric.id));
+        db.save(std::fs::File::create(&path).unwrap()).unwrap();
+        let repo = RepoRegistry::new().scheme(MemoryScheme).open(&format!("memory:?snapshot={}", path.to_string_lossy())).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(repo.get_lyric(lyric.id).await.unwrap().parts, lyric.parts);
+    }
 
     #[tokio::test]
     async fn post_lyric() {
@@ -263,6 +303,8 @@ mod tests {
         let lyric_post = LyricPost {
             title: "Alle 13 goed".to_owned(),
             parts: vec![],
+            extensions: Default::default(),
+            times: None,
         };
 
         let lyric = db.upsert_lyric((None, lyric_post).into()).await.unwrap();
@@ -279,6 +321,8 @@ mod tests {
         let lyric_post = LyricPost {
             title: "Alle 13 goed".to_owned(),
             parts: vec![],
+            extensions: Default::default(),
+            times: None,
         };
 
         let mut lyric = db.upsert_lyric((None, lyric_post).into()).await.unwrap();
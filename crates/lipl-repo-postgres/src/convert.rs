@@ -47,6 +47,8 @@ pub fn to_lyric(row: Row) -> Result<Lyric> {
             id: get_id(&row)?,
             title: get_title(&row)?,
             parts: get_parts(&row)?,
+            extensions: Default::default(),
+            times: None,
         }
     )    
 }
@@ -1,6 +1,6 @@
 use lipl_core::{error::RedisRepoError, Lyric, Playlist, Uuid};
 use parts::to_parts;
-pub use redis_repo::{RedisRepoConfig};
+pub use redis_repo::{RedisRepoConfig, RedisScheme};
 
 pub mod redis_repo;
 
@@ -11,6 +11,8 @@ pub fn new_lyric(title: &str, text: &str) -> Lyric {
         id: Uuid::default(),
         title: title.to_owned(),
         parts: to_parts(text.to_owned()),
+        extensions: Default::default(),
+        times: None,
     }
 }
 
@@ -5,6 +5,7 @@ use futures_util::{FutureExt, TryFutureExt, future::try_join_all};
 use parts::{to_parts, to_text};
 use std::{collections::{HashMap}, ops::DerefMut, sync::Arc, str::FromStr};
 use lipl_core::{Lyric, Uuid, error::RedisRepoError, Playlist, Summary, LiplRepo, by_title, ToRepo};
+use lipl_core::registry::{RepoScheme, RepoUrl};
 use crate::Result;
 
 const LYRIC: &str = "lyric";
@@ -26,6 +27,8 @@ fn hashmap_to_lyric(id: Uuid) -> impl Fn(HashMap<String, String>) -> Lyric {
         id, 
         title: hm.get(TITLE_ATTR).cloned().unwrap_or_default(), 
         parts: to_parts(hm.get(TEXT_ATTR).cloned().unwrap_or_default()),
+        extensions: Default::default(),
+        times: None,
     }
 }
 
@@ -96,6 +99,23 @@ where
     }
 }
 
+/// Opens `redis://host/`, option `clear` empties the database first
+pub struct RedisScheme;
+
+#[async_trait]
+impl RepoScheme for RedisScheme {
+    fn schemes(&self) -> &[&'static str] {
+        &["redis"]
+    }
+
+    async fn open(&self, mut url: RepoUrl) -> lipl_core::Result<Arc<dyn LiplRepo>> {
+        let clear = url.take_flag("clear")?;
+        RedisRepoConfig::new(clear, url.connection_string())
+            .to_repo()
+            .await
+    }
+}
+
 impl Default for RedisRepoConfig<String>
 {
     fn default() -> Self {
@@ -337,6 +357,7 @@ impl LiplRepo for RedisRepo {
     }
 
     async fn upsert_lyric(&self, lyric: Lyric) -> lipl_core::Result<Lyric> {
+        lyric.untimed()?;
         self.connection()
         .and_then(|mut connection| async move {
             connection.hset_multiple::<String, &str, String, ()>(
@@ -64,6 +64,8 @@ fn create_lyrics(lyric_files: &[PathBuf], playlists: Vec<(String, Vec<String>)>)
                         LyricPost {
                             title: #title.to_owned(),
                             parts: to_parts(include_str!(#file_path).to_owned()),
+                            extensions: Default::default(),
+                            times: None,
                         }
                     )
                 )
@@ -1,16 +1,43 @@
 use std::sync::Arc;
 
-use super::{to_json_response, to_status_ok, to_error_response, Key};
+use super::{openlyrics, to_etag_response, to_json_response, to_status_ok, to_error_response, Key};
+use async_trait::async_trait;
 use axum::{
-    Json,
-    extract::{Query, State},
-    http::StatusCode,
-    response::{Response},
+    BoxError, Json,
+    body::{Bytes, HttpBody},
+    extract::{FromRequest, Path, Query, State},
+    http::{header, HeaderMap, Request, StatusCode},
+    response::{IntoResponse, Response},
 };
 use futures_util::TryFutureExt;
-use lipl_core::{LiplRepo, LyricPost};
+use lipl_core::{LiplRepo, Lyric, LyricPost, TimedLyric, Uuid};
+use lipl_core::openlyrics::{lyric_post, Song, CONTENT_TYPE};
 use super::ListQuery;
 
+/// Lyric post as json, or as OpenLyrics xml with its content type
+pub struct LyricBody(pub LyricPost);
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for LyricBody
+where
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        if openlyrics(request.headers(), header::CONTENT_TYPE) {
+            let bytes = Bytes::from_request(request, state).await.map_err(IntoResponse::into_response)?;
+            lyric_post(&bytes).map(LyricBody).map_err(to_error_response)
+        }
+        else {
+            Json::<LyricPost>::from_request(request, state).await.map(|Json(lyric_post)| LyricBody(lyric_post)).map_err(IntoResponse::into_response)
+        }
+    }
+}
+
 /// Handler for getting all lyrics
 pub async fn list(
     State(connection): State<Arc<dyn LiplRepo>>,
@@ -31,22 +58,52 @@ pub async fn list(
     }
 }
 
-/// Handler for getting a specific lyric
+fn to_openlyrics_response(lyric: Lyric) -> Response {
+    (StatusCode::OK, [(header::CONTENT_TYPE, CONTENT_TYPE)], Song::from(&lyric).to_string()).into_response()
+}
+
+/// Handler for getting a specific lyric, as OpenLyrics xml if the client accepts it, otherwise as json with an etag
 pub async fn item(
     State(connection): State<Arc<dyn LiplRepo>>,
     key: Key,
+    headers: HeaderMap,
 ) -> Response 
 {
-    connection
-        .get_lyric(key.id)
-        .map_ok_or_else(to_error_response, to_json_response(StatusCode::OK))
-        .await
+    if openlyrics(&headers, header::ACCEPT) {
+        connection
+            .get_lyric(key.id)
+            .map_ok_or_else(to_error_response, to_openlyrics_response)
+            .await
+    }
+    else {
+        connection
+            .get_lyric(key.id)
+            .map_ok_or_else(to_error_response, to_etag_response(&headers))
+            .await
+    }
+}
+
+/// Handler for getting a specific lyric with the time of each line split from its text
+pub async fn timed(
+    State(connection): State<Arc<dyn LiplRepo>>,
+    Path(id): Path<String>,
+) -> Response
+{
+    match id.parse::<Uuid>() {
+        Ok(id) => 
+            connection
+                .get_lyric(id)
+                .map_ok(TimedLyric::from)
+                .map_ok_or_else(to_error_response, to_json_response(StatusCode::OK))
+                .await,
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
 }
 
-/// Handler for posting a new lyric
+/// Handler for posting a new lyric, as json or OpenLyrics xml
 pub async fn post(
     State(connection): State<Arc<dyn LiplRepo>>,
-    Json(lyric_post): Json<LyricPost>,
+    LyricBody(lyric_post): LyricBody,
 ) -> Response
 {
     connection
@@ -67,11 +124,11 @@ pub async fn delete(
             .await
 }
 
-/// Handler for changing a specific lyric
+/// Handler for changing a specific lyric, as json or OpenLyrics xml
 pub async fn put(
     State(connection): State<Arc<dyn LiplRepo>>,
     key: Key,
-    Json(lyric_post): Json<LyricPost>,
+    LyricBody(lyric_post): LyricBody,
 ) -> Response
 {
     connection
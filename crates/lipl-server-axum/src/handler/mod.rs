@@ -1,15 +1,18 @@
 use std::sync::Arc;
 
-use axum::{response::{IntoResponse, Json, Response}, extract::FromRequestParts};
+use axum::{response::{IntoResponse, Json, Response}, extract::FromRequestParts, http::{header::{self, HeaderName}, HeaderMap}};
 use futures_util::FutureExt;
 use hyper::StatusCode;
-use lipl_core::LiplRepo;
+use lipl_core::{Etag, LiplRepo};
+use lipl_core::hash::if_none_match;
+use lipl_core::openlyrics::is_openlyrics;
 use serde::{Deserialize, Serialize};
 
 use crate::{error::ErrorReport};
 
 pub mod lyric;
 pub mod playlist;
+pub mod songbook;
 
 #[derive(Deserialize)]
 pub struct ListQuery {
@@ -42,15 +45,51 @@ impl FromRequestParts<Arc<dyn LiplRepo>> for Key
     }
 }
 
+/// Whether a header like `accept` or `content-type` names OpenLyrics
+pub(crate) fn openlyrics(headers: &HeaderMap, name: HeaderName) -> bool {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .any(is_openlyrics)
+}
+
 pub(crate) fn to_json_response<T>(status_code: StatusCode) -> impl Fn(T) -> Response
 where T: Serialize
 {
     move |t| (status_code, Json(t)).into_response()
 }
 
+/// Json response with the etag of the content, or 304 Not Modified when the request has the etag in `If-None-Match`
+pub(crate) fn to_etag_response<T>(headers: &HeaderMap) -> impl Fn(T) -> Response + '_
+where T: Serialize + Etag
+{
+    move |t| {
+        let etag = t.etag();
+        let not_modified =
+            headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| if_none_match(value, &etag));
+        if not_modified {
+            (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response()
+        }
+        else {
+            (StatusCode::OK, [(header::ETAG, etag)], Json(t)).into_response()
+        }
+    }
+}
+
 pub(crate) fn to_error_response(error: lipl_core::Error) -> Response {
     match error {
         lipl_core::Error::NoKey(_) => (StatusCode::NOT_FOUND, Json(ErrorReport::from(error))).into_response(),
+        lipl_core::Error::ReadOnly => (StatusCode::SERVICE_UNAVAILABLE, Json(ErrorReport::from(error))).into_response(),
+        lipl_core::Error::OpenLyrics(_) => (StatusCode::BAD_REQUEST, Json(ErrorReport::from(error))).into_response(),
+        lipl_core::Error::TimesNotKept(_) => (StatusCode::BAD_REQUEST, Json(ErrorReport::from(error))).into_response(),
+        lipl_core::Error::NotOwned(_) => (StatusCode::FORBIDDEN, Json(ErrorReport::from(error))).into_response(),
+        #[cfg(feature = "file")]
+        lipl_core::Error::File(lipl_core::error::FileRepoError::Modified(_)) => (StatusCode::CONFLICT, Json(ErrorReport::from(error))).into_response(),
         _ => (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorReport::from(error))).into_response()
     }
     
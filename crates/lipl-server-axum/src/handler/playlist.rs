@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
-use super::{to_error_response, to_json_response, to_status_ok, Key};
-use axum::{extract::{State, Query}, http::StatusCode, Json, response::Response};
+use super::{to_error_response, to_etag_response, to_json_response, to_status_ok, Key};
+use axum::{extract::{State, Query}, http::{HeaderMap, StatusCode}, Json, response::Response};
 use futures_util::TryFutureExt;
 use lipl_core::{LiplRepo, PlaylistPost};
 use super::ListQuery;
@@ -26,15 +26,16 @@ pub async fn list(
     }
 }
 
-/// Handler for getting a specific playlist
+/// Handler for getting a specific playlist, with an etag
 pub async fn item(
     State(connection): State<Arc<dyn LiplRepo>>,
     key: Key,
+    headers: HeaderMap,
 ) -> Response
 {
     connection
         .get_playlist(key.id)
-        .map_ok_or_else(to_error_response, to_json_response(StatusCode::OK))
+        .map_ok_or_else(to_error_response, to_etag_response(&headers))
         .await
 }
 
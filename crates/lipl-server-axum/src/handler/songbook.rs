@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use super::to_error_response;
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use lipl_core::{LiplRepo, RepoDb, Uuid};
+use lipl_songbook::{to_html, to_pdf, to_slides, Fit, Songbook, HTML_CONTENT_TYPE, PDF_CONTENT_TYPE};
+use serde::Deserialize;
+
+const TITLE: &str = "Songbook";
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Html,
+    Pdf,
+}
+
+#[derive(Deserialize)]
+pub struct SongbookQuery {
+    format: Option<Format>,
+}
+
+/// Most lines on a slide and characters on a line, defaults of [`Fit`] if left out
+#[derive(Deserialize)]
+pub struct SlidesQuery {
+    lines: Option<usize>,
+    chars: Option<usize>,
+}
+
+impl From<&SlidesQuery> for Fit {
+    fn from(query: &SlidesQuery) -> Self {
+        let fit = Fit::default();
+        Fit { lines: query.lines.unwrap_or(fit.lines), chars: query.chars.unwrap_or(fit.chars) }
+    }
+}
+
+fn to_songbook_response(format: Format) -> impl Fn(Songbook) -> Response {
+    move |songbook| match format {
+        Format::Html => (StatusCode::OK, [(header::CONTENT_TYPE, HTML_CONTENT_TYPE)], to_html(&songbook)).into_response(),
+        Format::Pdf => match to_pdf(&songbook) {
+            Ok(pdf) => (StatusCode::OK, [(header::CONTENT_TYPE, PDF_CONTENT_TYPE)], pdf).into_response(),
+            Err(error) => to_error_response(error),
+        },
+    }
+}
+
+/// Handler for getting a songbook with the members of a playlist
+pub async fn playlist(
+    State(connection): State<Arc<dyn LiplRepo>>,
+    Path(id): Path<String>,
+    query: Query<SongbookQuery>,
+) -> Response
+{
+    match id.parse::<Uuid>() {
+        Ok(id) =>
+            Songbook::from_playlist(connection.as_ref(), id)
+                .await
+                .map_or_else(to_error_response, to_songbook_response(query.format.unwrap_or_default())),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Handler for getting a songbook with all lyrics
+pub async fn all(
+    State(connection): State<Arc<dyn LiplRepo>>,
+    query: Query<SongbookQuery>,
+) -> Response
+{
+    connection
+        .get_lyrics()
+        .await
+        .map(|lyrics| Songbook::from_repo_db(TITLE, &RepoDb { lyrics, playlists: vec![] }))
+        .map_or_else(to_error_response, to_songbook_response(query.format.unwrap_or_default()))
+}
+
+/// Handler for getting a slideshow with the members of a playlist
+pub async fn slides(
+    State(connection): State<Arc<dyn LiplRepo>>,
+    Path(id): Path<String>,
+    query: Query<SlidesQuery>,
+) -> Response
+{
+    match id.parse::<Uuid>() {
+        Ok(id) =>
+            Songbook::from_playlist(connection.as_ref(), id)
+                .await
+                .map_or_else(
+                    to_error_response,
+                    |songbook| (StatusCode::OK, [(header::CONTENT_TYPE, HTML_CONTENT_TYPE)], to_slides(&songbook, &Fit::from(&*query))).into_response(),
+                ),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
@@ -8,7 +8,7 @@ use tower_http::trace::TraceLayer;
 
 pub use crate::error::Error;
 pub use crate::param::app::LiplApp;
-use crate::handler::{lyric, playlist};
+use crate::handler::{lyric, playlist, songbook};
 
 pub mod constant;
 mod error;
@@ -39,8 +39,12 @@ where
             Router::new().nest(constant::PREFIX, Router::new()
                 .route("/lyric", get(lyric::list).post(lyric::post))
                 .route("/lyric/:id", get(lyric::item).delete(lyric::delete).put(lyric::put))
+                .route("/lyric/:id/timed", get(lyric::timed))
                 .route("/playlist", get(playlist::list).post(playlist::post))
                 .route("/playlist/:id", get(playlist::item).delete(playlist::delete).put(playlist::put))
+                .route("/playlist/:id/songbook", get(songbook::playlist))
+                .route("/playlist/:id/slides", get(songbook::slides))
+                .route("/songbook", get(songbook::all))
             )
             .layer(
                 ServiceBuilder::new()
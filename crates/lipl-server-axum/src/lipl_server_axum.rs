@@ -4,6 +4,7 @@ use axum::Router;
 use clap::Parser;
 use futures_util::TryFutureExt;
 use lipl_server_axum::{constant, create_service, exit_on_signal_int, LiplApp};
+use lipl_core::read_only::switch_on_signal;
 use lipl_core::{Result};
 
 async fn run(service: Router) -> Result<()> {
@@ -26,7 +27,10 @@ pub async fn main() -> Result<()> {
         .with_env_filter(log_filter())
         .init();
 
-    create_service(LiplApp::parse())
+    let app = LiplApp::parse();
+    tokio::spawn(switch_on_signal(app.read_only_switch.clone()));
+
+    create_service(app)
         .and_then(run)
         .await
 }
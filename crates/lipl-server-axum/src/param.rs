@@ -1,77 +1,87 @@
-#[cfg(feature = "postgres")]
 pub mod app {
     use std::sync::Arc;
     use async_trait::async_trait;
-    use clap::{ArgGroup, Parser};
+    use clap::Parser;
     use lipl_core::{LiplRepo, ToRepo};
-    use lipl_repo_memory::MemoryRepoConfig;
+    use lipl_core::layer::RepoBuilder;
+    use lipl_core::normalize::{NormalizeLayer, Normalizer};
+    use lipl_core::read_only::{ReadOnlyLayer, ReadOnlySwitch};
+    use lipl_core::federated::FederatedScheme;
+    use lipl_core::registry::RepoRegistry;
 
-    #[derive(Parser)]
-    #[command(author, version, about, long_about = None)]
-    #[command(group(ArgGroup::new("db").required(true).args(["postgres", "memory"])))]
-    pub struct LiplApp {
-        #[arg(long, group = "db")]
-        pub postgres: Option<String>,
-        #[arg(long, group = "db")]
-        pub memory: Option<bool>,
+    /// Schemes this binary was built with, `federated:` joins repositories of the other schemes
+    pub fn registry() -> RepoRegistry {
+        members().scheme(FederatedScheme::new(members()))
     }
 
-    impl LiplApp {
-        pub fn new_memory(memory: bool) -> Self {
-            Self { postgres: None, memory: Some(memory)}
-        }
-    }
+    /// Schemes of single repositories this binary was built with
+    fn members() -> RepoRegistry {
+        let registry = RepoRegistry::new();
 
-    #[async_trait]
-    impl ToRepo for LiplApp {
-    async fn to_repo(self) -> lipl_core::Result<Arc<dyn LiplRepo>> {
-        if let Some(postgres) = self.postgres {
-            let pool = lipl_axum_postgres::connection_pool(&postgres).await?;
-            Ok(
-                Arc::new(pool)
-            )    
-    
-        }
-        else {
-            let memory = self.memory.unwrap();
-            MemoryRepoConfig { include_sample_data: memory }
-                .to_repo()
-                .await
-        }
-    }
-}
+        #[cfg(feature = "postgres")]
+        let registry = registry.scheme(lipl_axum_postgres::PostgresScheme);
 
+        #[cfg(feature = "memory")]
+        let registry = registry.scheme(lipl_repo_memory::MemoryScheme);
 
-}
+        #[cfg(feature = "file")]
+        let registry = registry.scheme(lipl_repo_fs::FileScheme);
 
-#[cfg(not(feature = "postgres"))]
-pub mod app {
-    use std::{sync::Arc};
-    use async_trait::async_trait;
-    use clap::{Parser};
-    use lipl_core::{LiplRepo, ToRepo};
+        registry
+    }
 
     #[derive(Parser)]
     #[command(author, version, about, long_about = None)]
     pub struct LiplApp {
-        #[arg(long)]
-        pub memory: bool,
-    }    
+        #[arg(long, default_value = "memory:", help = "Repository url, like postgres://user@host/lipl, memory:?sample=true, file:./lyrics?protect or federated:[team=memory:,shared=postgres://user@host/lipl]")]
+        pub repo: String,
+        #[arg(long, hide = true, conflicts_with = "repo", help = "Same as --repo postgres:<connection string>")]
+        pub postgres: Option<String>,
+        #[arg(long, hide = true, conflicts_with_all = ["repo", "postgres"], help = "Same as --repo memory:?sample=<bool>")]
+        pub memory: Option<bool>,
+        #[arg(long, help = "Reject writes with 503 Service Unavailable, SIGUSR1 and SIGUSR2 turn read-only on and off at runtime, which needs the signal feature of lipl-core and Unix")]
+        pub read_only: bool,
+        #[arg(long, help = "Normalize the text of every upserted lyric and playlist title")]
+        pub normalize: bool,
+        #[arg(skip)]
+        pub read_only_switch: ReadOnlySwitch,
+    }
 
     impl LiplApp {
         pub fn new_memory(include_sample_data: bool) -> Self {
             Self {
-                memory: include_sample_data,
+                repo: format!("memory:?sample={include_sample_data}"),
+                postgres: None,
+                memory: None,
+                read_only: false,
+                normalize: false,
+                read_only_switch: Default::default(),
             }
-        }    
+        }
+
+        /// Repository url, from `--repo` or the options it replaced
+        pub fn url(&self) -> String {
+            match (&self.postgres, self.memory) {
+                (Some(postgres), _) if postgres.contains("://") => postgres.clone(),
+                (Some(postgres), _) => format!("postgres:{postgres}"),
+                (None, Some(sample)) => format!("memory:?sample={sample}"),
+                (None, None) => self.repo.clone(),
+            }
+        }
     }
 
     #[async_trait]
     impl ToRepo for LiplApp {
-    async fn to_repo(self) -> lipl_core::Result<Arc<dyn LiplRepo>> {
-        lipl_repo_memory::MemoryRepoConfig { sample_data: self.memory, transaction_log: None }
-            .to_repo()
-            .await
+        async fn to_repo(self) -> lipl_core::Result<Arc<dyn LiplRepo>> {
+            self.read_only_switch.set(self.read_only);
+            let url = self.url();
+            let builder = RepoBuilder::new()
+                .layer(ReadOnlyLayer::new(self.read_only_switch))
+                .option_layer(self.normalize.then(|| NormalizeLayer::new(Normalizer::default())));
+            registry()
+                .open(&url)
+                .await
+                .map(|repo| builder.service(repo))
         }
     }
 }
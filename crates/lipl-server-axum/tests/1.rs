@@ -1,7 +1,7 @@
 use std::vec;
 
 use lipl_server_axum::{create_service, LiplApp};
-use lipl_core::{Lyric, LyricPost, Summary, Playlist, PlaylistPost, Uuid};
+use lipl_core::{LineTime, Lyric, LyricPost, Summary, Playlist, PlaylistPost, TimedLine, TimedLyric, Uuid};
 use axum::{
     body::{Body},
     http::{Request, StatusCode}, Router,
@@ -24,6 +24,8 @@ fn daar_bij_die_molen() -> LyricPost {
                 "Daar bij die molen, die mooie molen".to_owned(),
             ]
         ],
+        extensions: Default::default(),
+        times: None,
     }
 }
 
@@ -39,7 +41,9 @@ fn roodkapje() -> LyricPost {
                 "'k ga naar grootmoeder koekjes brengen in het bos, in het bos".to_owned(),
                 "'k ga naar grootmoeder koekjes brengen in het bos".to_owned(),
             ]
-        ]
+        ],
+        extensions: Default::default(),
+        times: None,
     }
 }
 
@@ -68,6 +72,8 @@ async fn lyric_post() {
     let lyric_post = LyricPost {
         title: "Er is er één jarig".to_owned(),
         parts: vec![],
+        extensions: Default::default(),
+        times: None,
     };
 
     let lyric: Lyric = post(&service, LYRIC, &lyric_post).await;
@@ -166,6 +172,252 @@ async fn playlist_post_lyric_delete() {
     assert_eq!(playlist.members, vec![daar_bij_die_molen.id]);
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn playlist_post_federated() {
+    let app = LiplApp { repo: "federated:[team=memory:,shared=memory:?sample=true]".to_owned(), ..LiplApp::new_memory(false) };
+    let service = create_service(app).await.unwrap();
+
+    let shared: Vec<Summary> = list(&service, LYRIC).await;
+    assert!(!shared.is_empty());
+    let roodkapje: Lyric = post(&service, LYRIC, &roodkapje()).await;
+
+    let playlist_post = PlaylistPost {
+        title: "Gedeeld".to_owned(),
+        members: vec![roodkapje.id, shared[0].id],
+    };
+    let playlist: Playlist = post(&service, PLAYLIST, &playlist_post).await;
+    assert_eq!(playlist.members, vec![roodkapje.id, shared[0].id]);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn lyric_post_read_only() {
+    let app = LiplApp { read_only: true, ..LiplApp::new_memory(false) };
+    let read_only = app.read_only_switch.clone();
+    let service = create_service(app).await.unwrap();
+
+    let response = 
+        service
+        .clone()
+        .oneshot(
+            Request::post(format!("{PREFIX}{LYRIC}"))
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&roodkapje()).unwrap().into())
+            .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let lyrics: Vec<Summary> = list(&service, LYRIC).await;
+    assert_eq!(lyrics.len(), 0);
+
+    read_only.set(false);
+    let lyric: Lyric = post(&service, LYRIC, &roodkapje()).await;
+    assert_eq!(lyric.title, roodkapje().title);
+}
+
+#[cfg(feature = "file")]
+#[tokio::test(flavor = "current_thread")]
+async fn lyric_put_modified_file() {
+    let dir = std::env::temp_dir().join(format!("lipl-server-axum-protect-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(".transaction.log"), "").unwrap();
+    let app = LiplApp { repo: format!("file:{}?protect", dir.to_string_lossy()), ..LiplApp::new_memory(false) };
+    let service = create_service(app).await.unwrap();
+
+    let lyric: Lyric = post(&service, LYRIC, &roodkapje()).await;
+    let path = dir.join(format!("{}.md", lyric.id));
+    std::fs::write(&path, format!("{}\nIn het bos\n", std::fs::read_to_string(&path).unwrap())).unwrap();
+
+    for request in [
+        Request::put(format!("{PREFIX}{LYRIC}/{}", lyric.id))
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&roodkapje()).unwrap().into())
+        .unwrap(),
+        Request::delete(format!("{PREFIX}{LYRIC}/{}", lyric.id))
+        .body(Body::empty())
+        .unwrap(),
+    ] {
+        let response = service.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+    assert!(std::fs::read_to_string(&path).unwrap().ends_with("In het bos\n"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn repo_options_replaced_by_url() {
+    use clap::Parser;
+    assert_eq!(LiplApp::parse_from(["lipl", "--memory", "true"]).url(), "memory:?sample=true");
+    assert_eq!(LiplApp::parse_from(["lipl", "--postgres", "host=localhost user=paul"]).url(), "postgres:host=localhost user=paul");
+    assert_eq!(LiplApp::parse_from(["lipl", "--postgres", "postgres://paul@localhost/lipl"]).url(), "postgres://paul@localhost/lipl");
+    assert_eq!(LiplApp::parse_from(["lipl"]).url(), "memory:");
+    assert!(LiplApp::try_parse_from(["lipl", "--repo", "memory:", "--memory", "true"]).is_err());
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn lyric_post_normalized() {
+    let app = LiplApp { normalize: true, ..LiplApp::new_memory(false) };
+    let service = create_service(app).await.unwrap();
+
+    let lyric: Lyric = post(&service, LYRIC, &LyricPost { title: " Roodkapje\u{2019}s  bos ".to_owned(), ..roodkapje() }).await;
+    assert_eq!(lyric.title, "Roodkapje's bos");
+    assert_eq!(lyric.parts, roodkapje().parts);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn lyric_item_etag() {
+    let service = create_service(LiplApp::new_memory(false)).await.unwrap();
+    let lyric: Lyric = post(&service, LYRIC, &roodkapje()).await;
+    let get = |if_none_match: Option<String>| {
+        let mut request = Request::get(format!("{PREFIX}{LYRIC}/{}", lyric.id));
+        if let Some(etag) = if_none_match {
+            request = request.header("If-None-Match", etag);
+        }
+        service.clone().oneshot(request.body(Body::empty()).unwrap())
+    };
+
+    let response = get(None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = response.headers()["ETag"].to_str().unwrap().to_owned();
+    assert_eq!(etag, lipl_core::Etag::etag(&lyric));
+
+    let response = get(Some(etag.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()["ETag"], etag.as_str());
+
+    let _: Lyric = put(&service, LYRIC, lyric.id.to_string(), &daar_bij_die_molen()).await;
+    assert_eq!(get(Some(etag)).await.unwrap().status(), StatusCode::OK);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn lyric_item_openlyrics() {
+    let service = create_service(LiplApp::new_memory(false)).await.unwrap();
+    let lyric: Lyric = post(&service, LYRIC, &roodkapje()).await;
+
+    let response =
+        service
+        .clone()
+        .oneshot(
+            Request::get(format!("{PREFIX}{LYRIC}/{}", lyric.id))
+            .header("Accept", "application/openlyrics+xml, application/json;q=0.5")
+            .body(Body::empty())
+            .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["Content-Type"], "application/openlyrics+xml");
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let song = std::str::from_utf8(&body).unwrap().parse::<lipl_core::openlyrics::Song>().unwrap();
+    assert_eq!(LyricPost::from(song).parts, roodkapje().parts);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn lyric_post_openlyrics() {
+    let service = create_service(LiplApp::new_memory(false)).await.unwrap();
+    let xml = lipl_core::openlyrics::Song::from(&Lyric::from((None, roodkapje()))).to_string();
+
+    let response =
+        service
+        .clone()
+        .oneshot(
+            Request::post(format!("{PREFIX}{LYRIC}"))
+            .header("Content-Type", "application/openlyrics+xml")
+            .body(xml.into())
+            .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    let lyric: Lyric = serde_json::from_slice(&body).unwrap();
+    assert_eq!(lyric.title, roodkapje().title);
+    assert_eq!(lyric.parts, roodkapje().parts);
+
+    let response =
+        service
+        .clone()
+        .oneshot(
+            Request::put(format!("{PREFIX}{LYRIC}/{}", lyric.id))
+            .header("Content-Type", "application/openlyrics+xml")
+            .body("<hymn/>".into())
+            .unwrap()
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn lyric_item_timed() {
+    let service = create_service(LiplApp::new_memory(false)).await.unwrap();
+    let lyric_post = LyricPost {
+        title: "Roodkapje".to_owned(),
+        parts: vec![vec!["Zeg roodkapje waar ga je hene".to_owned(), "zo alleen".to_owned()]],
+        extensions: Default::default(),
+        times: Some(vec![vec!["00:01.50".parse().unwrap(), LineTime(None)]]),
+    };
+    let lyric: Lyric = post(&service, LYRIC, &lyric_post).await;
+    assert_eq!(lyric.parts, lyric_post.parts);
+    assert_eq!(lyric.times, lyric_post.times);
+
+    let timed: TimedLyric = item(&service, LYRIC, format!("{}/timed", lyric.id)).await;
+    assert_eq!(timed.parts[0][0], TimedLine { time: Some(1500), text: "Zeg roodkapje waar ga je hene".to_owned() });
+    assert_eq!(timed.parts[0][1], TimedLine { time: None, text: "zo alleen".to_owned() });
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn playlist_songbook() {
+    let service = create_service(LiplApp::new_memory(false)).await.unwrap();
+    let lyric1: Lyric = post(&service, LYRIC, &roodkapje()).await;
+    let lyric2: Lyric = post(&service, LYRIC, &daar_bij_die_molen()).await;
+    let playlist_post = PlaylistPost { title: "Kinderliedjes".to_owned(), members: vec![lyric1.id, lyric2.id] };
+    let playlist: Playlist = post(&service, PLAYLIST, &playlist_post).await;
+
+    let (content_type, body) = songbook(&service, format!("{PREFIX}{PLAYLIST}/{}/songbook", playlist.id)).await;
+    assert_eq!(content_type, "text/html; charset=utf-8");
+    let html = String::from_utf8(body).unwrap();
+    assert!(html.contains("<h1>Kinderliedjes</h1>"));
+    assert!(html.find("<span class=\"number\">1</span>Roodkapje</h2>").unwrap() < html.find("<span class=\"number\">2</span>Daar bij die molen</h2>").unwrap());
+
+    let (content_type, body) = songbook(&service, format!("{PREFIX}songbook?format=pdf")).await;
+    assert_eq!(content_type, "application/pdf");
+    assert!(body.starts_with(b"%PDF"));
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn playlist_slides() {
+    let service = create_service(LiplApp::new_memory(false)).await.unwrap();
+    let lyric: Lyric = post(&service, LYRIC, &roodkapje()).await;
+    let playlist_post = PlaylistPost { title: "Kinderliedjes".to_owned(), members: vec![lyric.id] };
+    let playlist: Playlist = post(&service, PLAYLIST, &playlist_post).await;
+
+    let (content_type, body) = songbook(&service, format!("{PREFIX}{PLAYLIST}/{}/slides?lines=1&chars=30", playlist.id)).await;
+    assert_eq!(content_type, "text/html; charset=utf-8");
+    let html = String::from_utf8(body).unwrap();
+    assert!(html.contains("<section class=\"title\"><h1>Roodkapje</h1>"));
+    assert!(html.contains("<section><div>Zeg roodkapje waar ga je</div></section>"));
+}
+
+async fn songbook(service: &Router<()>, uri: String) -> (String, Vec<u8>) {
+    let response = service
+        .clone()
+        .oneshot(
+            Request::get(uri)
+            .body(Body::empty())
+            .unwrap()
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let content_type = response.headers()["content-type"].to_str().unwrap().to_owned();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (content_type, body.to_vec())
+}
+
 async fn list<R: DeserializeOwned>(service: &Router<()>, name: &'static str) -> Vec<R> {
     let response = service
         .clone()
@@ -1,17 +1,74 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use lipl_core::{LiplRepo, RepoDb};
-use tracing::{info};
+use lipl_core::{LiplRepo, RepoDb, Uuid};
+use lipl_core::dump::{stream_in, stream_out, Dump, DumpFormat};
+use lipl_core::import::Report;
+use lipl_core::fsck::{check, repair as repair_problems, Problem};
+use lipl_core::lint::{diff_lyric, Linter, Severity};
+use lipl_core::normalize::{normalize_repo, Normalizer};
+use lipl_core::transaction::build_from_log;
+use lipl_archive::Archive;
+use lipl_songbook::{Site, Songbook};
+use crate::format::Format;
+use crate::repo::RepoConfig;
+use tracing::{info, warn};
 
-pub async fn list(repo: Arc<dyn LiplRepo>, yaml: bool) -> lipl_core::Result<()>
+pub async fn list(repo: Arc<dyn LiplRepo>, format: Option<DumpFormat>) -> lipl_core::Result<()>
 {
-    let db = RepoDb {
-        lyrics: repo.get_lyrics().await?,
-        playlists: repo.get_playlists().await?,
-    };
+    match format {
+        Some(DumpFormat::Ndjson) => stream_out(repo.as_ref(), std::io::stdout().lock()).await,
+        Some(format) => {
+            let db = RepoDb::from_repo(repo.as_ref()).await?;
+            db.save(format, std::io::stdout().lock())?;
+            println!();
+            Ok(())
+        },
+        None => {
+            println!("{}", RepoDb::from_repo(repo.as_ref()).await?);
+            Ok(())
+        },
+    }
+}
 
-    println!("{}", if yaml { db.to_yaml().unwrap() } else { db.to_string() }) ;
-    Ok(())
+fn dump_format(format: Option<DumpFormat>, path: &Path) -> lipl_core::Result<DumpFormat> {
+    format.or_else(|| DumpFormat::from_path(path)).ok_or(lipl_core::Error::Argument("unknown dump format, use --format"))
+}
+
+pub async fn dump(source: Arc<dyn LiplRepo>, format: Option<DumpFormat>, output: PathBuf) -> lipl_core::Result<()>
+{
+    let format = dump_format(format, &output)?;
+    info!("Dumping to {} as {format}", output.to_string_lossy());
+    let file = BufWriter::new(File::create(&output)?);
+    match format {
+        DumpFormat::Ndjson => stream_out(source.as_ref(), file).await,
+        format => RepoDb::from_repo(source.as_ref()).await?.save(format, file),
+    }
+}
+
+pub async fn load(file: PathBuf, format: Option<DumpFormat>, target: Arc<dyn LiplRepo>) -> lipl_core::Result<()>
+{
+    let format = dump_format(format, &file)?;
+    info!("Loading {} as {format}", file.to_string_lossy());
+    let reader = BufReader::new(File::open(&file)?);
+    let (lyrics, playlists) = match format {
+        DumpFormat::Ndjson => stream_in(reader, target.as_ref()).await?,
+        format => {
+            let db = RepoDb::load(format, reader)?;
+            let count = (db.lyrics.len(), db.playlists.len());
+            for lyric in db.lyrics {
+                target.upsert_lyric(lyric).await?;
+            }
+            for playlist in db.playlists {
+                target.upsert_playlist(playlist).await?;
+            }
+            count
+        },
+    };
+    info!("Loaded {lyrics} lyrics and {playlists} playlists");
+    target.stop().await
 }
 
 pub async fn copy(source: Arc<dyn LiplRepo>, target: Arc<dyn LiplRepo>) -> lipl_core::Result<()>
@@ -28,3 +85,265 @@ pub async fn copy(source: Arc<dyn LiplRepo>, target: Arc<dyn LiplRepo>) -> lipl_
 
     Ok(())
 }
+
+pub async fn replay(logs: Vec<PathBuf>, target: Arc<dyn LiplRepo>) -> lipl_core::Result<()>
+{
+    for log in logs {
+        info!("Replaying transaction log {}", log.to_string_lossy());
+        build_from_log(std::fs::File::open(log)?, target.as_ref()).await?;
+    }
+    target.stop().await
+}
+
+fn files_of(paths: Vec<PathBuf>, format: Format) -> lipl_core::Result<Vec<PathBuf>>
+{
+    let mut files = vec![];
+    for path in paths {
+        if path.is_dir() {
+            let mut entries = 
+                std::fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.retain(|entry| entry.extension().and_then(|ext| ext.to_str()).map(|ext| format.extensions().contains(&ext.to_lowercase().as_str())) == Some(true));
+            entries.sort();
+            files.extend(entries);
+        }
+        else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+pub async fn import(paths: Vec<PathBuf>, format: Format, target: RepoConfig, dry_run: bool) -> lipl_core::Result<()>
+{
+    let (dirs, paths): (Vec<_>, Vec<_>) = paths.into_iter().partition(|path| matches!(format, Format::Opensong) && path.is_dir());
+    let mut report = Report::default();
+    for dir in dirs {
+        let opensong = lipl_core::opensong::import_dir(&dir)?;
+        report.lyrics.extend(opensong.lyrics);
+        report.playlists.extend(opensong.playlists);
+        report.skipped.extend(opensong.skipped);
+    }
+    for file in files_of(paths, format)? {
+        let lyric_post = format.parse(&std::fs::read_to_string(&file)?)?;
+        report.lyrics.push(((None, lyric_post).into(), vec![]));
+    }
+    let report = report.import(target, dry_run).await?;
+    println!("{report}");
+    Ok(())
+}
+
+pub async fn export(source: Arc<dyn LiplRepo>, format: Format, dir: PathBuf, playlist: Option<Uuid>) -> lipl_core::Result<()>
+{
+    std::fs::create_dir_all(&dir)?;
+    let lyrics = match playlist {
+        Some(id) => {
+            let mut lyrics = vec![];
+            for (index, member) in source.get_playlist(id).await?.members.into_iter().enumerate() {
+                let lyric = source.get_lyric(member).await?;
+                lyrics.push((format!("{:02}-{}", index + 1, lyric.id), lyric));
+            }
+            lyrics
+        },
+        None => source.get_lyrics().await?.into_iter().map(|lyric| (lyric.id.to_string(), lyric)).collect(),
+    };
+    for (name, lyric) in lyrics {
+        let file = dir.join(name).with_extension(format.extension());
+        info!("Exporting lyric {} with id {} to {}", lyric.title, lyric.id, file.to_string_lossy());
+        std::fs::write(file, format.render(&lyric))?;
+    }
+    Ok(())
+}
+
+pub async fn pack(source: Arc<dyn LiplRepo>, output: PathBuf, attachments: Vec<PathBuf>) -> lipl_core::Result<()>
+{
+    let mut archive = Archive::from_repo(source.as_ref()).await?;
+    for attachment in attachments {
+        let name = attachment.file_name().map(|name| name.to_string_lossy().to_string()).ok_or(lipl_core::Error::Argument("attachment without file name"))?;
+        archive = archive.attach(&name, std::fs::read(&attachment)?);
+    }
+    info!("Packing {} lyrics, {} playlists and {} attachments to {}", archive.lyrics.len(), archive.playlists.len(), archive.attachments.len(), output.to_string_lossy());
+    archive.write(BufWriter::new(File::create(output)?))?;
+    Ok(())
+}
+
+pub async fn unpack(file: PathBuf, target: Arc<dyn LiplRepo>, attachments: Option<PathBuf>) -> lipl_core::Result<()>
+{
+    let archive = Archive::read(BufReader::new(File::open(&file)?))?;
+    info!("Unpacking {} lyrics and {} playlists from {}", archive.lyrics.len(), archive.playlists.len(), file.to_string_lossy());
+    archive.import(target.as_ref()).await?;
+    if let Some(dir) = attachments {
+        for (name, contents) in archive.attachments.iter() {
+            let path = Path::new(name);
+            if !path.components().all(|component| matches!(component, std::path::Component::Normal(_))) {
+                warn!("Skipping attachment {name} outside the attachments directory");
+                continue;
+            }
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, contents)?;
+        }
+    }
+    target.stop().await
+}
+
+pub async fn songbook(source: Arc<dyn LiplRepo>, playlist: Option<Uuid>, title: String, output: PathBuf) -> lipl_core::Result<()>
+{
+    let songbook = match playlist {
+        Some(id) => Songbook::from_playlist(source.as_ref(), id).await?,
+        None => {
+            let db = RepoDb { lyrics: source.get_lyrics().await?, playlists: vec![] };
+            Songbook::from_repo_db(&title, &db)
+        },
+    };
+    info!("Writing songbook {} with {} songs to {}", songbook.title, songbook.songs.len(), output.to_string_lossy());
+    if output.extension().map(|extension| extension.eq_ignore_ascii_case("pdf")).unwrap_or_default() {
+        std::fs::write(output, lipl_songbook::to_pdf(&songbook)?)?;
+    }
+    else {
+        std::fs::write(output, lipl_songbook::to_html(&songbook))?;
+    }
+    Ok(())
+}
+
+pub async fn site(source: Arc<dyn LiplRepo>, title: String, dir: PathBuf) -> lipl_core::Result<()>
+{
+    let site = Site::from_repo(&title, source.as_ref()).await?;
+    info!("Writing {} files of site {} to {}", site.files.len(), title, dir.to_string_lossy());
+    site.write(dir)
+}
+
+pub async fn normalize(source: Arc<dyn LiplRepo>, normalizer: Normalizer, dry_run: bool) -> lipl_core::Result<()>
+{
+    let (lyrics, playlists) = normalize_repo(source.as_ref(), &normalizer, dry_run).await?;
+    let verb = if dry_run { "Would normalize" } else { "Normalized" };
+    println!("{verb} {lyrics} lyrics and {playlists} playlists");
+    source.stop().await
+}
+
+/// Prints the diagnostics and with `fix` the diff of the fixes, fails if errors remain
+pub async fn lint(source: Arc<dyn LiplRepo>, linter: Linter, fix: bool, dry_run: bool) -> lipl_core::Result<()>
+{
+    let (mut count, mut errors) = (0, 0);
+    for lyric in source.get_lyrics().await? {
+        let diagnostics = linter.check(&lyric);
+        if diagnostics.is_empty() {
+            continue;
+        }
+        println!("{} {}", lyric.id, lyric.title);
+        for diagnostic in diagnostics.iter() {
+            println!("  {diagnostic}");
+        }
+        count += diagnostics.len();
+        let remaining = if fix {
+            let fixed = linter.fix(&lyric);
+            print!("{}", diff_lyric(&lyric, &fixed));
+            if dry_run {
+                diagnostics
+            }
+            else {
+                let remaining = linter.check(&fixed);
+                source.upsert_lyric(fixed).await?;
+                remaining
+            }
+        }
+        else {
+            diagnostics
+        };
+        errors += remaining.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).count();
+    }
+    println!("{count} problems, {errors} errors{}", if fix && !dry_run { " after fixing" } else { "" });
+    source.stop().await?;
+    if errors > 0 { Err(lipl_core::Error::Argument("lyrics with lint errors")) } else { Ok(()) }
+}
+
+/// Files the file repository cannot read, they have to be out of the way before it opens
+#[cfg(feature = "file")]
+async fn unreadable_files(source: &RepoConfig, repair: bool) -> lipl_core::Result<usize>
+{
+    if source.url().scheme != "file" {
+        return Ok(0);
+    }
+    let unreadable = lipl_repo_fs::unreadable(source.url().path()).await?;
+    for file in unreadable.iter() {
+        println!("unreadable {file}");
+        if repair {
+            println!("  moved to {}", lipl_repo_fs::quarantine(&file.path)?.to_string_lossy());
+        }
+    }
+    Ok(if repair { 0 } else { unreadable.len() })
+}
+
+/// Prints the problems and with `repair` repairs them, fails if repairable problems remain or reading failed
+pub async fn fsck(source: RepoConfig, repair: bool) -> lipl_core::Result<()>
+{
+    #[cfg(feature = "file")]
+    let unreadable = unreadable_files(&source, repair).await?;
+    #[cfg(not(feature = "file"))]
+    let unreadable = 0;
+    if unreadable > 0 {
+        println!("{unreadable} unreadable files, the repository cannot be checked until they are repaired");
+        return Err(lipl_core::Error::Argument("repository with unreadable files"));
+    }
+
+    let source = source.build_repo().await?;
+    let problems = check(source.as_ref()).await?;
+    for problem in problems.iter() {
+        println!("{problem}");
+    }
+    let repairable = problems.iter().filter(|problem| problem.repairable()).count();
+    let repaired = if repair { repair_problems(source.as_ref(), &problems).await? } else { 0 };
+    println!("{} problems, {repaired} repaired", problems.len());
+    source.stop().await?;
+    let read_errors = problems.iter().any(|problem| matches!(problem, Problem::Unreadable { .. }));
+    if repaired < repairable || read_errors { Err(lipl_core::Error::Argument("repository with problems")) } else { Ok(()) }
+}
+
+#[cfg(feature = "file")]
+pub async fn status(dir: PathBuf, accept: bool, migrate: bool) -> lipl_core::Result<()>
+{
+    use lipl_repo_fs::FileStatus;
+    let status = lipl_repo_fs::status(&dir).await?;
+    for (summary, status) in status.iter().filter(|(_, status)| *status != FileStatus::Unchanged) {
+        println!("{status:<10} {} {}", summary.id, summary.title);
+    }
+    let modified = status.iter().filter(|(_, status)| *status == FileStatus::Modified).map(|(summary, _)| summary.id).collect::<Vec<_>>();
+    if accept {
+        for id in modified.iter() {
+            lipl_repo_fs::accept(&dir, *id).await?;
+        }
+        println!("Accepted {} modified lyrics", modified.len());
+    }
+    else {
+        println!("{} of {} lyrics modified outside lipl", modified.len(), status.len());
+    }
+    if migrate {
+        println!("Migrated {} lyrics with a legacy hash", lipl_repo_fs::migrate(&dir).await?);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "openlp")]
+pub async fn openlp(command: crate::param::OpenlpCommand) -> lipl_core::Result<()>
+{
+    let mut openlp = lipl_openlp::OpenLp::open(&command.database)?;
+    for service in command.service {
+        openlp = openlp.service(service)?;
+    }
+    let report = openlp.import(*command.target, command.dry_run).await?;
+    println!("{report}");
+    Ok(())
+}
+
+#[cfg(feature = "document")]
+pub async fn document(command: crate::param::DocumentCommand) -> lipl_core::Result<()>
+{
+    let documents = command.files.iter().map(lipl_document::Document::open).collect::<Result<Vec<_>, _>>()?;
+    let options = lipl_document::Options { level: command.heading_level, playlist: command.playlist };
+    let report = lipl_document::import(&documents, *command.target, &options, command.dry_run).await?;
+    println!("{report}");
+    Ok(())
+}
@@ -1,10 +1,14 @@
 use std::sync::Arc;
-use warp::{body, path, Filter};
+use warp::{body, header, path, Filter};
 use warp::filters::query;
 use lipl_core::{LiplRepo};
+use lipl_core::openlyrics::is_openlyrics;
 use crate::constant::{API, VERSION};
 use crate::handler::lyric as lyric_handler;
+use crate::handler::openlyrics as openlyrics_handler;
 use crate::handler::playlist as playlist_handler;
+use crate::handler::slides as slides_handler;
+use crate::handler::timed as timed_handler;
 
 macro_rules! join_paths {
     ($head:expr, $($rest:expr),*) => { warp::path($head)$(.and(warp::path($rest)))* };
@@ -27,7 +31,7 @@ macro_rules! create_fn {
         
             let list         = and! (warp::get()   , prefix, path::end()  , repo_filter.clone(), query::query() ) .and_then($handler::list);
             let summaries    = and! (warp::get()   , prefix, path::end()  , repo_filter.clone()                 ) .and_then($handler::list_summary);
-            let item         = and! (warp::get()   , prefix, path::param(), repo_filter.clone()                 ) .and_then($handler::item);
+            let item         = and! (warp::get()   , prefix, path::param(), repo_filter.clone(), header::optional("if-none-match")) .and_then($handler::item);
             let post         = and! (warp::post()  , prefix, path::end()  , repo_filter.clone(), body::json()   ) .and_then($handler::post);
             let put          = and! (warp::put()   , prefix, path::param(), repo_filter.clone(), body::json()   ) .and_then($handler::put);
             let delete       = and! (warp::delete(), prefix, path::param(), repo_filter.clone()                 ) .and_then($handler::delete);
@@ -38,5 +42,51 @@ macro_rules! create_fn {
 }
 
 create_fn!(get_lyric_routes, lyric_handler);
+
+/// Lyric with the time of each line split from its text
+pub fn get_timed_route(repo: Arc<dyn LiplRepo>, name: &'static str) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+{
+    let repo_filter = warp::any().map(move || repo.clone());
+    let prefix = join_paths!(API, VERSION, name);
+
+    and! (warp::get(), prefix, path::param(), path("timed"), path::end(), repo_filter) .and_then(timed_handler::item)
+}
+
+/// Playlist as HTML slideshow
+pub fn get_slides_route(repo: Arc<dyn LiplRepo>, name: &'static str) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+{
+    let repo_filter = warp::any().map(move || repo.clone());
+    let prefix = join_paths!(API, VERSION, name);
+
+    and! (warp::get(), prefix, path::param(), path("slides"), path::end(), repo_filter, query::query()) .and_then(slides_handler::item)
+}
+
+/// Passes requests with OpenLyrics in the header, like `accept` or `content-type`
+fn openlyrics_header(name: &'static str) -> impl Filter<Extract = (), Error = warp::Rejection> + Clone
+{
+    header::optional::<String>(name)
+        .and_then(|value: Option<String>| async move {
+            if value.as_deref().map(is_openlyrics).unwrap_or_default() {
+                Ok(())
+            }
+            else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one()
+}
+
+/// Lyric as OpenLyrics xml for clients that accept it, and lyrics posted or put as OpenLyrics xml
+pub fn get_openlyrics_routes(repo: Arc<dyn LiplRepo>, name: &'static str) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone
+{
+    let repo_filter = warp::any().map(move || repo.clone());
+    let prefix = join_paths!(API, VERSION, name);
+
+    let item = and! (warp::get() , prefix, path::param(), path::end(), openlyrics_header("accept")      , repo_filter.clone()               ) .and_then(openlyrics_handler::item);
+    let post = and! (warp::post(), prefix, path::end()  ,              openlyrics_header("content-type"), repo_filter.clone(), body::bytes()) .and_then(openlyrics_handler::post);
+    let put  = and! (warp::put() , prefix, path::param(), path::end(), openlyrics_header("content-type"), repo_filter.clone(), body::bytes()) .and_then(openlyrics_handler::put);
+
+    or!(item, post, put)
+}
 create_fn!(get_playlist_routes, playlist_handler);
 
@@ -0,0 +1,54 @@
+use clap::ValueEnum;
+use lipl_core::{Lyric, LyricPost};
+use lipl_core::openlyrics::{self, Song};
+use lipl_core::opensong;
+use parts::{chordpro, lrc};
+
+/// Exchange formats for import and export of lyrics
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Format {
+    /// Markdown with frontmatter, the disk format of the file repository
+    Markdown,
+    Openlyrics,
+    Chordpro,
+    Opensong,
+    Lrc,
+}
+
+impl Format {
+    /// Extension of exported files, OpenSong songs have none
+    pub fn extension(&self) -> &'static str {
+        self.extensions().first().copied().unwrap_or_default()
+    }
+
+    /// Extensions of the files to import from a directory
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            Format::Markdown => &["md"],
+            Format::Openlyrics => &[openlyrics::EXTENSION],
+            Format::Chordpro => &chordpro::EXTENSIONS,
+            Format::Opensong => &[],
+            Format::Lrc => &[lrc::EXTENSION],
+        }
+    }
+
+    pub fn parse(&self, s: &str) -> lipl_core::Result<LyricPost> {
+        match self {
+            Format::Markdown => s.parse::<LyricPost>(),
+            Format::Openlyrics => s.parse::<Song>().map(LyricPost::from),
+            Format::Chordpro => Ok(LyricPost::from(chordpro::parse(s))),
+            Format::Opensong => s.parse::<opensong::Song>().map(LyricPost::from),
+            Format::Lrc => Ok(LyricPost::from(lrc::parse(s))),
+        }
+    }
+
+    pub fn render(&self, lyric: &Lyric) -> String {
+        match self {
+            Format::Markdown => lyric.to_string(),
+            Format::Openlyrics => Song::from(lyric).to_string(),
+            Format::Chordpro => chordpro::render(&lyric.title, &lyric.parts),
+            Format::Opensong => opensong::Song::from(lyric).to_string(),
+            Format::Lrc => lrc::render(&lyric.title, &lyric.timed()),
+        }
+    }
+}
@@ -3,9 +3,11 @@ macro_rules! create_handler {
     ($name:ident, $list:ident, $summaries:ident, $item:ident, $delete:ident, $update:ident, $post_type:path, $posted_type:path) => {
         pub mod $name {
             use std::sync::Arc;
-            use lipl_core::{LiplRepo, Uuid};
+            use lipl_core::{Etag, LiplRepo, Uuid};
+            use lipl_core::hash::if_none_match;
             use warp::{Reply, Rejection};
-            use warp::reply::{json, with_status};
+            use warp::reply::{json, with_header, with_status};
+            use warp::http::header::ETAG;
             use warp::http::status::StatusCode;
             use crate::model::{Query};
             use crate::error::{RepoError};
@@ -30,11 +32,20 @@ macro_rules! create_handler {
                 warp::reject::custom::<RepoError>(e.into())
             }
 
-            pub async fn item(id: String, repo: Arc<dyn LiplRepo>) -> Result<impl Reply, Rejection>
+            /// Json with the etag of the content, or 304 Not Modified when the request has the etag in `If-None-Match`
+            pub async fn item(id: String, repo: Arc<dyn LiplRepo>, none_match: Option<String>) -> Result<impl Reply, Rejection>
             {
                 let uuid = id.parse::<Uuid>().map_err(reject)?;
                 let data = repo.$item(uuid).await.map_err(reject)?;
-                Ok(json(&data))
+                let etag = data.etag();
+                let reply =
+                    if none_match.map(|header| if_none_match(&header, &etag)).unwrap_or_default() {
+                        with_status(warp::reply(), StatusCode::NOT_MODIFIED).into_response()
+                    }
+                    else {
+                        json(&data).into_response()
+                    };
+                Ok(with_header(reply, ETAG, etag))
             }
 
             pub async fn post(
@@ -90,3 +101,84 @@ create_handler! (
     lipl_core::PlaylistPost,
     lipl_core::Playlist
 );
+
+pub mod openlyrics {
+    use std::sync::Arc;
+    use lipl_core::{LiplRepo, Lyric, Uuid};
+    use lipl_core::openlyrics::{lyric_post, Song, CONTENT_TYPE};
+    use warp::{Reply, Rejection};
+    use warp::hyper::body::Bytes;
+    use warp::reply::{json, with_header, with_status};
+    use warp::http::header::CONTENT_TYPE as CONTENT_TYPE_HEADER;
+    use warp::http::status::StatusCode;
+    use crate::error::{RepoError};
+
+    fn reject<E: Into<RepoError>>(e: E) -> Rejection {
+        warp::reject::custom::<RepoError>(e.into())
+    }
+
+    pub async fn item(id: String, repo: Arc<dyn LiplRepo>) -> Result<impl Reply, Rejection>
+    {
+        let uuid = id.parse::<Uuid>().map_err(reject)?;
+        let lyric = repo.get_lyric(uuid).await.map_err(reject)?;
+        Ok(with_header(Song::from(&lyric).to_string(), CONTENT_TYPE_HEADER, CONTENT_TYPE))
+    }
+
+    pub async fn post(repo: Arc<dyn LiplRepo>, body: Bytes) -> Result<impl Reply, Rejection>
+    {
+        let lyric: Lyric = (None, lyric_post(&body).map_err(reject)?).into();
+        let data = repo.upsert_lyric(lyric).await.map_err(reject)?;
+        Ok(with_status(json(&data), StatusCode::CREATED))
+    }
+
+    pub async fn put(id: String, repo: Arc<dyn LiplRepo>, body: Bytes) -> Result<impl Reply, Rejection>
+    {
+        let uuid = id.parse::<Uuid>().map_err(reject)?;
+        let lyric: Lyric = (Some(uuid), lyric_post(&body).map_err(reject)?).into();
+        let data = repo.upsert_lyric(lyric).await.map_err(reject)?;
+        Ok(json(&data))
+    }
+}
+
+pub mod timed {
+    use std::sync::Arc;
+    use lipl_core::{LiplRepo, TimedLyric, Uuid};
+    use warp::{Reply, Rejection};
+    use warp::reply::json;
+    use crate::error::{RepoError};
+
+    fn reject<E: Into<RepoError>>(e: E) -> Rejection {
+        warp::reject::custom::<RepoError>(e.into())
+    }
+
+    pub async fn item(id: String, repo: Arc<dyn LiplRepo>) -> Result<impl Reply, Rejection>
+    {
+        let uuid = id.parse::<Uuid>().map_err(reject)?;
+        let lyric = repo.get_lyric(uuid).await.map_err(reject)?;
+        Ok(json(&TimedLyric::from(lyric)))
+    }
+}
+
+pub mod slides {
+    use std::sync::Arc;
+    use lipl_core::{LiplRepo, Uuid};
+    use lipl_songbook::{to_slides, Fit, Songbook, HTML_CONTENT_TYPE};
+    use warp::{Reply, Rejection};
+    use warp::reply::with_header;
+    use warp::http::header::CONTENT_TYPE as CONTENT_TYPE_HEADER;
+    use crate::error::{RepoError};
+    use crate::model::SlidesQuery;
+
+    fn reject<E: Into<RepoError>>(e: E) -> Rejection {
+        warp::reject::custom::<RepoError>(e.into())
+    }
+
+    pub async fn item(id: String, repo: Arc<dyn LiplRepo>, query: SlidesQuery) -> Result<impl Reply, Rejection>
+    {
+        let uuid = id.parse::<Uuid>().map_err(reject)?;
+        let songbook = Songbook::from_playlist(repo.as_ref(), uuid).await.map_err(reject)?;
+        let fit = Fit::default();
+        let fit = Fit { lines: query.lines.unwrap_or(fit.lines), chars: query.chars.unwrap_or(fit.chars) };
+        Ok(with_header(to_slides(&songbook, &fit), CONTENT_TYPE_HEADER, HTML_CONTENT_TYPE))
+    }
+}
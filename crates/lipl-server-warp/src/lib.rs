@@ -3,6 +3,7 @@ pub mod constant;
 pub mod db;
 mod error;
 mod filter;
+mod format;
 pub mod message;
 mod model;
 pub mod param;
@@ -10,6 +11,10 @@ mod recover;
 mod repo;
 pub mod serve;
 
+use lipl_core::layer::RepoBuilder;
+use lipl_core::normalize::NormalizeLayer;
+use lipl_core::read_only::{ReadOnlyLayer, ReadOnlySwitch};
+use lipl_core::transaction::TransactionLogLayer;
 use param::{LiplApp, LiplCommand};
 use clap::{Parser};
 use futures::TryFutureExt;
@@ -18,8 +23,16 @@ pub async fn run() -> lipl_core::Result<()> {
     let cli = LiplApp::parse();
     match cli.command {
         LiplCommand::Serve(serve) => {
+            let read_only = ReadOnlySwitch::new(serve.read_only);
+            let transaction_log = serve.transaction_log().as_ref().map(TransactionLogLayer::new).transpose()?;
+            let builder = 
+                RepoBuilder::new()
+                .layer(ReadOnlyLayer::new(read_only.clone()))
+                .option_layer(serve.normalizer().map(NormalizeLayer::new))
+                .option_layer(transaction_log);
             serve.source.build_repo()
-            .and_then(|source| crate::serve::run(source, serve.port))
+            .map_ok(|source| builder.service(source))
+            .and_then(|source| crate::serve::run(source, serve.port, read_only))
             .await
         },
         LiplCommand::Copy(copy) => {
@@ -29,9 +42,79 @@ pub async fn run() -> lipl_core::Result<()> {
             .await
         },
         LiplCommand::List(list) => {
+            let format = list.format();
             list.source.build_repo()
-            .and_then(|source| crate::db::list(source, list.yaml))
+            .and_then(|source| crate::db::list(source, format))
             .await
+        },
+        LiplCommand::Dump(dump) => {
+            dump.source.build_repo()
+            .and_then(|source| crate::db::dump(source, dump.format, dump.output))
+            .await
+        },
+        LiplCommand::Load(load) => {
+            load.target.build_repo()
+            .and_then(|target| crate::db::load(load.file, load.format, target))
+            .await
+        },
+        LiplCommand::Pack(pack) => {
+            pack.source.build_repo()
+            .and_then(|source| crate::db::pack(source, pack.output, pack.attachment))
+            .await
+        },
+        LiplCommand::Unpack(unpack) => {
+            unpack.target.build_repo()
+            .and_then(|target| crate::db::unpack(unpack.file, target, unpack.attachments))
+            .await
+        },
+        LiplCommand::Replay(replay) => {
+            replay.target.build_repo()
+            .and_then(|target| crate::db::replay(replay.log, target))
+            .await
+        },
+        LiplCommand::Import(import) => {
+            crate::db::import(import.files, import.format, *import.target, import.dry_run).await
+        },
+        LiplCommand::Export(export) => {
+            export.source.build_repo()
+            .and_then(|source| crate::db::export(source, export.format, export.dir, export.playlist))
+            .await
+        },
+        LiplCommand::Songbook(songbook) => {
+            songbook.source.build_repo()
+            .and_then(|source| crate::db::songbook(source, songbook.playlist, songbook.title, songbook.output))
+            .await
+        },
+        LiplCommand::Site(site) => {
+            site.source.build_repo()
+            .and_then(|source| crate::db::site(source, site.title, site.dir))
+            .await
+        },
+        LiplCommand::Normalize(normalize) => {
+            normalize.source.build_repo()
+            .and_then(|source| crate::db::normalize(source, normalize.normalizer.normalizer(), normalize.dry_run))
+            .await
+        },
+        LiplCommand::Lint(lint) => {
+            let linter = lint.linter();
+            lint.source.build_repo()
+            .and_then(|source| crate::db::lint(source, linter, lint.fix, lint.dry_run))
+            .await
+        },
+        LiplCommand::Fsck(fsck) => {
+            crate::db::fsck(*fsck.source, fsck.repair).await
+        },
+        #[cfg(feature = "file")]
+        LiplCommand::Status(status) => {
+            crate::db::status(status.dir, status.accept, status.migrate).await
+        },
+        #[cfg(feature = "openlp")]
+        LiplCommand::Openlp(openlp) => {
+            crate::db::openlp(openlp).await
+        }
+        #[cfg(feature = "document")]
+        LiplCommand::Document(document) => {
+            crate::db::document(document).await
         }
     }
 }
@@ -1,3 +1,5 @@
 pub const STARTING: &str = "Starting";
 pub const STOPPING: &str = "Stopping";
 pub const FINISHED: &str = "Finished";
+
+pub const READ_ONLY: &str = "Read-only mode";
@@ -4,3 +4,10 @@ use serde::{Serialize, Deserialize};
 pub struct Query {
     pub full: bool
 }
+
+/// Most lines on a slide and characters on a line
+#[derive(Deserialize, Serialize)]
+pub struct SlidesQuery {
+    pub lines: Option<usize>,
+    pub chars: Option<usize>,
+}
@@ -1,12 +1,86 @@
-use clap::{command, Subcommand, Parser};
+use std::path::PathBuf;
+use clap::{command, Args, Subcommand, Parser};
+use lipl_core::Uuid;
+use lipl_core::dump::DumpFormat;
+use lipl_core::lint::{Linter, RuleSeverity};
+use lipl_core::normalize::{Dashes, Normalizer, Quotes};
+use lipl_core::transaction::LogSink;
+use crate::format::Format;
 use crate::repo::{RepoConfig};
 
+#[derive(Args)]
+pub struct NormalizeArgs {
+    #[arg(long, help = "Leave out Unicode NFC")]
+    pub no_nfc: bool,
+    #[arg(long, help = "Leave zero-width characters, special spaces and runs of spaces alone")]
+    pub keep_whitespace: bool,
+    #[arg(long, help = "Quotes policy: keep, straight or curly [default: straight]")]
+    pub quotes: Option<Quotes>,
+    #[arg(long, help = "Dashes policy: keep, hyphen or en-dash [default: keep]")]
+    pub dashes: Option<Dashes>,
+    #[arg(long, help = "Capitalize the first letter of every line")]
+    pub capitalize: bool,
+}
+
+impl NormalizeArgs {
+    pub fn normalizer(&self) -> Normalizer {
+        Normalizer {
+            nfc: !self.no_nfc,
+            whitespace: !self.keep_whitespace,
+            quotes: self.quotes.unwrap_or_default(),
+            dashes: self.dashes.unwrap_or_default(),
+            capitalize: self.capitalize,
+        }
+    }
+
+    /// Whether any option was given on the command line
+    pub fn given(&self) -> bool {
+        self.no_nfc || self.keep_whitespace || self.quotes.is_some() || self.dashes.is_some() || self.capitalize
+    }
+}
+
 #[derive(Parser)]
 pub struct ServeCommand {
     #[arg(long, short)]
     pub port: u16,
     #[arg(long, short)]
     pub source: Box<RepoConfig>,
+    #[arg(long, help = "Reject writes with 503 Service Unavailable, SIGUSR1 and SIGUSR2 turn read-only on and off at runtime, which needs the signal feature of lipl-core and Unix")]
+    pub read_only: bool,
+    #[arg(long, help = "Normalize the text of every upserted lyric and playlist title, implied by the normalize options")]
+    pub normalize: bool,
+    #[command(flatten)]
+    pub normalizer: NormalizeArgs,
+    #[arg(long, help = "Log upserts and deletes to this file, - logs to stdout")]
+    pub transaction_log: Option<LogSink>,
+    #[arg(long, requires = "transaction_log", help = "Rotate the transaction log when it grows beyond this size")]
+    pub transaction_log_max_bytes: Option<u64>,
+    #[arg(long, default_value_t = 5, help = "Number of rotated transaction logs to keep")]
+    pub transaction_log_keep: usize,
+}
+
+impl ServeCommand {
+    /// Normalizer for upserts, with `--normalize` or any of the normalize options
+    pub fn normalizer(&self) -> Option<Normalizer> {
+        (self.normalize || self.normalizer.given()).then(|| self.normalizer.normalizer())
+    }
+
+    pub fn transaction_log(&self) -> Option<LogSink> {
+        self.transaction_log
+            .clone()
+            .map(|sink| match self.transaction_log_max_bytes {
+                Some(max_bytes) => sink.rotate(max_bytes, self.transaction_log_keep),
+                None => sink,
+            })
+    }
+}
+
+#[derive(Parser)]
+pub struct ReplayCommand {
+    #[arg(long, short, required = true, help = "Transaction logs to replay, oldest first")]
+    pub log: Vec<PathBuf>,
+    #[arg(long, short)]
+    pub target: Box<RepoConfig>,
 }
 
 #[derive(Parser)]
@@ -21,8 +95,179 @@ pub struct CopyCommand {
 pub struct ListCommand {
     #[arg(long, short)]
     pub source: RepoConfig,
-    #[arg(long, short)]
+    #[arg(long, short, conflicts_with = "format", help = "Same as --format yaml")]
     pub yaml: bool,
+    #[arg(long, short, help = "Dump format: yaml, json, ndjson, toml or ron, a summary if left out")]
+    pub format: Option<DumpFormat>,
+}
+
+impl ListCommand {
+    pub fn format(&self) -> Option<DumpFormat> {
+        if self.yaml { Some(DumpFormat::Yaml) } else { self.format }
+    }
+}
+
+#[derive(Parser)]
+pub struct DumpCommand {
+    #[arg(long, short)]
+    pub source: Box<RepoConfig>,
+    #[arg(long, short, help = "Dump format, by the extension of the output if left out")]
+    pub format: Option<DumpFormat>,
+    #[arg(long, short, help = "Dump file, ndjson is written one lyric at a time")]
+    pub output: PathBuf,
+}
+
+#[derive(Parser)]
+pub struct LoadCommand {
+    #[arg(long, short, help = "Dump format, by the extension of the file if left out")]
+    pub format: Option<DumpFormat>,
+    #[arg(help = "Dump file, ndjson is read one line at a time")]
+    pub file: PathBuf,
+    #[arg(long, short)]
+    pub target: Box<RepoConfig>,
+}
+
+#[derive(Parser)]
+pub struct ImportCommand {
+    #[arg(long, short, value_enum)]
+    pub format: Format,
+    #[arg(required = true, help = "Files to import as new lyrics, directories are searched for files of the format, an OpenSong directory with Songs and Sets also gives playlists")]
+    pub files: Vec<PathBuf>,
+    #[arg(long, short)]
+    pub target: Box<RepoConfig>,
+    #[arg(long, help = "Report what would be created without writing to the target")]
+    pub dry_run: bool,
+}
+
+#[derive(Parser)]
+pub struct ExportCommand {
+    #[arg(long, short, value_enum)]
+    pub format: Format,
+    #[arg(long, short)]
+    pub source: Box<RepoConfig>,
+    #[arg(long, short, help = "Directory for the exported lyrics, one file per lyric")]
+    pub dir: PathBuf,
+    #[arg(long, help = "Export the members of this playlist only, numbered in playlist order")]
+    pub playlist: Option<Uuid>,
+}
+
+#[derive(Parser)]
+pub struct PackCommand {
+    #[arg(long, short)]
+    pub source: Box<RepoConfig>,
+    #[arg(long, short, help = "Archive file, usually with extension lipl")]
+    pub output: PathBuf,
+    #[arg(long, help = "Files to add to the archive as attachments")]
+    pub attachment: Vec<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct UnpackCommand {
+    #[arg(help = "Archive file to check and import")]
+    pub file: PathBuf,
+    #[arg(long, short)]
+    pub target: Box<RepoConfig>,
+    #[arg(long, help = "Directory for the attachments, left out if not given")]
+    pub attachments: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct SongbookCommand {
+    #[arg(long, short)]
+    pub source: Box<RepoConfig>,
+    #[arg(long, help = "Songbook of the members of this playlist, all lyrics if left out")]
+    pub playlist: Option<Uuid>,
+    #[arg(long, default_value = "Songbook", help = "Title of a songbook of all lyrics")]
+    pub title: String,
+    #[arg(long, short, help = "Songbook file, PDF if the extension is pdf, HTML otherwise")]
+    pub output: PathBuf,
+}
+
+#[derive(Parser)]
+pub struct NormalizeCommand {
+    #[arg(long, short)]
+    pub source: Box<RepoConfig>,
+    #[command(flatten)]
+    pub normalizer: NormalizeArgs,
+    #[arg(long, help = "Report how many lyrics and playlists would change without writing")]
+    pub dry_run: bool,
+}
+
+#[derive(Parser)]
+pub struct LintCommand {
+    #[arg(long, short)]
+    pub source: Box<RepoConfig>,
+    #[arg(long, short = 'r', help = "Severity of a rule as rule=severity, severity is off, warning or error, rules are warnings by default")]
+    pub rule: Vec<RuleSeverity>,
+    #[arg(long, help = "Fix the lyrics and show the changes as a diff")]
+    pub fix: bool,
+    #[arg(long, requires = "fix", help = "Show the diff of the fixes without writing")]
+    pub dry_run: bool,
+}
+
+impl LintCommand {
+    pub fn linter(&self) -> Linter {
+        self.rule
+            .iter()
+            .fold(Linter::default(), |linter, RuleSeverity(rule, severity)| linter.severity(*rule, *severity))
+    }
+}
+
+#[derive(Parser)]
+pub struct FsckCommand {
+    #[arg(long, short)]
+    pub source: Box<RepoConfig>,
+    #[arg(long, help = "Remove dangling and duplicate members, delete orphans and move unreadable files aside")]
+    pub repair: bool,
+}
+
+#[derive(Parser)]
+pub struct SiteCommand {
+    #[arg(long, short)]
+    pub source: Box<RepoConfig>,
+    #[arg(long, short, help = "Directory for the static website, existing files of the site are overwritten")]
+    pub dir: PathBuf,
+    #[arg(long, default_value = "Lipl", help = "Title of the index page")]
+    pub title: String,
+}
+
+#[cfg(feature = "file")]
+#[derive(Parser)]
+pub struct StatusCommand {
+    #[arg(long, short, help = "Directory of a file repository")]
+    pub dir: PathBuf,
+    #[arg(long, help = "Accept the modified lyric files, so a protected repository writes them again")]
+    pub accept: bool,
+    #[arg(long, help = "Rewrite lyric files with a legacy hash with the canonical hash")]
+    pub migrate: bool,
+}
+
+#[cfg(feature = "openlp")]
+#[derive(Parser)]
+pub struct OpenlpCommand {
+    #[arg(long, short, help = "OpenLP songs.sqlite database")]
+    pub database: PathBuf,
+    #[arg(long, help = "OpenLP service files (.osz) to import as playlists")]
+    pub service: Vec<PathBuf>,
+    #[arg(long, short)]
+    pub target: Box<RepoConfig>,
+    #[arg(long, help = "Report what would be created without writing to the target")]
+    pub dry_run: bool,
+}
+
+#[cfg(feature = "document")]
+#[derive(Parser)]
+pub struct DocumentCommand {
+    #[arg(required = true, help = "Songbooks in DOCX or ODT format")]
+    pub files: Vec<PathBuf>,
+    #[arg(long, short)]
+    pub target: Box<RepoConfig>,
+    #[arg(long, default_value_t = 1, help = "Level of the headings that start a song")]
+    pub heading_level: usize,
+    #[arg(long, help = "Create a playlist for every document with its songs in order")]
+    pub playlist: bool,
+    #[arg(long, help = "Report what would be created without writing to the target")]
+    pub dry_run: bool,
 }
 
 #[derive(Parser)]
@@ -37,5 +282,23 @@ pub enum LiplCommand {
     Serve(ServeCommand),
     Copy(CopyCommand),
     List(ListCommand),
+    Dump(DumpCommand),
+    Load(LoadCommand),
+    Pack(PackCommand),
+    Unpack(UnpackCommand),
+    Replay(ReplayCommand),
+    Import(ImportCommand),
+    Export(ExportCommand),
+    Songbook(SongbookCommand),
+    Site(SiteCommand),
+    Normalize(NormalizeCommand),
+    Lint(LintCommand),
+    Fsck(FsckCommand),
+    #[cfg(feature = "file")]
+    Status(StatusCommand),
+    #[cfg(feature = "openlp")]
+    Openlp(OpenlpCommand),
+    #[cfg(feature = "document")]
+    Document(DocumentCommand),
 }
 
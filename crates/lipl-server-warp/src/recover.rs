@@ -4,6 +4,8 @@ use tracing::error;
 
 use serde::Serialize;
 use warp::{Rejection, hyper::StatusCode, Reply};
+#[cfg(feature = "file")]
+use lipl_core::error::FileRepoError;
 use crate::error::RepoError;
 
 #[derive(Serialize)]
@@ -28,10 +30,27 @@ pub fn json_response(code: StatusCode, message: &str) -> Result<impl Reply, Infa
 pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
     if let Some(e) = err.find::<RepoError>() {
         match e {
+            RepoError::Model(lipl_core::Error::ReadOnly) => {
+                json_response(StatusCode::SERVICE_UNAVAILABLE, &lipl_core::Error::ReadOnly.to_string())
+            },
+            RepoError::Model(e @ (lipl_core::Error::OpenLyrics(_) | lipl_core::Error::TimesNotKept(_))) => {
+                json_response(StatusCode::BAD_REQUEST, &e.to_string())
+            },
+            RepoError::Model(e @ lipl_core::Error::NotOwned(_)) => {
+                json_response(StatusCode::FORBIDDEN, &e.to_string())
+            },
+            #[cfg(feature = "file")]
+            RepoError::Model(e @ lipl_core::Error::File(FileRepoError::Modified(_))) => {
+                json_response(StatusCode::CONFLICT, &e.to_string())
+            },
             RepoError::Model(m) => {
                 json_response(StatusCode::NOT_FOUND, &m.to_string())
             },
             #[cfg(feature = "file")]
+            RepoError::File(f @ FileRepoError::Modified(_)) => {
+                json_response(StatusCode::CONFLICT, &f.to_string())
+            },
+            #[cfg(feature = "file")]
             RepoError::File(f) => {
                 json_response(StatusCode::INTERNAL_SERVER_ERROR, &f.to_string())
             },
@@ -66,4 +85,19 @@ pub async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible>
         error!("unhandled rejection: {:?}", err);
         json_response(StatusCode::INTERNAL_SERVER_ERROR, "UNHANDLED_REJECTION")
     }
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, feature = "file"))]
+mod tests {
+    use lipl_core::error::FileRepoError;
+    use warp::{hyper::StatusCode, Reply};
+    use crate::error::RepoError;
+    use super::handle_rejection;
+
+    #[tokio::test]
+    async fn modified_is_conflict() {
+        let rejection = warp::reject::custom(RepoError::Model(FileRepoError::Modified("roodkapje".to_owned()).into()));
+        let response = handle_rejection(rejection).await.unwrap().into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+}
@@ -1,56 +1,50 @@
+use async_trait::async_trait;
 use lipl_core::{LiplRepo, ToRepo};
+use lipl_core::federated::FederatedScheme;
+use lipl_core::registry::{RepoRegistry, RepoUrl};
 use std::{str::FromStr, sync::Arc};
 
-#[cfg(feature = "file")]
-const PREFIX_FILE: &str = "file:";
-
-#[cfg(feature = "memory")]
-const PREFIX_MEMORY: &str = "memory:";
+/// Schemes this binary was built with, `federated:` joins repositories of the other schemes
+pub fn registry() -> RepoRegistry {
+    members().scheme(FederatedScheme::new(members()))
+}
 
-#[cfg(feature = "postgres")]
-const PREFIX_POSTGRES: &str = "postgres:";
+/// Schemes of single repositories this binary was built with
+fn members() -> RepoRegistry {
+    let registry = RepoRegistry::new();
 
-#[cfg(feature = "redis")]
-const PREFIX_REDIS: &str = "redis:";
+    #[cfg(feature = "file")]
+    let registry = registry.scheme(lipl_repo_fs::FileScheme);
 
-#[derive(Clone)]
-pub enum RepoConfig {
     #[cfg(feature = "postgres")]
-    Postgres(Box<lipl_repo_postgres::PostgresRepoConfig>),
-
-    #[cfg(feature = "file")]
-    File(Box<lipl_repo_fs::FileRepoConfig>),
+    let registry = registry.scheme(lipl_repo_postgres::PostgresScheme);
 
     #[cfg(feature = "redis")]
-    Redis(Box<lipl_repo_redis::redis_repo::RedisRepoConfig<String>>),
+    let registry = registry.scheme(lipl_repo_redis::RedisScheme);
 
     #[cfg(feature = "memory")]
-    Memory(Box<lipl_repo_memory::MemoryRepoConfig>),
+    let registry = registry.scheme(lipl_repo_memory::MemoryScheme);
+
+    registry
 }
 
-impl RepoConfig {
-    pub async fn build_repo(self) -> lipl_core::Result<Arc<dyn LiplRepo>> {
-        match self {
-            #[cfg(feature = "file")]
-            RepoConfig::File(config) => {
-                config.to_repo().await
-            },
+#[derive(Clone)]
+pub struct RepoConfig(RepoUrl);
 
-            #[cfg(feature = "postgres")]
-            RepoConfig::Postgres(config) => {
-                config.to_repo().await
-            },
+impl RepoConfig {
+    pub fn url(&self) -> &RepoUrl {
+        &self.0
+    }
 
-            #[cfg(feature = "redis")]
-            RepoConfig::Redis(config) => {
-                config.to_repo().await
-            }
+    pub async fn build_repo(self) -> lipl_core::Result<Arc<dyn LiplRepo>> {
+        registry().open_url(self.0).await
+    }
+}
 
-            #[cfg(feature = "memory")]
-            RepoConfig::Memory(config) => {
-                config.to_repo().await
-            }
-        }
+#[async_trait]
+impl ToRepo for RepoConfig {
+    async fn to_repo(self) -> lipl_core::Result<Arc<dyn LiplRepo>> {
+        self.build_repo().await
     }
 }
 
@@ -66,46 +60,8 @@ impl FromStr for RepoConfig {
     type Err = lipl_core::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        #[cfg(feature = "file")]
-        if s.starts_with(PREFIX_FILE) {
-            return 
-                s.strip_prefix(PREFIX_FILE)
-                .unwrap()
-                .parse::<lipl_repo_fs::FileRepoConfig>()
-                .map(Box::new)
-                .map(RepoConfig::File);
-        }
-
-        #[cfg(feature = "postgres")]
-        if s.starts_with(PREFIX_POSTGRES) {
-            return 
-                s.strip_prefix(PREFIX_POSTGRES)
-                    .unwrap()
-                    .parse::<lipl_repo_postgres::PostgresRepoConfig>()
-                    .map(Box::new)
-                    .map(RepoConfig::Postgres);
-        }
-
-        #[cfg(feature = "redis")]
-        if s.starts_with(PREFIX_REDIS) {
-            return 
-                s.strip_prefix(PREFIX_REDIS)
-                    .unwrap()
-                    .parse::<lipl_repo_redis::redis_repo::RedisRepoConfig<String>>()
-                    .map(Box::new)
-                    .map(RepoConfig::Redis);
-        }
-
-        #[cfg(feature = "memory")]
-        if s.starts_with(PREFIX_MEMORY) {
-            return
-                s.strip_prefix(PREFIX_MEMORY)
-                    .unwrap()
-                    .parse::<lipl_repo_memory::MemoryRepoConfig>()
-                    .map(Box::new)
-                    .map(RepoConfig::Memory);
-        }
-
-        Err(lipl_core::Error::Argument("Invalid argument"))
+        let url = s.parse::<RepoUrl>()?;
+        registry().check(&url)?;
+        Ok(RepoConfig(url))
     }
 }
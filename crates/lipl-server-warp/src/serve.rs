@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use lipl_core::LiplRepo;
+use lipl_core::read_only::{switch_on_signal, ReadOnlySwitch};
 use tokio::signal;
 use tracing::{info, error};
 use warp::Filter;
@@ -8,9 +9,9 @@ use warp::Filter;
 use crate::constant;
 use crate::error::RepoError;
 use crate::message;
-use crate::filter::{get_lyric_routes, get_playlist_routes};
+use crate::filter::{get_lyric_routes, get_openlyrics_routes, get_playlist_routes, get_slides_route, get_timed_route};
 
-pub async fn run(repo: Arc<dyn LiplRepo>, port: u16) -> lipl_core::Result<()> 
+pub async fn run(repo: Arc<dyn LiplRepo>, port: u16, read_only: ReadOnlySwitch) -> lipl_core::Result<()> 
 {
     let filter =
         std::env::var(constant::RUST_LOG)
@@ -20,12 +21,26 @@ pub async fn run(repo: Arc<dyn LiplRepo>, port: u16) -> lipl_core::Result<()>
     .with_env_filter(filter)
     .init();
 
+    if read_only.is_read_only() {
+        info!("{}", message::READ_ONLY);
+    }
+    tokio::spawn(switch_on_signal(read_only));
+
     // Cache warmup
     let _lyrics = repo.get_lyrics().await;
     let _playlists = repo.get_playlists().await;
 
     let routes = 
-        get_lyric_routes(repo.clone(), constant::LYRIC)
+        get_timed_route(repo.clone(), constant::LYRIC)
+        .or(
+            get_slides_route(repo.clone(), constant::PLAYLIST)
+        )
+        .or(
+            get_openlyrics_routes(repo.clone(), constant::LYRIC)
+        )
+        .or(
+            get_lyric_routes(repo.clone(), constant::LYRIC)
+        )
         .or(
             get_playlist_routes(repo.clone(), constant::PLAYLIST)
         )
@@ -0,0 +1,100 @@
+/*!
+ Standalone HTML with print CSS.
+
+ Browsers print every section on a new page. Paged media renderers like WeasyPrint also
+ fill in the page numbers in the table of contents and the index.
+ */
+
+use std::fmt::Write;
+use parts::markup::{self, Span};
+use crate::Songbook;
+
+const STYLE: &str = "
+@page { size: A5; margin: 15mm 15mm 20mm; @bottom-center { content: counter(page); } }
+@page :first { @bottom-center { content: none; } }
+body { font-family: Georgia, serif; font-size: 11pt; line-height: 1.4; }
+section { break-before: page; page-break-before: always; }
+section.title { break-before: auto; page-break-before: auto; display: flex; flex-direction: column; justify-content: center; min-height: 90vh; text-align: center; }
+section.title h1 { font-size: 28pt; }
+ol, ul { list-style: none; padding: 0; }
+.toc a, .index a { color: inherit; text-decoration: none; }
+.toc a::after, .index a::after { content: leader('.') target-counter(attr(href), page); }
+.number { display: inline-block; min-width: 2.5em; }
+.song p { margin: 0 0 1em; break-inside: avoid; page-break-inside: avoid; }
+@media screen { body { max-width: 40em; margin: auto; } }
+";
+
+pub(crate) fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn span(span: &Span) -> String {
+    match span {
+        Span::Text(text) => escape(text),
+        Span::Emphasis(text) => format!("<em>{}</em>", escape(text)),
+        Span::Strong(text) => format!("<strong>{}</strong>", escape(text)),
+    }
+}
+
+/// Line with its indentation and markup
+pub(crate) fn line(s: &str) -> String {
+    let line = markup::parse(s);
+    let mut html = "&nbsp;".repeat(line.indent_width());
+    if let Some(speaker) = line.speaker.as_ref() {
+        let _ = write!(html, "<span class=\"speaker\">{}</span>", span(speaker));
+    }
+    html.extend(line.spans.iter().map(span));
+    html
+}
+
+pub fn to_html(songbook: &Songbook) -> String {
+    let title = escape(&songbook.title);
+    let mut html = String::new();
+    let _ = write!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n");
+    let _ = writeln!(html, "<section class=\"title\"><h1>{title}</h1><p>{} songs</p></section>", songbook.songs.len());
+
+    html.push_str("<section class=\"toc\">\n<h2>Contents</h2>\n<ol>\n");
+    for (i, song) in songbook.songs.iter().enumerate() {
+        let _ = writeln!(html, "<li><a href=\"#song-{n}\"><span class=\"number\">{n}</span>{}</a></li>", escape(&song.title), n = i + 1);
+    }
+    html.push_str("</ol>\n</section>\n");
+
+    for (i, song) in songbook.songs.iter().enumerate() {
+        let _ = writeln!(html, "<section class=\"song\" id=\"song-{n}\">\n<h2><span class=\"number\">{n}</span>{}</h2>", escape(&song.title), n = i + 1);
+        for part in song.parts.iter() {
+            let lines = part.iter().map(|s| line(s)).collect::<Vec<_>>();
+            let _ = writeln!(html, "<p>{}</p>", lines.join("<br>\n"));
+        }
+        html.push_str("</section>\n");
+    }
+
+    html.push_str("<section class=\"index\">\n<h2>Index</h2>\n<ul>\n");
+    for (title, number) in songbook.index() {
+        let _ = writeln!(html, "<li><a href=\"#song-{number}\"><span class=\"number\">{number}</span>{}</a></li>", escape(title));
+    }
+    html.push_str("</ul>\n</section>\n</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::lyric;
+    use crate::Songbook;
+    use super::to_html;
+
+    #[test]
+    fn sections_in_order() {
+        let songbook = Songbook::new("Kampvuur & zo", vec![lyric("Zilveren maan", 2), lyric("<Amazing grace>", 1)]);
+        let html = to_html(&songbook);
+        assert!(html.contains("<h1>Kampvuur &amp; zo</h1>"));
+        assert_eq!(html.matches("<section class=\"song\"").count(), 2);
+        assert!(html.contains("<p>Regel 0 van Zilveren maan<br>\nTweede regel</p>"));
+
+        let toc = html.find("class=\"toc\"").unwrap();
+        let song = html.find("id=\"song-1\"").unwrap();
+        let index = html.find("class=\"index\"").unwrap();
+        assert!(toc < song && song < index);
+        assert_eq!(super::line("  *All:* <Amen>, **Amen**"), "&nbsp;&nbsp;<span class=\"speaker\"><em>All:</em></span> &lt;Amen&gt;, <strong>Amen</strong>");
+        assert!(html[index..].find("&lt;Amazing grace&gt;").unwrap() < html[index..].find("Zilveren maan").unwrap());
+    }
+}
@@ -0,0 +1,99 @@
+/*!
+ Printable songbooks, slideshows and static websites.
+
+ A [`Songbook`] numbers its songs in order and gets a title page, a table of contents
+ and an alphabetical index. It renders to a standalone HTML page with print CSS
+ and to an A5 PDF, in both every song starts on a new page.
+ It also renders to an HTML slideshow for a projector.
+ A [`Site`] publishes a whole library as static HTML.
+ */
+
+use lipl_core::{by_title, LiplRepo, Lyric, RepoDb, Uuid};
+
+pub use html::to_html;
+pub use parts::slides::Fit;
+pub use pdf::to_pdf;
+pub use site::Site;
+pub use slides::to_slides;
+
+mod html;
+mod pdf;
+mod site;
+mod slides;
+
+pub const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+pub const PDF_CONTENT_TYPE: &str = "application/pdf";
+
+pub struct Songbook {
+    pub title: String,
+    pub songs: Vec<Lyric>,
+}
+
+impl Songbook {
+    pub fn new(title: &str, songs: Vec<Lyric>) -> Self {
+        Self { title: title.to_owned(), songs }
+    }
+
+    /// Songbook with the members of a playlist, in playlist order
+    pub async fn from_playlist<R: LiplRepo + ?Sized>(repo: &R, id: Uuid) -> lipl_core::Result<Self> {
+        let playlist = repo.get_playlist(id).await?;
+        let mut songs = vec![];
+        for member in playlist.members {
+            songs.push(repo.get_lyric(member).await?);
+        }
+        Ok(Self::new(&playlist.title, songs))
+    }
+
+    /// Songbook with all lyrics, ordered by title
+    pub fn from_repo_db(title: &str, db: &RepoDb) -> Self {
+        let mut songs = db.lyrics.clone();
+        songs.sort_by(by_title);
+        Self::new(title, songs)
+    }
+
+    /// Title and number of every song, ordered by title
+    pub fn index(&self) -> Vec<(&str, usize)> {
+        let mut index =
+            self.songs
+            .iter()
+            .enumerate()
+            .map(|(i, song)| (song.title.as_str(), i + 1))
+            .collect::<Vec<_>>();
+        index.sort_by_key(|(title, number)| (title.to_lowercase(), *number));
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use lipl_core::{LiplRepo, Lyric, Playlist, RepoDb, Uuid};
+    use lipl_repo_memory::MemoryRepo;
+    use super::Songbook;
+
+    pub(crate) fn lyric(title: &str, parts: usize) -> Lyric {
+        Lyric {
+            id: Uuid::default(),
+            title: title.to_owned(),
+            parts: (0..parts).map(|i| vec![format!("Regel {i} van {title}"), "Tweede regel".to_owned()]).collect(),
+            extensions: Default::default(),
+            times: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn songs_in_playlist_order() {
+        let (a, b) = (lyric("Zilveren maan", 2), lyric("Amazing grace", 1));
+        let repo = Arc::new(MemoryRepo::from(RepoDb { lyrics: vec![a.clone(), b.clone()], playlists: vec![] }));
+        let playlist = Playlist { id: Uuid::default(), title: "Zondag".to_owned(), members: vec![a.id, b.id] };
+        repo.upsert_playlist(playlist.clone()).await.unwrap();
+
+        let songbook = Songbook::from_playlist(repo.as_ref(), playlist.id).await.unwrap();
+        assert_eq!(songbook.title, "Zondag");
+        assert_eq!(songbook.songs.iter().map(|song| song.title.as_str()).collect::<Vec<_>>(), vec!["Zilveren maan", "Amazing grace"]);
+        assert_eq!(songbook.index(), vec![("Amazing grace", 2), ("Zilveren maan", 1)]);
+
+        let songbook = Songbook::from_repo_db("Alles", &RepoDb { lyrics: vec![a, b], playlists: vec![] });
+        assert_eq!(songbook.songs[0].title, "Amazing grace");
+    }
+}
@@ -0,0 +1,238 @@
+/*!
+ A5 PDF with the builtin Helvetica fonts.
+
+ The builtin fonts cover Windows-1252, other characters are left out.
+ Songs are laid out before the table of contents, so it can show the page of every song.
+ Long lines wrap on an estimated average character width.
+ Markup is left out, indentation is kept.
+ */
+
+use lipl_core::Error;
+use parts::markup;
+use printpdf::{BuiltinFont, Mm, PdfDocument};
+use crate::Songbook;
+
+const WIDTH: f32 = 148.0;
+const HEIGHT: f32 = 210.0;
+const MARGIN: f32 = 15.0;
+const TOP: f32 = HEIGHT - MARGIN;
+const BOTTOM: f32 = 20.0;
+const FOOTER: f32 = 10.0;
+const TITLE: f32 = 24.0;
+const HEADING: f32 = 15.0;
+const BODY: f32 = 11.0;
+const INDENT: f32 = 10.0;
+
+/// Millimeters in a point
+const MM: f32 = 0.3528;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Text {
+    x: f32,
+    y: f32,
+    size: f32,
+    bold: bool,
+    text: String,
+}
+
+#[derive(Default)]
+struct Layout {
+    pages: Vec<Vec<Text>>,
+    y: f32,
+}
+
+impl Layout {
+    fn new_page(&mut self) {
+        self.pages.push(vec![]);
+        self.y = TOP;
+    }
+
+    fn space(&mut self, mm: f32) {
+        self.y -= mm;
+    }
+
+    /// Writes a line at the current position, on a new page if it does not fit
+    fn text(&mut self, x: f32, size: f32, bold: bool, text: &str) {
+        let height = size * MM * 1.4;
+        if self.pages.is_empty() || self.y - height < BOTTOM {
+            self.new_page();
+        }
+        self.y -= height;
+        let y = self.y;
+        self.pages.last_mut().unwrap().push(Text { x, y, size, bold, text: text.to_owned() });
+    }
+
+    /// Writes a number and a title with the page number aligned right
+    fn entry(&mut self, number: usize, title: &str, page: usize) {
+        let title = truncate(title, max_chars(WIDTH - 2.0 * MARGIN - 2.0 * INDENT, BODY));
+        self.text(MARGIN, BODY, false, &number.to_string());
+        let y = self.y;
+        let page = page.to_string();
+        let last = self.pages.last_mut().unwrap();
+        last.push(Text { x: MARGIN + INDENT, y, size: BODY, bold: false, text: title });
+        last.push(Text { x: WIDTH - MARGIN - text_width(&page, BODY), y, size: BODY, bold: false, text: page });
+    }
+
+    fn page(&self) -> usize {
+        self.pages.len()
+    }
+}
+
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * MM * 0.5
+}
+
+fn max_chars(width: f32, size: f32) -> usize {
+    (width / (size * MM * 0.5)) as usize
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() > max {
+        text.chars().take(max.saturating_sub(3)).chain("...".chars()).collect()
+    }
+    else {
+        text.to_owned()
+    }
+}
+
+/// Words of a line spread over lines of at most `max` characters
+fn wrap(line: &str, max: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > max {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+}
+
+/// Pages of the songs and the first page of every song, counting from one
+fn songs(songbook: &Songbook) -> (Vec<Vec<Text>>, Vec<usize>) {
+    let mut layout = Layout::default();
+    let mut starts = vec![];
+    let max = max_chars(WIDTH - 2.0 * MARGIN - INDENT, BODY);
+    for (i, song) in songbook.songs.iter().enumerate() {
+        layout.new_page();
+        starts.push(layout.page());
+        layout.text(MARGIN, HEADING, true, &format!("{}. {}", i + 1, song.title));
+        layout.space(HEADING * MM);
+        for part in song.parts.iter() {
+            for line in part.iter().map(|line| markup::parse(line)) {
+                let x = MARGIN + line.indent_width() as f32 * BODY * MM * 0.5;
+                let mut wrapped = wrap(&line.plain(), max).into_iter();
+                if let Some(first) = wrapped.next() {
+                    layout.text(x, BODY, false, &first);
+                }
+                for rest in wrapped {
+                    layout.text(x + INDENT, BODY, false, &rest);
+                }
+            }
+            layout.space(BODY * MM);
+        }
+    }
+    (layout.pages, starts)
+}
+
+/// Table of contents, first page of every song after `offset` pages
+fn contents(songbook: &Songbook, starts: &[usize], offset: usize) -> Vec<Vec<Text>> {
+    let mut layout = Layout::default();
+    layout.text(MARGIN, HEADING, true, "Contents");
+    layout.space(HEADING * MM);
+    for (i, song) in songbook.songs.iter().enumerate() {
+        layout.entry(i + 1, &song.title, starts[i] + offset);
+    }
+    layout.pages
+}
+
+fn index(songbook: &Songbook, starts: &[usize], offset: usize) -> Vec<Vec<Text>> {
+    let mut layout = Layout::default();
+    layout.text(MARGIN, HEADING, true, "Index");
+    layout.space(HEADING * MM);
+    for (title, number) in songbook.index() {
+        layout.entry(number, title, starts[number - 1] + offset);
+    }
+    layout.pages
+}
+
+/// Pages of the songbook and the page number of every song
+fn layout(songbook: &Songbook) -> (Vec<Vec<Text>>, Vec<usize>) {
+    let title = vec![
+        Text { x: MARGIN, y: HEIGHT * 0.6, size: TITLE, bold: true, text: truncate(&songbook.title, max_chars(WIDTH - 2.0 * MARGIN, TITLE)) },
+        Text { x: MARGIN, y: HEIGHT * 0.6 - 2.0 * TITLE * MM, size: BODY, bold: false, text: format!("{} songs", songbook.songs.len()) },
+    ];
+    let (songs, starts) = songs(songbook);
+    let offset = 1 + contents(songbook, &starts, 0).len();
+
+    let mut pages = vec![title];
+    pages.extend(contents(songbook, &starts, offset));
+    pages.extend(songs);
+    pages.extend(index(songbook, &starts, offset));
+    (pages, starts.into_iter().map(|start| start + offset).collect())
+}
+
+pub fn to_pdf(songbook: &Songbook) -> lipl_core::Result<Vec<u8>> {
+    let pdf_error = |error: printpdf::Error| Error::Pdf(Box::new(error));
+    let (pages, starts) = layout(songbook);
+    let (doc, first_page, first_layer) = PdfDocument::new(&songbook.title, Mm(WIDTH), Mm(HEIGHT), "Songbook");
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica).map_err(pdf_error)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(pdf_error)?;
+
+    let mut page_indexes = vec![];
+    for (i, page) in pages.iter().enumerate() {
+        let (page_index, layer_index) =
+            if i == 0 { (first_page, first_layer) }
+            else { doc.add_page(Mm(WIDTH), Mm(HEIGHT), "Songbook") };
+        page_indexes.push(page_index);
+        let layer = doc.get_page(page_index).get_layer(layer_index);
+        for text in page {
+            layer.use_text(&text.text, text.size, Mm(text.x), Mm(text.y), if text.bold { &bold } else { &regular });
+        }
+        if i > 0 {
+            let number = (i + 1).to_string();
+            layer.use_text(&number, BODY, Mm((WIDTH - text_width(&number, BODY)) / 2.0), Mm(FOOTER), &regular);
+        }
+    }
+    for (song, start) in songbook.songs.iter().zip(starts) {
+        doc.add_bookmark(song.title.clone(), page_indexes[start - 1]);
+    }
+    doc.save_to_bytes().map_err(pdf_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::lyric;
+    use crate::Songbook;
+    use super::{layout, to_pdf, wrap};
+
+    #[test]
+    fn pages_of_songs_in_contents_and_index() {
+        let songbook = Songbook::new("Kampvuur", vec![lyric("Zilveren maan", 20), lyric("Amazing grace", 1)]);
+        let (pages, starts) = layout(&songbook);
+        // title, contents, two pages for the first song, one for the second and the index
+        assert_eq!(pages.len(), 6);
+        assert_eq!(starts, vec![3, 5]);
+        assert_eq!(pages[1].iter().filter(|text| text.text == "3" || text.text == "5").count(), 2);
+        assert_eq!(pages[4][0].text, "2. Amazing grace");
+
+        let index = pages.last().unwrap().iter().map(|text| text.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(index, vec!["Index", "2", "Amazing grace", "5", "1", "Zilveren maan", "3"]);
+    }
+
+    #[test]
+    fn wrap_long_lines() {
+        assert_eq!(wrap("een twee drie vier", 9), vec!["een twee", "drie vier"]);
+        assert_eq!(wrap("", 9), vec![""]);
+    }
+
+    #[test]
+    fn pdf_document() {
+        let pdf = to_pdf(&Songbook::new("Kampvuur", vec![lyric("Één", 1)])).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+    }
+}
@@ -0,0 +1,203 @@
+/*!
+ Static website of a whole library.
+
+ The site has an index of lyrics and playlists by title, a page per lyric made from its Markdown
+ (the `Display` of [`Lyric`] without the frontmatter), a page per playlist with links to its members
+ and `search.json`, an index of titles and text for searching without a server.
+
+ Generating is deterministic: the same library gives the same files byte for byte,
+ so a site can be diffed and hosted anywhere.
+ */
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use lipl_core::{LiplRepo, Lyric, Playlist, RepoDb, Uuid};
+use parts::{markup, Markdown};
+use serde::Serialize;
+use crate::html::{escape, line};
+
+const STYLE: &str = "body { font-family: Georgia, serif; max-width: 40em; margin: 2em auto; padding: 0 1em; line-height: 1.4; }
+nav { margin-bottom: 2em; }
+ul { padding-left: 1.2em; }
+input { font-size: 1em; width: 100%; padding: 0.3em; box-sizing: border-box; }
+.results:empty { display: none; }
+";
+
+const SEARCH: &str = "
+const input = document.getElementById('search');
+const results = document.getElementById('results');
+let entries = [];
+fetch('search.json').then(response => response.json()).then(json => entries = json);
+input.addEventListener('input', () => {
+  const words = input.value.toLowerCase().split(/\\s+/).filter(word => word);
+  results.replaceChildren(...entries
+    .filter(entry => words.length && words.every(word => (entry.title + ' ' + entry.text).toLowerCase().includes(word)))
+    .map(entry => {
+      const item = document.createElement('li');
+      const link = document.createElement('a');
+      link.href = entry.url;
+      link.textContent = entry.title;
+      item.append(link);
+      return item;
+    }));
+});
+";
+
+/// Entry of `search.json`
+#[derive(Serialize)]
+struct SearchEntry {
+    kind: &'static str,
+    id: String,
+    title: String,
+    url: String,
+    text: String,
+}
+
+/// Files of the site by path relative to its root
+pub struct Site {
+    pub files: BTreeMap<String, String>,
+}
+
+fn lyric_url(id: &Uuid) -> String {
+    format!("lyric/{id}.html")
+}
+
+fn playlist_url(id: &Uuid) -> String {
+    format!("playlist/{id}.html")
+}
+
+fn page(title: &str, root: &str, body: &str) -> String {
+    let title = escape(title);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n<body>\n<nav><a href=\"{root}index.html\">Index</a></nav>\n<h1>{title}</h1>\n{body}</body>\n</html>\n"
+    )
+}
+
+/// Markdown parts as paragraphs, lines with their indentation and markup ending in a hard break
+fn markdown_to_html(markdown: String) -> String {
+    Markdown::from(markdown)
+        .parts
+        .iter()
+        .map(|part| format!("<p>{}</p>\n", part.iter().map(|s| line(s)).collect::<Vec<_>>().join("<br>\n")))
+        .collect()
+}
+
+fn by_title_and_id<T>(items: &[T], key: impl Fn(&T) -> (&str, &Uuid)) -> Vec<&T> {
+    let mut sorted = items.iter().collect::<Vec<_>>();
+    sorted.sort_by_cached_key(|item| {
+        let (title, id) = key(item);
+        (title.to_lowercase(), id.to_string())
+    });
+    sorted
+}
+
+fn lyric_page(lyric: &Lyric) -> String {
+    page(&lyric.title, "../", &markdown_to_html(lyric.to_string()))
+}
+
+fn playlist_page(playlist: &Playlist, lyrics: &[Lyric]) -> String {
+    let mut body = String::from("<ol>\n");
+    for member in playlist.members.iter() {
+        if let Some(lyric) = lyrics.iter().find(|lyric| lyric.id == *member) {
+            let _ = writeln!(body, "<li><a href=\"../{}\">{}</a></li>", lyric_url(&lyric.id), escape(&lyric.title));
+        }
+    }
+    body.push_str("</ol>\n");
+    page(&playlist.title, "../", &body)
+}
+
+impl Site {
+    pub fn new(title: &str, db: &RepoDb) -> Self {
+        let lyrics = by_title_and_id(&db.lyrics, |lyric| (&lyric.title, &lyric.id));
+        let playlists = by_title_and_id(&db.playlists, |playlist| (&playlist.title, &playlist.id));
+        let mut files = BTreeMap::new();
+        let mut search = vec![];
+
+        let mut index = String::from("<input id=\"search\" type=\"search\" placeholder=\"Search\">\n<ul id=\"results\" class=\"results\"></ul>\n<h2>Lyrics</h2>\n<ul>\n");
+        for lyric in lyrics.iter() {
+            let url = lyric_url(&lyric.id);
+            let _ = writeln!(index, "<li><a href=\"{url}\">{}</a></li>", escape(&lyric.title));
+            files.insert(url.clone(), lyric_page(lyric));
+            search.push(SearchEntry {
+                kind: "lyric",
+                id: lyric.id.to_string(),
+                title: lyric.title.clone(),
+                url,
+                text: lyric.parts.iter().flatten().map(|line| markup::strip(line).trim().to_owned()).collect::<Vec<_>>().join(" "),
+            });
+        }
+        index.push_str("</ul>\n<h2>Playlists</h2>\n<ul>\n");
+        for playlist in playlists.iter() {
+            let url = playlist_url(&playlist.id);
+            let _ = writeln!(index, "<li><a href=\"{url}\">{}</a></li>", escape(&playlist.title));
+            files.insert(url.clone(), playlist_page(playlist, &db.lyrics));
+            search.push(SearchEntry { kind: "playlist", id: playlist.id.to_string(), title: playlist.title.clone(), url, text: String::new() });
+        }
+        let _ = write!(index, "</ul>\n<script>{SEARCH}</script>\n");
+
+        files.insert("index.html".to_owned(), page(title, "", &index));
+        files.insert("style.css".to_owned(), STYLE.to_owned());
+        files.insert("search.json".to_owned(), serde_json::to_string_pretty(&search).unwrap_or_default());
+        Self { files }
+    }
+
+    pub async fn from_repo<R: LiplRepo + ?Sized>(title: &str, repo: &R) -> lipl_core::Result<Self> {
+        Ok(Self::new(title, &RepoDb::from_repo(repo).await?))
+    }
+
+    /// Writes the files of the site in `dir`, other files in `dir` are left alone
+    pub fn write<P: AsRef<Path>>(&self, dir: P) -> lipl_core::Result<()> {
+        for (path, contents) in self.files.iter() {
+            let path = dir.as_ref().join(path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, contents)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lipl_core::{Playlist, RepoDb, Uuid};
+    use crate::tests::lyric;
+    use super::Site;
+
+    fn db() -> RepoDb {
+        let (a, b) = (lyric("Zilveren maan", 2), lyric("Amazing <grace>", 1));
+        let playlist = Playlist { id: Uuid::default(), title: "Zondag".to_owned(), members: vec![a.id, b.id] };
+        RepoDb { lyrics: vec![a, b], playlists: vec![playlist] }
+    }
+
+    #[test]
+    fn pages_and_search_index() {
+        let db = db();
+        let site = Site::new("Liedjes", &db);
+        assert_eq!(site.files.len(), 6);
+
+        let index = &site.files["index.html"];
+        assert!(index.find("Amazing &lt;grace&gt;").unwrap() < index.find("Zilveren maan").unwrap());
+        assert!(index.contains(&format!("<a href=\"playlist/{}.html\">Zondag</a>", db.playlists[0].id)));
+
+        let lyric = &site.files[&format!("lyric/{}.html", db.lyrics[0].id)];
+        assert!(lyric.contains("<p>Regel 0 van Zilveren maan<br>\nTweede regel</p>"));
+        assert!(!lyric.contains("hash"));
+
+        let playlist = &site.files[&format!("playlist/{}.html", db.playlists[0].id)];
+        assert!(playlist.find("Zilveren maan").unwrap() < playlist.find("Amazing").unwrap());
+
+        let search: serde_json::Value = serde_json::from_str(&site.files["search.json"]).unwrap();
+        assert_eq!(search[0]["title"], "Amazing <grace>");
+        assert_eq!(search[2]["kind"], "playlist");
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut db = db();
+        let first = Site::new("Liedjes", &db).files;
+        db.lyrics.reverse();
+        assert_eq!(Site::new("Liedjes", &db).files, first);
+    }
+}
@@ -0,0 +1,88 @@
+/*!
+ Self-contained HTML slideshow for a projector.
+
+ Every song starts with a title slide, its parts are fitted on slides with [`parts::slides`].
+ Chords, time tags, markup and indentation are left out. Arrow keys, page keys and space move between slides,
+ Home and End go to the first and the last slide and F toggles full screen.
+ */
+
+use std::fmt::Write;
+use parts::chordpro::strip_chords;
+use parts::markup::strip;
+use parts::slides::{slides, Fit};
+use crate::html::escape;
+use crate::Songbook;
+
+const STYLE: &str = "
+html, body { margin: 0; height: 100%; background: black; color: white; font-family: Helvetica, Arial, sans-serif; }
+section { display: none; height: 100vh; flex-direction: column; justify-content: center; align-items: center; text-align: center; font-size: 5vh; line-height: 1.3; }
+section.current { display: flex; }
+section.title h1 { font-size: 9vh; margin: 0; }
+section.title p, .number { color: #aaa; font-size: 4vh; }
+";
+
+const SCRIPT: &str = "
+const slides = document.querySelectorAll('section');
+let current = 0;
+function show(index) {
+  slides[current].classList.remove('current');
+  current = Math.max(0, Math.min(slides.length - 1, index));
+  slides[current].classList.add('current');
+}
+document.addEventListener('keydown', event => {
+  switch (event.key) {
+    case 'ArrowRight': case 'ArrowDown': case 'PageDown': case ' ': show(current + 1); break;
+    case 'ArrowLeft': case 'ArrowUp': case 'PageUp': show(current - 1); break;
+    case 'Home': show(0); break;
+    case 'End': show(slides.length - 1); break;
+    case 'f': case 'F':
+      if (document.fullscreenElement) document.exitFullscreen(); else document.documentElement.requestFullscreen();
+      break;
+    default: return;
+  }
+  event.preventDefault();
+});
+document.addEventListener('click', () => show(current + 1));
+if (slides.length) show(0);
+";
+
+pub fn to_slides(songbook: &Songbook, fit: &Fit) -> String {
+    let mut html = String::new();
+    let _ = write!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n", escape(&songbook.title));
+    for (i, song) in songbook.songs.iter().enumerate() {
+        let _ = writeln!(html, "<section class=\"title\"><h1>{}</h1><p>{}</p></section>", escape(&song.title), i + 1);
+        for part in song.parts.iter() {
+            let part = part.iter().map(|line| strip_chords(strip(line).trim())).filter(|line| !line.is_empty()).collect::<Vec<_>>();
+            for slide in slides(&part, fit) {
+                let lines = slide.iter().map(|line| escape(line)).collect::<Vec<_>>();
+                let _ = writeln!(html, "<section><div>{}</div></section>", lines.join("<br>\n"));
+            }
+        }
+    }
+    let _ = write!(html, "<script>{SCRIPT}</script>\n</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use lipl_core::LineTime;
+    use parts::slides::Fit;
+    use crate::tests::lyric;
+    use crate::Songbook;
+    use super::to_slides;
+
+    #[test]
+    fn title_slide_per_song() {
+        let mut song = lyric("Zilveren maan", 1);
+        song.parts[0] = (1..=5).map(|i| format!("[C]Regel {i}")).collect();
+        song.times = Some(vec![(1..=5).map(|i| LineTime(Some(Duration::from_secs(i)))).collect()]);
+        let songbook = Songbook::new("Zondag", vec![song, lyric("Amazing grace", 2)]);
+        let html = to_slides(&songbook, &Fit { lines: 4, chars: 40 });
+
+        assert_eq!(html.matches("<section class=\"title\">").count(), 2);
+        assert_eq!(html.matches("<section><div>").count(), 4);
+        assert!(html.contains("<section><div>Regel 1<br>\nRegel 2<br>\nRegel 3</div></section>"));
+        assert!(html.find("Zilveren maan").unwrap() < html.find("Amazing grace").unwrap());
+    }
+}
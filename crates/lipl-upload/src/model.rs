@@ -9,6 +9,8 @@ impl From<fs::Entry> for LyricPost {
         Self {
             title: entry.title(),
             parts: to_parts(entry.contents),
+            extensions: Default::default(),
+            times: None,
         }
     }
 }
@@ -0,0 +1,238 @@
+/*!
+ ChordPro songs, as kept by musicians.
+
+ Directives like `{title: ...}` and `{start_of_chorus}` give the title and the sections of a song,
+ chords stay in the lines as `[C]`, because a line is the only place a chord fits in parts.
+ Sections without an environment are separated by empty lines.
+ Tab and grid sections are not lyrics and are left out of the parts,
+ `{chorus}` repeats the last chorus.
+
+ Rendering marks parts that occur more than once as chorus,
+ the first one as `{start_of_chorus}` environment and the repeats as `{chorus}`.
+ */
+
+pub const EXTENSIONS: [&str; 4] = ["cho", "chordpro", "chopro", "crd"];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SectionKind {
+    Verse,
+    Chorus,
+    Bridge,
+    Tab,
+    Grid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Section {
+    pub kind: SectionKind,
+    pub label: Option<String>,
+    pub lines: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChordPro {
+    pub title: Option<String>,
+    pub subtitles: Vec<String>,
+    pub artists: Vec<String>,
+    pub sections: Vec<Section>,
+}
+
+fn directive(line: &str) -> Option<(String, Option<String>)> {
+    let inner = line.strip_prefix('{')?.strip_suffix('}')?;
+    let (name, value) = match inner.split_once([':', ' ']) {
+        Some((name, value)) => (name, Some(value.trim().to_owned()).filter(|value| !value.is_empty())),
+        None => (inner, None),
+    };
+    Some((name.trim().to_lowercase(), value))
+}
+
+fn start(name: &str) -> Option<SectionKind> {
+    match name {
+        "start_of_verse" | "sov" => Some(SectionKind::Verse),
+        "start_of_chorus" | "soc" => Some(SectionKind::Chorus),
+        "start_of_bridge" | "sob" => Some(SectionKind::Bridge),
+        "start_of_tab" | "sot" => Some(SectionKind::Tab),
+        "start_of_grid" | "sog" => Some(SectionKind::Grid),
+        _ => None,
+    }
+}
+
+fn end(name: &str) -> bool {
+    matches!(name, "end_of_verse" | "eov" | "end_of_chorus" | "eoc" | "end_of_bridge" | "eob" | "end_of_tab" | "eot" | "end_of_grid" | "eog")
+}
+
+/// Line without its chords
+pub fn strip_chords(line: &str) -> String {
+    let mut acc = String::with_capacity(line.len());
+    let mut in_chord = false;
+    for c in line.chars() {
+        match c {
+            '[' => in_chord = true,
+            ']' if in_chord => in_chord = false,
+            _ if !in_chord => acc.push(c),
+            _ => {},
+        }
+    }
+    acc.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl ChordPro {
+    fn close(&mut self, current: &mut Option<Section>) {
+        if let Some(section) = current.take() {
+            if !section.lines.is_empty() {
+                self.sections.push(section);
+            }
+        }
+    }
+
+    /// Lyric parts in order of appearance, without tab and grid sections
+    pub fn parts(&self, chords: bool) -> Vec<Vec<String>> {
+        self.sections
+            .iter()
+            .filter(|section| !matches!(section.kind, SectionKind::Tab | SectionKind::Grid))
+            .map(|section|
+                section.lines
+                .iter()
+                .map(|line| if chords { line.clone() } else { strip_chords(line) })
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+            )
+            .filter(|part| !part.is_empty())
+            .collect()
+    }
+}
+
+pub fn parse(s: &str) -> ChordPro {
+    let mut song = ChordPro::default();
+    let mut current: Option<Section> = None;
+    let mut environment = false;
+
+    for line in s.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        match directive(line) {
+            Some((name, value)) => match name.as_str() {
+                "title" | "t" => song.title = value,
+                "subtitle" | "st" => song.subtitles.extend(value),
+                "artist" => song.artists.extend(value),
+                "chorus" => {
+                    song.close(&mut current);
+                    if let Some(chorus) = song.sections.iter().rev().find(|section| section.kind == SectionKind::Chorus).cloned() {
+                        song.sections.push(chorus);
+                    }
+                },
+                name if start(name).is_some() => {
+                    song.close(&mut current);
+                    current = Some(Section { kind: start(name).unwrap(), label: value, lines: vec![] });
+                    environment = true;
+                },
+                name if end(name) => {
+                    song.close(&mut current);
+                    environment = false;
+                },
+                _ => {},
+            },
+            None if line.is_empty() => {
+                if !environment {
+                    song.close(&mut current);
+                }
+            },
+            None => {
+                current
+                    .get_or_insert_with(|| Section { kind: SectionKind::Verse, label: None, lines: vec![] })
+                    .lines
+                    .push(line.to_owned());
+            },
+        }
+    }
+    song.close(&mut current);
+    song
+}
+
+pub fn render(title: &str, parts: &[Vec<String>]) -> String {
+    let mut acc = vec![format!("{{title: {title}}}")];
+    let mut choruses: Vec<&Vec<String>> = vec![];
+    for part in parts {
+        acc.push(String::new());
+        if choruses.contains(&part) {
+            acc.push("{chorus}".to_owned());
+        }
+        else if parts.iter().filter(|other| *other == part).count() > 1 {
+            choruses.push(part);
+            acc.push("{start_of_chorus}".to_owned());
+            acc.extend(part.iter().cloned());
+            acc.push("{end_of_chorus}".to_owned());
+        }
+        else {
+            acc.extend(part.iter().cloned());
+        }
+    }
+    acc.push(String::new());
+    acc.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, render, strip_chords, SectionKind};
+
+    const SONG: &str = "# Traditional
+{title: Twinkle Twinkle}
+{st: Little Star}
+{artist: Jane Taylor}
+
+[C]Twinkle, twinkle, [F]little [C]star
+How I wonder what you are
+
+{start_of_chorus: Refrain}
+Up above the world so high
+
+Like a diamond in the sky
+{end_of_chorus}
+
+{start_of_tab}
+e|---0---|
+{end_of_tab}
+
+When the blazing sun is gone
+{chorus}
+";
+
+    #[test]
+    fn parse_directives_and_sections() {
+        let song = parse(SONG);
+        assert_eq!(song.title, Some("Twinkle Twinkle".to_owned()));
+        assert_eq!(song.subtitles, vec!["Little Star".to_owned()]);
+        assert_eq!(song.artists, vec!["Jane Taylor".to_owned()]);
+        assert_eq!(
+            song.sections.iter().map(|section| section.kind.clone()).collect::<Vec<_>>(),
+            vec![SectionKind::Verse, SectionKind::Chorus, SectionKind::Tab, SectionKind::Verse, SectionKind::Chorus],
+        );
+        assert_eq!(song.sections[1].label, Some("Refrain".to_owned()));
+        assert_eq!(song.sections[1].lines.len(), 2);
+
+        let parts = song.parts(true);
+        assert_eq!(parts.len(), 4);
+        assert_eq!(parts[0][0], "[C]Twinkle, twinkle, [F]little [C]star");
+        assert_eq!(parts[1], parts[3]);
+        assert_eq!(song.parts(false)[0][0], "Twinkle, twinkle, little star");
+    }
+
+    #[test]
+    fn render_marks_repeated_parts_as_chorus() {
+        let parts = parse(SONG).parts(true);
+        let text = render("Twinkle Twinkle", &parts);
+        assert_eq!(text.matches("{start_of_chorus}").count(), 1);
+        assert_eq!(text.matches("{chorus}").count(), 1);
+
+        let song = parse(&text);
+        assert_eq!(song.title, Some("Twinkle Twinkle".to_owned()));
+        assert_eq!(song.parts(true), parts);
+    }
+
+    #[test]
+    fn test_strip_chords() {
+        assert_eq!(strip_chords("[Am]Hallo [G]allemaal"), "Hallo allemaal");
+        assert_eq!(strip_chords("[C]"), "");
+    }
+}
@@ -0,0 +1,173 @@
+/*!
+ Frontmatter of Markdown files: YAML between `---` lines, TOML between `+++` lines or a JSON object.
+
+ Frontmatter starts at the first line that is not empty. JSON frontmatter ends with the brace
+ that closes the object, the other styles with the next line with the same delimiter.
+ Frontmatter that is not closed runs to the end of the text.
+ A block starting with `{` that does not parse as a JSON object is not frontmatter but text.
+ */
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Frontmatter {
+    #[default]
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl Frontmatter {
+    pub fn delimiter(&self) -> Option<&'static str> {
+        match self {
+            Frontmatter::Yaml => Some("---"),
+            Frontmatter::Toml => Some("+++"),
+            Frontmatter::Json => None,
+        }
+    }
+
+    /// Style of frontmatter that may start with this line, [`split`] only accepts JSON that parses as an object
+    pub fn start(line: &str) -> Option<Self> {
+        match line.trim() {
+            "---" => Some(Frontmatter::Yaml),
+            "+++" => Some(Frontmatter::Toml),
+            line if line.starts_with('{') => Some(Frontmatter::Json),
+            _ => None,
+        }
+    }
+
+    /// Style of the frontmatter of a text, if any
+    pub fn of(text: &str) -> Option<Self> {
+        split(text).0.map(|(style, _)| style)
+    }
+
+    /// Frontmatter block with serialized metadata, ending in a line break
+    pub fn wrap(&self, metadata: &str) -> String {
+        let metadata = metadata.trim_end();
+        match self.delimiter() {
+            Some(delimiter) => format!("{delimiter}\n{metadata}\n{delimiter}\n"),
+            None => format!("{metadata}\n"),
+        }
+    }
+}
+
+/// Finds the end of frontmatter, one line at a time
+#[derive(Debug, Default)]
+pub struct Scanner {
+    style: Option<Frontmatter>,
+    depth: usize,
+    string: bool,
+    escape: bool,
+}
+
+impl Scanner {
+    /// Style of the frontmatter seen so far
+    pub fn style(&self) -> Option<Frontmatter> {
+        self.style
+    }
+
+    /// Adds a line, returns whether the frontmatter continues after it.
+    /// The first line that is not empty and does not start frontmatter ends the scan, it is not frontmatter.
+    pub fn line(&mut self, line: &str) -> bool {
+        match self.style {
+            None if line.trim().is_empty() => true,
+            None => {
+                self.style = Frontmatter::start(line);
+                match self.style {
+                    Some(Frontmatter::Json) => self.json(line),
+                    Some(_) => true,
+                    None => false,
+                }
+            },
+            Some(Frontmatter::Json) => self.json(line),
+            Some(style) => Some(line.trim()) != style.delimiter(),
+        }
+    }
+
+    /// Counts braces outside strings, returns whether the object is still open
+    fn json(&mut self, line: &str) -> bool {
+        for c in line.chars() {
+            match c {
+                _ if self.escape => self.escape = false,
+                '\\' if self.string => self.escape = true,
+                '"' => self.string = !self.string,
+                '{' if !self.string => self.depth += 1,
+                '}' if !self.string => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.depth == 0 {
+                        return false;
+                    }
+                },
+                _ => {},
+            }
+        }
+        true
+    }
+}
+
+/// Frontmatter without delimiters and its style, and the text after it
+pub fn split(text: &str) -> (Option<(Frontmatter, &str)>, &str) {
+    let mut scanner = Scanner::default();
+    let mut start = None;
+    let mut offset = 0;
+    let mut end = text.len();
+    for line in text.split_inclusive('\n') {
+        let more = scanner.line(line);
+        if start.is_none() && scanner.style().is_some() {
+            start = Some(offset);
+        }
+        offset += line.len();
+        if !more {
+            end = offset;
+            break;
+        }
+    }
+
+    match (scanner.style(), start) {
+        (Some(style), Some(start)) => {
+            let block = &text[start..end];
+            let content = match style.delimiter() {
+                Some(delimiter) => {
+                    let inner = block.split_once('\n').map(|(_, rest)| rest).unwrap_or_default();
+                    let inner = inner.trim_end();
+                    inner.strip_suffix(delimiter).unwrap_or(inner)
+                },
+                None => block.trim(),
+            };
+            if style == Frontmatter::Json && serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(content).is_err() {
+                return (None, text);
+            }
+            (Some((style, content)), &text[end..])
+        },
+        _ => (None, text),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{split, Frontmatter};
+
+    #[test]
+    fn styles() {
+        assert_eq!(split("\n---\ntitle: Ja\n---\n\nRegel\n"), (Some((Frontmatter::Yaml, "title: Ja\n")), "\nRegel\n"));
+        assert_eq!(split("+++\ntitle = \"Ja\"\n\nhash = \"1\"\n+++\nRegel"), (Some((Frontmatter::Toml, "title = \"Ja\"\n\nhash = \"1\"\n")), "Regel"));
+        assert_eq!(split("{\n  \"title\": \"} {\\\"\",\n  \"x\": {}\n}\n\nRegel"), (Some((Frontmatter::Json, "{\n  \"title\": \"} {\\\"\",\n  \"x\": {}\n}")), "\nRegel"));
+        assert_eq!(split("Regel\n---\n"), (None, "Regel\n---\n"));
+        assert_eq!(split("---\ntitle: Ja"), (Some((Frontmatter::Yaml, "title: Ja")), ""));
+    }
+
+    #[test]
+    fn json_must_be_an_object() {
+        assert_eq!(split("{Refrein}\nRegel\n"), (None, "{Refrein}\nRegel\n"));
+        assert_eq!(split("{ \"title\": \"Ja\"\nRegel\n"), (None, "{ \"title\": \"Ja\"\nRegel\n"));
+        assert_eq!(Frontmatter::of("{ \"title\": \"Ja\" }\nRegel"), Some(Frontmatter::Json));
+    }
+
+    #[test]
+    fn wrap_and_detect() {
+        for style in [Frontmatter::Yaml, Frontmatter::Toml, Frontmatter::Json] {
+            let metadata = if style == Frontmatter::Json { "{ \"title\": \"Ja\" }" } else { "title: Ja\n" };
+            let text = format!("{}\nRegel", style.wrap(metadata));
+            assert_eq!(Frontmatter::of(&text), Some(style));
+            assert_eq!(split(&text).0.unwrap().1.trim(), metadata.trim());
+        }
+    }
+}
@@ -1,75 +1,34 @@
-use lazy_static::lazy_static;
-use regex::Regex;
-
-mod from_async_reader;
-mod from_reader;
-mod st;
-pub use st::to_parts_async;
-pub use from_async_reader::from_async_reader;
-pub use from_reader::parts_from_reader;
-
-const DOUBLE_LINE: &str = r"\n\s*\n";
-
-lazy_static! {
-    static ref DOUBLE_LINE_REGEX: Regex = DOUBLE_LINE.parse().unwrap();
-}
+pub mod chordpro;
+pub mod frontmatter;
+pub mod lrc;
+pub mod markup;
+pub mod normalize;
+mod parser;
+pub mod slides;
+pub use frontmatter::Frontmatter;
+pub use parser::{from_async_reader, parts_from_reader, to_parts_async, trim, Parser, Parts};
 
 pub struct Markdown {
     pub frontmatter: Option<String>,
+    pub style: Frontmatter,
     pub parts: Vec<Vec<String>>,
 }
 
+/// Markdown with YAML, TOML or JSON frontmatter, see [`frontmatter`]
 impl From<String> for Markdown {
     fn from(s: String) -> Self {
-        parse_markdown(s, "---")
-    }
-}
-
-fn parse_markdown(text: String, yaml_separator: &str) -> Markdown {
-    let parts = to_parts(text);
-    if 
-        !parts.is_empty()
-        && !parts[0].is_empty()
-        && parts[0][0] == *yaml_separator
-    {
-        Markdown {
-            frontmatter: Some(
-                parts[0]
-                .clone()
-                .into_iter()
-                .filter(|s| s != yaml_separator)
-                .collect::<Vec<_>>()
-                .join("\n")
-            ),
-            parts: parts[1..].to_vec(),
-        }
-    }
-    else {
+        let (frontmatter, body) = frontmatter::split(&s);
         Markdown {
-            frontmatter: None,
-            parts
+            style: frontmatter.map(|(style, _)| style).unwrap_or_default(),
+            frontmatter: frontmatter.map(|(_, frontmatter)| frontmatter.trim_end().to_owned()),
+            parts: to_parts(body.to_owned()),
         }
     }
 }
 
-fn trim_end(s: &str) -> &str {
-    s.trim_end()
-}
-
-fn to_lines(s: &str) -> Vec<String> {
-    s.split('\n')
-        .map(trim_end)
-        .filter(|&s| !s.is_empty())
-        .map(|s| s.to_string())
-        .collect()
-}
-
+/// Parts of a text, see [`Parser`] for the rules
 pub fn to_parts(s: String) -> Vec<Vec<String>> {
-    DOUBLE_LINE_REGEX
-    .split(&s)
-    .map(to_lines)
-    .filter(|p| !p.is_empty())
-    .collect()
+    parser::parse(&s)
 }
 
 pub fn to_text(parts: &[Vec<String>]) -> String {
@@ -87,18 +46,8 @@ mod test {
 
     #[test]
     fn test_trim() {
-        let test = "\rHallo allema  \t";
-        assert_eq!(super::trim_end(test), "\rHallo allema");
-    }
-
-    #[test]
-    fn test_to_lines() {
-        let test = "Hallo\nAllemaal\r\nWat fijn  \n\r\n";
-        let result = super::to_lines(test);
-        assert_eq!(result.len(), 3);
-        assert_eq!(result[0], "Hallo");
-        assert_eq!(result[1], "Allemaal");
-        assert_eq!(result[2], "Wat fijn");
+        let test = "\r  Hallo allema  \t".to_owned();
+        assert_eq!(super::to_parts(test), vec![vec!["  Hallo allema"]]);
     }
 
     #[test]
@@ -117,7 +66,7 @@ mod test {
     #[test]
     fn test_parse_markdown() {
         let test = "---\nyaml: is_fine\n---\n\nAllemaal\r\n\nWat fijn  \n\r\n".to_owned();
-        let result = super::parse_markdown(test, "---");
+        let result = super::Markdown::from(test);
         assert_eq!(result.parts, vec![vec!["Allemaal"], vec!["Wat fijn"]]);
         assert_eq!(result.frontmatter, Some("yaml: is_fine".to_owned()))
     }
@@ -125,10 +74,19 @@ mod test {
     #[test]
     fn test_parse_markdown_no_content() {
         let test = "---\nyaml: is_fine\n---".to_owned();
-        let result = super::parse_markdown(test, "---");
+        let result = super::Markdown::from(test);
         assert!(result.parts.is_empty());
         assert_eq!(result.frontmatter, Some("yaml: is_fine".to_owned()))
     }
 
+    #[test]
+    fn test_parse_markdown_toml() {
+        let test = "+++\ntoml = \"is fine\"\n+++\nAllemaal".to_owned();
+        let result = super::Markdown::from(test);
+        assert_eq!(result.style, super::Frontmatter::Toml);
+        assert_eq!(result.parts, vec![vec!["Allemaal"]]);
+        assert_eq!(result.frontmatter, Some("toml = \"is fine\"".to_owned()))
+    }
+
 
 }
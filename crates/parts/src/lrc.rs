@@ -0,0 +1,247 @@
+/*!
+ Timed lyrics and the LRC format of karaoke players.
+
+ Lyrics keep the time of their lines apart from the text, so plain consumers never see time tags.
+ [`split`] takes timed lines apart in text and times, [`join`] puts them together again.
+
+ An LRC file has a time tag in front of every line and header tags like `[ti:Title]`.
+ Parts are separated by empty lines or by time tags without text.
+ A line with several time tags is repeated at every time and word times like `<01:02.50>` are dropped.
+ */
+
+use std::time::Duration;
+
+pub const EXTENSION: &str = "lrc";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedLine {
+    pub time: Option<Duration>,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Lrc {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub parts: Vec<Vec<TimedLine>>,
+}
+
+/// Time of a tag like `01:02.50`, `01:02:50` or `01:02`
+pub fn parse_time(tag: &str) -> Option<Duration> {
+    let (minutes, rest) = tag.split_once(':')?;
+    let (seconds, fraction) = rest.split_once(['.', ':']).unwrap_or((rest, ""));
+    if minutes.is_empty() || seconds.is_empty() || !(minutes.chars().chain(seconds.chars()).chain(fraction.chars()).all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let millis = match fraction.len() {
+        0 => 0,
+        1 => fraction.parse::<u64>().ok()? * 100,
+        2 => fraction.parse::<u64>().ok()? * 10,
+        _ => fraction[..3].parse::<u64>().ok()?,
+    };
+    Some(Duration::from_millis((minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?) * 1000 + millis))
+}
+
+/// Time as in a tag like `01:02.50`, with milliseconds like `01:02.505` if the time has them
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    let seconds = format!("{:02}:{:02}", millis / 60_000, millis / 1000 % 60);
+    match millis % 1000 {
+        fraction if fraction % 10 == 0 => format!("{seconds}.{:02}", fraction / 10),
+        fraction => format!("{seconds}.{fraction:03}"),
+    }
+}
+
+/// Leading time tags of a line and the text after them
+fn split_tags(line: &str) -> (Vec<Duration>, &str) {
+    let mut times = vec![];
+    let mut rest = line.trim();
+    while let Some((tag, after)) = rest.strip_prefix('[').and_then(|inner| inner.split_once(']')) {
+        match parse_time(tag) {
+            Some(time) => {
+                times.push(time);
+                rest = after;
+            },
+            None => break,
+        }
+    }
+    (times, rest)
+}
+
+fn without_word_times(text: &str) -> String {
+    let mut acc = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        match rest[start..].find('>').filter(|end| parse_time(&rest[start + 1..start + end]).is_some()) {
+            Some(end) => {
+                acc.push_str(&rest[..start]);
+                rest = &rest[start + end + 1..];
+            },
+            None => {
+                acc.push_str(&rest[..=start]);
+                rest = &rest[start + 1..];
+            },
+        }
+    }
+    acc.push_str(rest);
+    acc.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Text and times of timed lines
+pub fn split(parts: &[Vec<TimedLine>]) -> (Vec<Vec<String>>, Vec<Vec<Option<Duration>>>) {
+    let text = parts.iter().map(|part| part.iter().map(|line| line.text.clone()).collect()).collect();
+    let times = parts.iter().map(|part| part.iter().map(|line| line.time).collect()).collect();
+    (text, times)
+}
+
+/// Lines with their time, lines without a time in `times` have none
+pub fn join(parts: &[Vec<String>], times: &[Vec<Option<Duration>>]) -> Vec<Vec<TimedLine>> {
+    parts
+        .iter()
+        .enumerate()
+        .map(|(p, part)|
+            part
+            .iter()
+            .enumerate()
+            .map(|(l, text)| TimedLine { time: times.get(p).and_then(|part| part.get(l)).copied().flatten(), text: text.clone() })
+            .collect()
+        )
+        .collect()
+}
+
+fn header(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.trim().strip_prefix('[')?.strip_suffix(']')?.split_once(':')?;
+    if key.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some((key.to_lowercase(), value.trim().to_owned()))
+    }
+    else {
+        None
+    }
+}
+
+pub fn parse(s: &str) -> Lrc {
+    let mut lrc = Lrc::default();
+    let mut offset = 0i64;
+    let mut lines: Vec<(bool, Duration, String)> = vec![];
+    let mut repeated = false;
+    let mut part_break = false;
+
+    for line in s.lines() {
+        if line.trim().is_empty() {
+            part_break = true;
+            continue;
+        }
+        if let Some((key, value)) = header(line) {
+            match key.as_str() {
+                "ti" => lrc.title = Some(value),
+                "ar" => lrc.artist = Some(value),
+                "offset" => offset = value.parse().unwrap_or_default(),
+                _ => {},
+            }
+            continue;
+        }
+        let (times, text) = split_tags(line);
+        let text = without_word_times(text);
+        repeated |= times.len() > 1;
+        for time in times {
+            lines.push((part_break || text.is_empty(), time, text.clone()));
+            part_break = false;
+        }
+    }
+
+    if repeated {
+        lines.sort_by_key(|(_, time, _)| *time);
+    }
+    let shift = |time: Duration| {
+        let millis = time.as_millis() as i64 - offset;
+        Duration::from_millis(millis.max(0) as u64)
+    };
+    let mut part = vec![];
+    for (part_break, time, text) in lines {
+        if part_break && !part.is_empty() {
+            lrc.parts.push(std::mem::take(&mut part));
+        }
+        if !text.is_empty() {
+            part.push(TimedLine { time: Some(shift(time)), text });
+        }
+    }
+    if !part.is_empty() {
+        lrc.parts.push(part);
+    }
+    lrc
+}
+
+pub fn render(title: &str, parts: &[Vec<TimedLine>]) -> String {
+    let mut acc = vec![format!("[ti:{title}]")];
+    for part in parts {
+        acc.push(String::new());
+        acc.extend(
+            part
+            .iter()
+            .map(|line| match line.time {
+                Some(time) => format!("[{}]{}", format_time(time), line.text),
+                None => line.text.clone(),
+            })
+        );
+    }
+    acc.push(String::new());
+    acc.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use super::{format_time, join, parse, render, split, TimedLine};
+
+    const LRC: &str = "[ti:Twinkle Twinkle]
+[ar:Jane Taylor]
+[offset:500]
+[00:10.50]Twinkle, twinkle, <00:11.00>little star
+[00:14.00]How I wonder what you are
+[00:18.00]
+[00:20.00][00:40.00]Up above the world so high
+[00:30.00]When the blazing sun is gone
+";
+
+    fn millis(millis: u64) -> Option<Duration> {
+        Some(Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn parse_lrc() {
+        let lrc = parse(LRC);
+        assert_eq!(lrc.title, Some("Twinkle Twinkle".to_owned()));
+        assert_eq!(lrc.artist, Some("Jane Taylor".to_owned()));
+        assert_eq!(lrc.parts.len(), 2);
+        assert_eq!(lrc.parts[0][0], TimedLine { time: millis(10_000), text: "Twinkle, twinkle, little star".to_owned() });
+        assert_eq!(
+            lrc.parts[1].iter().map(|line| line.time).collect::<Vec<_>>(),
+            vec![millis(19_500), millis(29_500), millis(39_500)],
+        );
+    }
+
+    #[test]
+    fn split_and_join() {
+        let parts = parse(LRC).parts;
+        let (text, times) = split(&parts);
+        assert_eq!(text[0][1], "How I wonder what you are");
+        assert_eq!(times[0][1], millis(13_500));
+        assert_eq!(join(&text, &times), parts);
+
+        let plain = vec![vec!["[C]Twinkle".to_owned(), "Little star".to_owned()]];
+        assert_eq!(join(&plain, &[vec![millis(1_000)]])[0], vec![
+            TimedLine { time: millis(1_000), text: "[C]Twinkle".to_owned() },
+            TimedLine { time: None, text: "Little star".to_owned() },
+        ]);
+    }
+
+    #[test]
+    fn render_and_parse_again() {
+        let parts = parse(LRC).parts;
+        let lrc = parse(&render("Twinkle Twinkle", &parts));
+        assert_eq!(lrc.title, Some("Twinkle Twinkle".to_owned()));
+        assert_eq!(lrc.parts, parts);
+        assert_eq!(format_time(Duration::from_millis(3_723_450)), "62:03.45");
+        assert_eq!(format_time(Duration::from_millis(1_505)), "00:01.505");
+    }
+}
@@ -0,0 +1,175 @@
+/*!
+ Inline markup of lyric lines.
+
+ Lines keep their indentation and a small subset of Markdown: `*emphasis*`, `**strong**`
+ and a speaker marker, emphasis or strong ending in a colon at the start of a line, like `*All:*`.
+ A `*` without a matching closing one is text, so any line parses.
+
+ A [`Line`] renders back to exactly the line it was parsed from,
+ [`strip`] gives the plain text for consumers without markup.
+ */
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Columns of a tab in [`Line::indent_width`]
+pub const TAB: usize = 4;
+const EMPHASIS: &str = "*";
+const STRONG: &str = "**";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Span {
+    Text(String),
+    Emphasis(String),
+    Strong(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Line {
+    /// Leading whitespace
+    pub indent: String,
+    /// Marker like `*All:*`, its text includes the colon
+    pub speaker: Option<Span>,
+    pub spans: Vec<Span>,
+}
+
+impl Span {
+    pub fn text(&self) -> &str {
+        match self {
+            Span::Text(text) | Span::Emphasis(text) | Span::Strong(text) => text,
+        }
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Span::Text(text) => write!(f, "{text}"),
+            Span::Emphasis(text) => write!(f, "{EMPHASIS}{text}{EMPHASIS}"),
+            Span::Strong(text) => write!(f, "{STRONG}{text}{STRONG}"),
+        }
+    }
+}
+
+impl Line {
+    /// Name of the speaker without the colon
+    pub fn speaker_name(&self) -> Option<&str> {
+        self.speaker.as_ref().map(|speaker| speaker.text().trim_end_matches(':'))
+    }
+
+    /// Columns of the indentation
+    pub fn indent_width(&self) -> usize {
+        self.indent.chars().map(|c| if c == '\t' { TAB } else { 1 }).sum()
+    }
+
+    /// Text without markup, indentation included
+    pub fn plain(&self) -> String {
+        let mut plain = self.indent.clone();
+        plain.extend(self.speaker.iter().chain(self.spans.iter()).map(Span::text));
+        plain
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{}", self.indent)?;
+        for span in self.speaker.iter().chain(self.spans.iter()) {
+            write!(f, "{span}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Text between delimiters, it may not be empty or start or end with whitespace
+fn delimited<'a>(s: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let inner = s.strip_prefix(delimiter)?;
+    let end = inner.find(delimiter)?;
+    let text = &inner[..end];
+    (!text.is_empty() && text.trim() == text && !(delimiter == EMPHASIS && inner[end..].starts_with(STRONG)))
+        .then(|| (text, &inner[end + delimiter.len()..]))
+}
+
+fn push_text(spans: &mut Vec<Span>, text: &str) {
+    match spans.last_mut() {
+        _ if text.is_empty() => {},
+        Some(Span::Text(last)) => last.push_str(text),
+        _ => spans.push(Span::Text(text.to_owned())),
+    }
+}
+
+fn spans(s: &str) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut rest = s;
+    while let Some(start) = rest.find(EMPHASIS) {
+        push_text(&mut spans, &rest[..start]);
+        rest = &rest[start..];
+        if let Some((text, after)) = delimited(rest, STRONG) {
+            spans.push(Span::Strong(text.to_owned()));
+            rest = after;
+        }
+        else if let Some((text, after)) = delimited(rest, EMPHASIS) {
+            spans.push(Span::Emphasis(text.to_owned()));
+            rest = after;
+        }
+        else {
+            let skip = if rest.starts_with(STRONG) { STRONG.len() } else { EMPHASIS.len() };
+            push_text(&mut spans, &rest[..skip]);
+            rest = &rest[skip..];
+        }
+    }
+    push_text(&mut spans, rest);
+    spans
+}
+
+pub fn parse(line: &str) -> Line {
+    let text = line.trim_start();
+    let mut spans = spans(text);
+    let speaker = match spans.first() {
+        Some(Span::Emphasis(text) | Span::Strong(text)) if text.ends_with(':') => Some(spans.remove(0)),
+        _ => None,
+    };
+    Line { indent: line[..line.len() - text.len()].to_owned(), speaker, spans }
+}
+
+/// Line without markup, indentation included
+pub fn strip(line: &str) -> String {
+    parse(line).plain()
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use super::{parse, strip, Line, Span};
+
+    #[test]
+    fn speaker_and_spans() {
+        let line = parse("    *All:* Heer, **ontferm** U *over* ons");
+        assert_eq!(line.indent, "    ");
+        assert_eq!(parse("\t  Amen").indent_width(), 6);
+        assert_eq!(line.speaker_name(), Some("All"));
+        assert_eq!(
+            line.spans,
+            vec![
+                Span::Text(" Heer, ".to_owned()),
+                Span::Strong("ontferm".to_owned()),
+                Span::Text(" U ".to_owned()),
+                Span::Emphasis("over".to_owned()),
+                Span::Text(" ons".to_owned()),
+            ]
+        );
+        assert_eq!(strip("    *All:* Heer, **ontferm** U *over* ons"), "    All: Heer, ontferm U over ons");
+    }
+
+    #[test]
+    fn unmatched_is_text() {
+        assert_eq!(parse("2 * 3 = 6 *"), Line { indent: String::new(), speaker: None, spans: vec![Span::Text("2 * 3 = 6 *".to_owned())] });
+        assert_eq!(parse("**Voorganger:** Amen").speaker_name(), Some("Voorganger"));
+        assert_eq!(parse("Eerst *ik:* dan").speaker, None);
+    }
+
+    proptest! {
+        #[test]
+        fn renders_faithfully(line in "[ \ta*:]{0,30}") {
+            prop_assert_eq!(parse(&line).to_string(), line);
+        }
+    }
+}
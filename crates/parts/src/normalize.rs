@@ -0,0 +1,235 @@
+/*!
+ Normalisation of lyric text from Word documents and websites.
+
+ A [`Normalizer`] applies, in order and each one optional:
+ - Unicode NFC, so `é` is one character whatever the source
+ - whitespace cleanup: zero-width characters and soft hyphens are left out, other spaces like
+   the non-breaking space become a plain space and runs of spaces after the indentation become one
+ - a [`Quotes`] policy, so `'t Knaapje` and `’t Knaapje` sort and search the same
+ - a [`Dashes`] policy
+ - capitalisation of the first letter of every line, lines starting with an elision like `'t` are left alone
+
+ Line endings `\r\n` and a lone `\r` become `\n`, lines that end up empty are left out like [`crate::to_parts`] does.
+ */
+
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+
+const ZERO_WIDTH: &[char] = &['\u{00AD}', '\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+const SINGLE_QUOTES: &[char] = &['\u{2018}', '\u{2019}', '\u{201A}', '\u{201B}', '\u{2032}', '`', '\u{00B4}'];
+const DOUBLE_QUOTES: &[char] = &['\u{201C}', '\u{201D}', '\u{201E}', '\u{201F}', '\u{2033}', '\u{00AB}', '\u{00BB}'];
+const DASHES: &[char] = &['\u{2010}', '\u{2011}', '\u{2012}', '\u{2013}', '\u{2014}', '\u{2015}', '\u{2212}'];
+const EN_DASH: char = '\u{2013}';
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Quotes {
+    Keep,
+    /// Typographic quotes become `'` and `"`
+    #[default]
+    Straight,
+    /// Double quotes become `“` or `”` by position, single quotes become the apostrophe `’`,
+    /// as elisions like `’t` are far more common in lyrics than single quoted text
+    Curly,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dashes {
+    #[default]
+    Keep,
+    /// Every kind of dash becomes `-`
+    Hyphen,
+    /// A hyphen or dash between spaces becomes `–`
+    EnDash,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Normalizer {
+    pub nfc: bool,
+    pub whitespace: bool,
+    pub quotes: Quotes,
+    pub dashes: Dashes,
+    pub capitalize: bool,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self { nfc: true, whitespace: true, quotes: Quotes::default(), dashes: Dashes::default(), capitalize: false }
+    }
+}
+
+impl Quotes {
+    pub const ALL: &'static [Quotes] = &[Quotes::Keep, Quotes::Straight, Quotes::Curly];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quotes::Keep => "keep",
+            Quotes::Straight => "straight",
+            Quotes::Curly => "curly",
+        }
+    }
+}
+
+impl Dashes {
+    pub const ALL: &'static [Dashes] = &[Dashes::Keep, Dashes::Hyphen, Dashes::EnDash];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dashes::Keep => "keep",
+            Dashes::Hyphen => "hyphen",
+            Dashes::EnDash => "en-dash",
+        }
+    }
+}
+
+macro_rules! policy {
+    ($policy:ident) => {
+        impl Display for $policy {
+            fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+                write!(f, "{}", self.name())
+            }
+        }
+
+        impl FromStr for $policy {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $policy::ALL
+                    .iter()
+                    .find(|policy| policy.name() == s.trim().to_lowercase())
+                    .copied()
+                    .ok_or_else(|| format!("unknown policy {s}, expected one of {}", $policy::ALL.iter().map($policy::name).collect::<Vec<_>>().join(", ")))
+            }
+        }
+    };
+}
+
+policy!(Quotes);
+policy!(Dashes);
+
+fn clean_whitespace(line: &str) -> String {
+    let line = line.replace(ZERO_WIDTH, "").replace(|c: char| c != '\t' && c.is_whitespace(), " ");
+    let text = line.trim_start();
+    let mut cleaned = line[..line.len() - text.len()].to_owned();
+    cleaned.push_str(&text.split(' ').filter(|word| !word.is_empty()).collect::<Vec<_>>().join(" "));
+    cleaned
+}
+
+fn curly_quotes(line: &str) -> String {
+    let mut curly = String::with_capacity(line.len());
+    let mut previous = None;
+    for c in line.chars() {
+        curly.push(match c {
+            '"' if previous.map(|p: char| p.is_whitespace() || "([{*".contains(p)).unwrap_or(true) => '\u{201C}',
+            '"' => '\u{201D}',
+            '\'' => '\u{2019}',
+            c => c,
+        });
+        previous = Some(c);
+    }
+    curly
+}
+
+fn quotes(line: String, quotes: Quotes) -> String {
+    match quotes {
+        Quotes::Keep => line,
+        Quotes::Straight => line.replace(SINGLE_QUOTES, "'").replace(DOUBLE_QUOTES, "\""),
+        Quotes::Curly => curly_quotes(&line.replace(SINGLE_QUOTES, "'").replace(DOUBLE_QUOTES, "\"")),
+    }
+}
+
+fn dashes(line: String, dashes: Dashes) -> String {
+    match dashes {
+        Dashes::Keep => line,
+        Dashes::Hyphen => line.replace(DASHES, "-"),
+        Dashes::EnDash => {
+            let text = line.trim_start();
+            let indent = &line[..line.len() - text.len()];
+            let text = text
+                .split(' ')
+                .map(|word| if word == "-" || (word.chars().count() == 1 && word.contains(DASHES)) { EN_DASH.to_string() } else { word.to_owned() })
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("{indent}{text}")
+        },
+    }
+}
+
+/// Uppercase first letter, after indentation and markup
+fn capitalize(line: String) -> String {
+    match line.char_indices().find(|(_, c)| !c.is_whitespace() && *c != '*') {
+        Some((i, c)) if c.is_lowercase() => {
+            let mut capitalized = line[..i].to_owned();
+            capitalized.extend(c.to_uppercase());
+            capitalized.push_str(&line[i + c.len_utf8()..]);
+            capitalized
+        },
+        _ => line,
+    }
+}
+
+impl Normalizer {
+    /// Normalizer that changes nothing
+    pub fn none() -> Self {
+        Self { nfc: false, whitespace: false, quotes: Quotes::Keep, dashes: Dashes::Keep, capitalize: false }
+    }
+
+    pub fn line(&self, line: &str) -> String {
+        let line = if self.nfc { line.nfc().collect() } else { line.to_owned() };
+        let line = if self.whitespace { clean_whitespace(&line) } else { line };
+        let line = dashes(quotes(line, self.quotes), self.dashes);
+        if self.capitalize { capitalize(line) } else { line }
+    }
+
+    /// Title, normalized like a line without capitalisation and trimmed
+    pub fn title(&self, title: &str) -> String {
+        Normalizer { capitalize: false, ..self.clone() }.line(title).trim().to_owned()
+    }
+
+    pub fn text(&self, text: &str) -> String {
+        text
+            .replace("\r\n", "\n")
+            .replace('\r', "\n")
+            .split('\n')
+            .map(|line| self.line(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn parts(&self, parts: &[Vec<String>]) -> Vec<Vec<String>> {
+        crate::parser::parse(&self.text(&crate::to_text(parts)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Dashes, Normalizer, Quotes};
+
+    #[test]
+    fn default_pipeline() {
+        let normalizer = Normalizer::default();
+        assert_eq!(normalizer.line("\u{2019}t Kna\u{200B}apje\u{00A0}\u{00A0}zat  \u{201C}te\u{201D} dansen  "), "'t Knaapje zat \"te\" dansen");
+        assert_eq!(normalizer.line("  Cafe\u{0301}"), "  Caf\u{00E9}");
+        assert_eq!(normalizer.title(" \u{2018}t Knaapje "), "'t Knaapje");
+    }
+
+    #[test]
+    fn policies() {
+        let curly = Normalizer { quotes: Quotes::Curly, dashes: Dashes::EnDash, ..Normalizer::none() };
+        assert_eq!(curly.line("\"'t Is\" - zei hij \u{2014} zo"), "\u{201C}\u{2019}t Is\u{201D} \u{2013} zei hij \u{2013} zo");
+        let hyphen = Normalizer { dashes: Dashes::Hyphen, ..Normalizer::none() };
+        assert_eq!(hyphen.line("heen \u{2014} weer"), "heen - weer");
+        assert_eq!("en-dash".parse::<Dashes>().unwrap(), Dashes::EnDash);
+        assert!("fancy".parse::<Quotes>().is_err());
+    }
+
+    #[test]
+    fn capitalize_and_parts() {
+        let normalizer = Normalizer { capitalize: true, ..Normalizer::default() };
+        let parts = vec![vec!["  *all:* amen".to_owned(), "'t is \u{00E9}\u{00E9}n".to_owned(), "\u{00E9}\u{00E9}n\r\u{200B}".to_owned()]];
+        assert_eq!(
+            normalizer.parts(&parts),
+            vec![vec!["  *All:* amen".to_owned(), "'t is \u{00E9}\u{00E9}n".to_owned(), "\u{00C9}\u{00E9}n".to_owned()]]
+        );
+    }
+}
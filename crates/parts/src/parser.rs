@@ -0,0 +1,178 @@
+/*!
+ The one parser of parts, with front-ends for a string, a reader, an async reader and a stream of lines.
+ All front-ends feed lines to [`Parser`], so they agree on the result.
+ */
+
+use std::io::{BufRead, BufReader, Error, Read};
+use futures::io::{AsyncBufReadExt, AsyncRead, BufReader as AsyncBufReader};
+use futures::{pin_mut, Stream, StreamExt};
+
+/// Incremental parser, fed one line at a time
+///
+/// - lines end at `\n`, whitespace at the end of a line is trimmed, so `\r\n` endings are fine
+/// - leading spaces and tabs are kept as indentation, see [`markup`](crate::markup)
+/// - a line that is empty after trimming ends a part
+/// - empty parts are left out, so leading, trailing and repeated empty lines do not count
+///
+/// [`to_text`](crate::to_text) is the inverse for parts of non-empty lines as [`trim`] gives them.
+#[derive(Debug, Default)]
+pub struct Parser {
+    parts: Vec<Vec<String>>,
+    new_part: bool,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a line, only the trimmed text is copied
+    pub fn line(&mut self, line: &str) {
+        if line.trim().is_empty() {
+            self.new_part = true;
+        }
+        else {
+            let trimmed = trim(line);
+            match self.parts.last_mut() {
+                Some(part) if !self.new_part => part.push(trimmed),
+                _ => self.parts.push(vec![trimmed]),
+            }
+            self.new_part = false;
+        }
+    }
+
+    pub fn finish(self) -> Vec<Vec<String>> {
+        self.parts
+    }
+}
+
+/// Line without trailing whitespace, leading whitespace other than spaces and tabs
+/// is left out of the indentation
+pub fn trim(line: &str) -> String {
+    let line = line.trim_end();
+    let text = line.trim_start();
+    let mut trimmed = line[..line.len() - text.len()].replace(|c| c != ' ' && c != '\t', "");
+    trimmed.push_str(text);
+    trimmed
+}
+
+pub fn parse(s: &str) -> Vec<Vec<String>> {
+    let mut parser = Parser::new();
+    s.split('\n').for_each(|line| parser.line(line));
+    parser.finish()
+}
+
+pub struct Parts(Vec<Vec<String>>);
+
+impl From<Vec<Vec<String>>> for Parts {
+    fn from(value: Vec<Vec<String>>) -> Self {
+        Parts(value)
+    }
+}
+
+impl Parts {
+    pub fn parts(&self) -> Vec<Vec<String>> {
+        self.0.clone()
+    }
+}
+
+/// Parts of a reader, one line buffer is reused for all lines
+pub fn parts_from_reader<R>(r: R) -> Result<Parts, Error>
+where
+    R: Read,
+{
+    let mut reader = BufReader::new(r);
+    let mut parser = Parser::new();
+    let mut buffer = String::new();
+    while reader.read_line(&mut buffer)? > 0 {
+        parser.line(&buffer);
+        buffer.clear();
+    }
+    Ok(parser.finish().into())
+}
+
+/// Parts of an async reader, one line buffer is reused for all lines
+pub async fn from_async_reader<R>(r: R) -> Result<Vec<Vec<String>>, Error>
+where
+    R: AsyncRead,
+{
+    let reader = AsyncBufReader::new(r);
+    pin_mut!(reader);
+    let mut parser = Parser::new();
+    let mut buffer = String::new();
+    while reader.read_line(&mut buffer).await? > 0 {
+        parser.line(&buffer);
+        buffer.clear();
+    }
+    Ok(parser.finish())
+}
+
+/// Parts of a stream of lines
+pub async fn to_parts_async(mut s: impl Stream<Item=Result<String, Error>> + Unpin) -> Result<Vec<Vec<String>>, Error>
+{
+    let mut parser = Parser::new();
+    while let Some(line) = s.next().await {
+        parser.line(&line?);
+    }
+    Ok(parser.finish())
+}
+
+#[cfg(test)]
+mod test {
+    use futures::io::{AsyncBufReadExt, BufReader, Cursor};
+    use proptest::prelude::*;
+    use crate::to_text;
+    use super::{from_async_reader, parse, parts_from_reader, to_parts_async, trim};
+
+    const TEXT: &str = "\r  Hallo allemaal  \t\r\n  Wat fijn dat u er bent\t\n \t\n\n En dan ook nog\neen tweede couplet\r\n\n";
+
+    fn expected() -> Vec<Vec<String>> {
+        vec![
+            vec!["  Hallo allemaal".to_owned(), "  Wat fijn dat u er bent".to_owned()],
+            vec![" En dan ook nog".to_owned(), "een tweede couplet".to_owned()],
+        ]
+    }
+
+    #[tokio::test]
+    async fn front_ends_agree() {
+        assert_eq!(parse(TEXT), expected());
+        assert_eq!(parts_from_reader(TEXT.as_bytes()).unwrap().parts(), expected());
+        assert_eq!(from_async_reader(TEXT.as_bytes()).await.unwrap(), expected());
+        assert_eq!(to_parts_async(BufReader::new(Cursor::new(TEXT)).lines()).await.unwrap(), expected());
+    }
+
+    #[test]
+    fn indentation() {
+        assert_eq!(trim("\r\t  *All:* Amen \r"), "\t  *All:* Amen");
+        assert_eq!(parse("Heer\n    ontferm U\n"), vec![vec!["Heer".to_owned(), "    ontferm U".to_owned()]]);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(parse("").is_empty());
+        assert!(parse(" \n\t\r\n").is_empty());
+        assert!(parts_from_reader("".as_bytes()).unwrap().parts().is_empty());
+    }
+
+    /// Parts as [`parse`] gives them: indented, non-empty lines without line breaks in non-empty parts
+    fn normalized_parts() -> impl Strategy<Value = Vec<Vec<String>>> {
+        let line = "[ \t]{0,4}[^\\s](.*[^\\s])?".prop_filter("single line", |line: &String| !line.contains(['\n', '\r']) && trim(line) == *line);
+        prop::collection::vec(prop::collection::vec(line, 1..6), 0..6)
+    }
+
+    proptest! {
+        #[test]
+        fn to_text_round_trips(parts in normalized_parts()) {
+            let text = to_text(&parts);
+            prop_assert_eq!(parse(&text), parts.clone());
+            prop_assert_eq!(parts_from_reader(text.as_bytes()).unwrap().parts(), parts);
+        }
+
+        #[test]
+        fn parse_is_normalized(text in "[ a-z\t\r\n]{0,200}") {
+            let parts = parse(&text);
+            prop_assert_eq!(parse(&to_text(&parts)), parts.clone());
+            prop_assert_eq!(futures::executor::block_on(from_async_reader(text.as_bytes())).unwrap(), parts);
+        }
+    }
+}
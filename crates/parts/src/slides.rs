@@ -0,0 +1,119 @@
+/*!
+ Parts fitted on projector slides.
+
+ A line longer than fits is wrapped at spaces into lines of about equal length,
+ so a long line does not end in a single word. A word longer than fits keeps its own line.
+ A part that does not fit on one slide is spread over as few slides as possible with about
+ the same number of lines each, the lines of a wrapped line stay on the same slide.
+ */
+
+/// Most lines on a slide and most characters on a line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fit {
+    pub lines: usize,
+    pub chars: usize,
+}
+
+impl Default for Fit {
+    fn default() -> Self {
+        Self { lines: 6, chars: 40 }
+    }
+}
+
+fn greedy(words: &[&str], width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    for word in words {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Line wrapped in as few lines of at most `chars` characters as possible, with about equal lengths
+pub fn wrap(line: &str, chars: usize) -> Vec<String> {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let chars = chars.max(1);
+    let lines = greedy(&words, chars);
+    if lines.len() < 2 {
+        return lines;
+    }
+    let length = words.iter().map(|word| word.chars().count()).sum::<usize>() + words.len() - 1;
+    (length.div_ceil(lines.len())..chars)
+        .map(|width| greedy(&words, width))
+        .find(|balanced| balanced.len() == lines.len())
+        .unwrap_or(lines)
+}
+
+/// Part spread over slides
+pub fn slides(part: &[String], fit: &Fit) -> Vec<Vec<String>> {
+    let max = fit.lines.max(1);
+    let units =
+        part
+        .iter()
+        .flat_map(|line| wrap(line, fit.chars).chunks(max).map(<[String]>::to_vec).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let total = units.iter().map(Vec::len).sum::<usize>();
+    if total == 0 {
+        return vec![];
+    }
+    let target = total.div_ceil(total.div_ceil(max));
+
+    let mut slides = vec![];
+    let mut current: Vec<String> = vec![];
+    for unit in units {
+        if !current.is_empty() && (current.len() >= target || current.len() + unit.len() > max) {
+            slides.push(std::mem::take(&mut current));
+        }
+        current.extend(unit);
+    }
+    slides.push(current);
+    slides
+}
+
+#[cfg(test)]
+mod test {
+    use super::{slides, wrap, Fit};
+
+    fn lines(count: usize) -> Vec<String> {
+        (1..=count).map(|i| format!("Regel {i}")).collect()
+    }
+
+    #[test]
+    fn wrap_in_balanced_lines() {
+        assert_eq!(wrap("Daar bij die molen", 40), vec!["Daar bij die molen"]);
+        assert_eq!(
+            wrap("Zeg roodkapje waar ga je hene, zo alleen", 30),
+            vec!["Zeg roodkapje waar ga", "je hene, zo alleen"],
+        );
+        assert_eq!(wrap("Onafhankelijkheidsverklaring", 10), vec!["Onafhankelijkheidsverklaring"]);
+        assert!(wrap("  ", 10).is_empty());
+    }
+
+    #[test]
+    fn spread_over_slides() {
+        let fit = Fit { lines: 4, chars: 40 };
+        assert_eq!(slides(&lines(4), &fit), vec![lines(4)]);
+        assert_eq!(slides(&lines(5), &fit).iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 2]);
+        assert_eq!(slides(&lines(9), &fit).iter().map(Vec::len).collect::<Vec<_>>(), vec![3, 3, 3]);
+        assert!(slides(&[], &fit).is_empty());
+    }
+
+    #[test]
+    fn wrapped_lines_stay_together() {
+        let fit = Fit { lines: 3, chars: 20 };
+        let part = vec!["Kort".to_owned(), "Kort".to_owned(), "Een regel die te lang is voor een regel".to_owned()];
+        let slides = slides(&part, &fit);
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0], vec!["Kort", "Kort"]);
+        assert_eq!(slides[1], vec!["Een regel die", "te lang is", "voor een regel"]);
+    }
+}